@@ -0,0 +1,134 @@
+//! Alpha modes of textures and conversion of decoded texels between straight and premultiplied alpha.
+//!
+//! The alpha mode is stored in the [`ALPHA_PREMULTIPLIED`](DataFormatFlags::ALPHA_PREMULTIPLIED) flag of the basic
+//! data format descriptor. Textures without an alpha sample are opaque.
+
+use crate::{BasicDataFormatDescriptor, Channel, ColorModel, DataFormatDescriptorHeader, DataFormatFlags, Reader};
+
+/// How the alpha channel of a texture relates to its color channels
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AlphaMode {
+    /// The texture has no alpha channel
+    Opaque,
+    /// Color channels are independent of alpha
+    Straight,
+    /// Color channels are multiplied by alpha
+    Premultiplied,
+}
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// Alpha mode given by the basic data format descriptor, wherever it is among the descriptor blocks
+    ///
+    /// Returns `None` if there's no valid basic descriptor.
+    pub fn alpha_mode(&self) -> Option<AlphaMode> {
+        let dfd = self
+            .data_format_descriptors()
+            .find(|dfd| dfd.header == DataFormatDescriptorHeader::BASIC)?;
+        let dfd = BasicDataFormatDescriptor::parse(dfd.data).ok()?;
+        // BC7, ASTC and PVRTC blocks encode alpha along with color in a single sample
+        let has_alpha = matches!(
+            dfd.header.color_model,
+            Some(ColorModel::BC7 | ColorModel::ASTC | ColorModel::PVRTC | ColorModel::PVRTC2)
+        ) || dfd
+            .channels()
+            .any(|channel| matches!(channel, Channel::Alpha | Channel::Aaa | Channel::Rgba | Channel::Rrrg));
+        Some(if !has_alpha {
+            AlphaMode::Opaque
+        } else if dfd.header.flags.contains(DataFormatFlags::ALPHA_PREMULTIPLIED) {
+            AlphaMode::Premultiplied
+        } else {
+            AlphaMode::Straight
+        })
+    }
+}
+
+/// Multiply the color channels of decoded texels by their alpha
+///
+/// Values are multiplied as they are, so texels with a non-linear transfer function should be converted to linear
+/// first.
+pub fn premultiply_alpha(texels: &mut [[f32; 4]]) {
+    for texel in texels {
+        for channel in 0..3 {
+            texel[channel] *= texel[3];
+        }
+    }
+}
+
+/// Divide the color channels of decoded texels by their alpha, the inverse of [`premultiply_alpha`]
+///
+/// Color channels of fully transparent texels are set to 0.
+pub fn unpremultiply_alpha(texels: &mut [[f32; 4]]) {
+    for texel in texels {
+        for channel in 0..3 {
+            texel[channel] = if texel[3] == 0.0 {
+                0.0
+            } else {
+                texel[channel] / texel[3]
+            };
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl crate::Writer {
+    /// Set or clear the [`ALPHA_PREMULTIPLIED`](DataFormatFlags::ALPHA_PREMULTIPLIED) flag of the basic data format
+    /// descriptor, leaving the level data unchanged
+    pub fn set_alpha_premultiplied(&mut self, premultiplied: bool) {
+        // The flags are the last byte of the first word after the block header
+        if let Some(flags) = self
+            .basic_data_format_descriptor_mut()
+            .and_then(|dfd| dfd.get_mut(DataFormatDescriptorHeader::LENGTH + 3))
+        {
+            let bit = DataFormatFlags::ALPHA_PREMULTIPLIED.bits() as u8;
+            if premultiplied {
+                *flags |= bit;
+            } else {
+                *flags &= !bit;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_alpha_mode() {
+    let texture = |format: crate::Format, data: &[u8]| crate::Writer {
+        header: crate::Header {
+            format: Some(format),
+            type_size: 1,
+            pixel_width: 1,
+            pixel_height: 1,
+            pixel_depth: 0,
+            layer_count: 0,
+            face_count: 1,
+            level_count: 1,
+            supercompression_scheme: None,
+            index: Default::default(),
+        },
+        data_format_descriptors: format.data_format_descriptors().unwrap(),
+        key_value_data: Default::default(),
+        supercompression_global_data: std::vec::Vec::new(),
+        levels: std::vec![crate::WriterLevel::new(data.to_vec())],
+    };
+    let mut writer = texture(crate::Format::R8G8B8A8_UNORM, &[255, 128, 0, 128]);
+    let alpha_mode = |writer: &crate::Writer| Reader::new(writer.write()).unwrap().alpha_mode();
+    assert_eq!(alpha_mode(&writer), Some(AlphaMode::Straight));
+    writer.set_alpha_premultiplied(true);
+    assert_eq!(alpha_mode(&writer), Some(AlphaMode::Premultiplied));
+    writer.set_alpha_premultiplied(false);
+    assert_eq!(alpha_mode(&writer), Some(AlphaMode::Straight));
+
+    let opaque = texture(crate::Format::R8G8B8_UNORM, &[255, 128, 0]);
+    assert_eq!(alpha_mode(&opaque), Some(AlphaMode::Opaque));
+
+    // Alpha of BC7 is part of its single color sample
+    let mut bc7 = texture(crate::Format::BC7_UNORM_BLOCK, &[0; 16]);
+    bc7.set_alpha_premultiplied(true);
+    assert_eq!(alpha_mode(&bc7), Some(AlphaMode::Premultiplied));
+
+    let mut texels = [[1.0, 0.5, 0.25, 0.5], [1.0, 1.0, 1.0, 0.0]];
+    premultiply_alpha(&mut texels);
+    assert_eq!(texels, [[0.5, 0.25, 0.125, 0.5], [0.0, 0.0, 0.0, 0.0]]);
+    unpremultiply_alpha(&mut texels);
+    assert_eq!(texels, [[1.0, 0.5, 0.25, 0.5], [0.0, 0.0, 0.0, 0.0]]);
+}
@@ -0,0 +1,252 @@
+use crate::{cubemap::CUBEMAP_INCOMPLETE_KEY, Reader, TextureError, Writer, WriterLevel};
+use std::vec::Vec;
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// Split an array texture into one texture per layer, copying level data without decoding it
+    ///
+    /// Textures which aren't arrays are returned as a single texture. The faces of an incomplete cubemap are stored
+    /// as layers, so they're split too and `KTXcubemapIncomplete` is dropped from the key-value data.
+    pub fn split_layers(&self) -> Result<Vec<Writer>, TextureError> {
+        let mut original = Writer::from_reader(self);
+        original.key_value_data.remove(CUBEMAP_INCOMPLETE_KEY);
+        let layer_count = original.header.layer_count.max(1) as usize;
+        let level_layers = layers(&original, layer_count)?;
+
+        Ok((0..layer_count)
+            .map(|layer| {
+                let mut writer = original.clone();
+                writer.header.layer_count = 0;
+                for (level, layers) in writer.levels.iter_mut().zip(&level_layers) {
+                    *level = WriterLevel::new(layers[layer].to_vec());
+                }
+                writer
+            })
+            .collect())
+    }
+
+    /// Convert a 3D texture into a 2D array texture with a layer per depth slice
+    ///
+    /// Only the base level is kept as the mips of a 3D texture shrink in depth, all other levels are dropped. The
+    /// depth axis is removed from `KTXorientation`. Returns [`TextureError::UnsupportedLayout`] if the texture isn't
+    /// 3D.
+    pub fn volume_to_array(&self) -> Result<Writer, TextureError> {
+        let mut writer = base_level(self)?;
+        if writer.header.pixel_depth == 0 {
+            return Err(TextureError::UnsupportedLayout);
+        }
+        set_orientation_axes(&mut writer, 2);
+        let header = &mut writer.header;
+        if header.format.is_some_and(|format| format.block_dimensions()[2] != 1) {
+            return Err(TextureError::UnsupportedFormat);
+        }
+
+        // Slices of 3D array layers are stored consecutively, so each becomes a layer of its own
+        header.layer_count = header.pixel_depth * header.layer_count.max(1);
+        header.pixel_depth = 0;
+        Ok(writer)
+    }
+
+    /// Convert a 2D array texture into a 3D texture with a depth slice per layer
+    ///
+    /// Only the base level is kept as the mips of a 3D texture shrink in depth, all other levels are dropped. An
+    /// `i` depth axis is added to a 2D `KTXorientation`. Returns [`TextureError::UnsupportedLayout`] if the texture
+    /// isn't a 2D array.
+    pub fn array_to_volume(&self) -> Result<Writer, TextureError> {
+        let mut writer = base_level(self)?;
+        let header = &writer.header;
+        if header.layer_count == 0 || header.pixel_depth != 0 || header.pixel_height == 0 || header.face_count != 1 {
+            return Err(TextureError::UnsupportedLayout);
+        }
+        set_orientation_axes(&mut writer, 3);
+        let header = &mut writer.header;
+
+        header.pixel_depth = header.layer_count;
+        header.layer_count = 0;
+        Ok(writer)
+    }
+}
+
+impl Writer {
+    /// Combine textures of the same format, dimensions and level count into an array texture, copying level data
+    /// without decoding it
+    ///
+    /// Layers of array textures are all included. Key-value data is taken from the first texture, so the
+    /// `KTXcubemapIncomplete` and `KTXorientation` entries, which describe every layer, must match across textures.
+    pub fn array<Data: AsRef<[u8]>>(textures: &[Reader<Data>]) -> Result<Self, TextureError> {
+        let (first, rest) = textures.split_first().ok_or(TextureError::InvalidLength)?;
+        let mut writer = Self::from_reader(first);
+        if writer.header.supercompression_scheme.is_some() {
+            return Err(TextureError::UnsupportedSupercompression);
+        }
+
+        for texture in rest {
+            let other = Self::from_reader(texture);
+            let compatible_header = |writer: &Self| {
+                let mut header = writer.header;
+                header.layer_count = 0;
+                header.index = Default::default();
+                header
+            };
+            let layer_metadata = |writer: &Self| {
+                [CUBEMAP_INCOMPLETE_KEY, "KTXorientation"].map(|key| writer.key_value_data.get(key).cloned())
+            };
+            if compatible_header(&other) != compatible_header(&writer)
+                || other.data_format_descriptors != writer.data_format_descriptors
+                || layer_metadata(&other) != layer_metadata(&writer)
+            {
+                return Err(TextureError::IncompatibleTextures);
+            }
+
+            writer.header.layer_count = writer.header.layer_count.max(1) + other.header.layer_count.max(1);
+            for (level, other_level) in writer.levels.iter_mut().zip(other.levels) {
+                level.data.extend_from_slice(&other_level.data);
+                level.uncompressed_byte_length = level.data.len() as u64;
+            }
+        }
+        Ok(writer)
+    }
+}
+
+/// Data of each layer of each level
+fn layers(writer: &Writer, layer_count: usize) -> Result<Vec<Vec<&[u8]>>, TextureError> {
+    if writer.header.supercompression_scheme.is_some() {
+        return Err(TextureError::UnsupportedSupercompression);
+    }
+    writer
+        .levels
+        .iter()
+        .map(|level| {
+            if level.data.len() % layer_count != 0 {
+                return Err(TextureError::InvalidLength);
+            }
+            Ok(level.data.chunks_exact(level.data.len() / layer_count).collect())
+        })
+        .collect()
+}
+
+fn base_level<Data: AsRef<[u8]>>(reader: &Reader<Data>) -> Result<Writer, TextureError> {
+    let mut writer = Writer::from_reader(reader);
+    if writer.header.supercompression_scheme.is_some() {
+        return Err(TextureError::UnsupportedSupercompression);
+    }
+    writer.levels.truncate(1);
+    writer.header.level_count = 1;
+    Ok(writer)
+}
+
+/// Truncate `KTXorientation` to `axes` axes or extend it with the default `i` depth axis
+fn set_orientation_axes(writer: &mut Writer, axes: usize) {
+    if let Some(value) = writer.key_value_data.get_mut("KTXorientation") {
+        let length = value.iter().position(|&byte| byte == 0).unwrap_or(value.len());
+        value.truncate(length.min(axes));
+        if value.len() == 2 && axes == 3 {
+            value.push(b'i');
+        }
+        value.push(0);
+    }
+}
+
+#[test]
+fn test_arrays() {
+    use crate::Format;
+
+    let texture = |value| {
+        Writer {
+            header: crate::Header {
+                format: Some(Format::R8_UNORM),
+                type_size: 1,
+                pixel_width: 4,
+                pixel_height: 4,
+                pixel_depth: 0,
+                layer_count: 0,
+                face_count: 1,
+                level_count: 3,
+                supercompression_scheme: None,
+                index: Default::default(),
+            },
+            data_format_descriptors: Format::R8_UNORM.data_format_descriptors().unwrap(),
+            key_value_data: Default::default(),
+            supercompression_global_data: Vec::new(),
+            levels: [16, 4, 1]
+                .map(|length| WriterLevel::new(std::vec![value; length]))
+                .to_vec(),
+        }
+        .write()
+    };
+    let files = [texture(1), texture(2), texture(3)];
+    let readers = files
+        .iter()
+        .map(|file| Reader::new(&file[..]).unwrap())
+        .collect::<Vec<_>>();
+
+    let array = Writer::array(&readers).unwrap().write();
+    let array = Reader::new(&array[..]).unwrap();
+    assert_eq!(array.header().layer_count, 3);
+    assert_eq!(array.levels().nth(2).unwrap().data, [1, 2, 3]);
+
+    let layers = array.split_layers().unwrap();
+    assert_eq!(layers.len(), 3);
+    assert_eq!(layers[1].write(), files[1]);
+
+    let volume = array.array_to_volume().unwrap().write();
+    let volume = Reader::new(&volume[..]).unwrap();
+    assert_eq!(volume.header().pixel_depth, 3);
+    assert_eq!(volume.header().level_count, 1);
+    assert_eq!(volume.volume_to_array().unwrap().header.layer_count, 3);
+    assert!(matches!(array.volume_to_array(), Err(TextureError::UnsupportedLayout)));
+    assert!(matches!(volume.array_to_volume(), Err(TextureError::UnsupportedLayout)));
+
+    let mut oriented = Writer::from_reader(&array);
+    oriented
+        .key_value_data
+        .insert("KTXorientation".into(), b"ru\0".to_vec());
+    let oriented = oriented.write();
+    let volume = Reader::new(&oriented[..]).unwrap().array_to_volume().unwrap().write();
+    let volume = Reader::new(&volume[..]).unwrap();
+    assert_eq!(volume.key_value_data().next(), Some(("KTXorientation", &b"rui\0"[..])));
+    let array = volume.volume_to_array().unwrap();
+    assert_eq!(array.key_value_data["KTXorientation"], b"ru\0");
+
+    let incomplete = Writer::cubemap(
+        Format::R8_UNORM,
+        1,
+        [Some(&[1][..]), None, None, Some(&[4]), None, None],
+    )
+    .unwrap()
+    .write();
+    let faces = Reader::new(&incomplete[..]).unwrap().split_layers().unwrap();
+    assert_eq!(faces.len(), 2);
+    assert!(faces.iter().all(|face| face.key_value_data.is_empty()));
+
+    let mut other = Writer::from_reader(&readers[0]);
+    other.header.pixel_width = 2;
+    let other = other.write();
+    let mismatched = [Reader::new(&files[0][..]).unwrap(), Reader::new(&other[..]).unwrap()];
+    assert!(matches!(
+        Writer::array(&mismatched),
+        Err(TextureError::IncompatibleTextures)
+    ));
+
+    let mut flipped = Writer::from_reader(&readers[0]);
+    flipped.key_value_data.insert("KTXorientation".into(), b"ru\0".to_vec());
+    let flipped = flipped.write();
+    let mismatched = [Reader::new(&files[0][..]).unwrap(), Reader::new(&flipped[..]).unwrap()];
+    assert!(matches!(
+        Writer::array(&mismatched),
+        Err(TextureError::IncompatibleTextures)
+    ));
+
+    let incomplete = |faces: [Option<&[u8]>; 6]| Writer::cubemap(Format::R8_UNORM, 1, faces).unwrap().write();
+    let incomplete = [
+        incomplete([Some(&[1]), None, None, None, None, None]),
+        incomplete([None, Some(&[2]), None, None, None, None]),
+    ];
+    let mismatched = incomplete
+        .iter()
+        .map(|file| Reader::new(&file[..]).unwrap())
+        .collect::<Vec<_>>();
+    assert!(matches!(
+        Writer::array(&mismatched),
+        Err(TextureError::IncompatibleTextures)
+    ));
+}
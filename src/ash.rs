@@ -0,0 +1,126 @@
+//! Integration with the [ash](https://github.com/ash-rs/ash) Vulkan bindings, enabled by the `ash` feature.
+
+use crate::{Format, Header, ImageLayout, Reader, TextureError};
+use ::ash::vk;
+use std::vec::Vec;
+
+/// Convert a KTX2 format into a Vulkan format. KTX2 formats are Vulkan formats, so this always succeeds.
+pub fn vk_format(format: Format) -> vk::Format {
+    vk::Format::from_raw(format.0.get() as i32)
+}
+
+/// Convert a Vulkan format into a KTX2 format, returning `None` for `vk::Format::UNDEFINED`
+pub fn format(vk_format: vk::Format) -> Option<Format> {
+    Format::new(vk_format.as_raw() as u32)
+}
+
+/// Describe an optimally tiled image matching `header`
+///
+/// Cube maps are created with six array layers per cube and [`vk::ImageCreateFlags::CUBE_COMPATIBLE`].
+pub fn image_create_info(
+    header: &Header,
+    usage: vk::ImageUsageFlags,
+) -> Result<vk::ImageCreateInfo<'static>, TextureError> {
+    let format = header.format.ok_or(TextureError::UnsupportedFormat)?;
+    let [width, height, depth] = header.level_extent(0);
+    let image_type = if header.pixel_depth > 0 {
+        vk::ImageType::TYPE_3D
+    } else if header.pixel_height > 0 {
+        vk::ImageType::TYPE_2D
+    } else {
+        vk::ImageType::TYPE_1D
+    };
+    let flags = if header.face_count == 6 {
+        vk::ImageCreateFlags::CUBE_COMPATIBLE
+    } else {
+        vk::ImageCreateFlags::empty()
+    };
+
+    Ok(vk::ImageCreateInfo::default()
+        .flags(flags)
+        .image_type(image_type)
+        .format(vk_format(format))
+        .extent(vk::Extent3D { width, height, depth })
+        .mip_levels(header.level_count.max(1))
+        .array_layers(header.layer_count.max(1) * header.face_count)
+        .samples(vk::SampleCountFlags::TYPE_1)
+        .tiling(vk::ImageTiling::OPTIMAL)
+        .usage(usage)
+        .sharing_mode(vk::SharingMode::EXCLUSIVE)
+        .initial_layout(vk::ImageLayout::UNDEFINED))
+}
+
+/// Copy regions for uploading every level, layer and face of `reader` with `vkCmdCopyBufferToImage`
+///
+/// Buffer offsets are relative to the start of [`Reader::data`], whose level alignment satisfies the requirements of
+/// buffer to image copies. Combined depth/stencil formats and supercompressed textures are not supported.
+pub fn buffer_image_copies<Data: AsRef<[u8]>>(reader: &Reader<Data>) -> Result<Vec<vk::BufferImageCopy>, TextureError> {
+    let header = reader.header();
+    if header.supercompression_scheme.is_some() {
+        return Err(TextureError::UnsupportedSupercompression);
+    }
+    let format = header.format.ok_or(TextureError::UnsupportedFormat)?;
+    let aspect_mask = match format {
+        Format::D16_UNORM | Format::X8_D24_UNORM_PACK32 | Format::D32_SFLOAT => vk::ImageAspectFlags::DEPTH,
+        Format::S8_UINT => vk::ImageAspectFlags::STENCIL,
+        Format::D16_UNORM_S8_UINT | Format::D24_UNORM_S8_UINT | Format::D32_SFLOAT_S8_UINT => {
+            return Err(TextureError::UnsupportedFormat)
+        }
+        _ => vk::ImageAspectFlags::COLOR,
+    };
+    let layer_count = header
+        .layer_count
+        .max(1)
+        .checked_mul(header.face_count)
+        .ok_or(TextureError::InvalidLength)?;
+
+    let data_start = reader.data().as_ptr() as usize;
+    reader
+        .levels()
+        .enumerate()
+        .map(|(mip_level, level)| {
+            let mip_level = mip_level as u32;
+            let extent = header.level_extent(mip_level);
+            let layout = ImageLayout::new(format, extent)?;
+            if Some(level.data.len()) != layout.byte_length.checked_mul(layer_count as usize) {
+                return Err(TextureError::InvalidLength);
+            }
+
+            Ok(vk::BufferImageCopy {
+                buffer_offset: (level.data.as_ptr() as usize - data_start) as vk::DeviceSize,
+                // Tightly packed
+                buffer_row_length: 0,
+                buffer_image_height: 0,
+                image_subresource: vk::ImageSubresourceLayers {
+                    aspect_mask,
+                    mip_level,
+                    base_array_layer: 0,
+                    layer_count,
+                },
+                image_offset: vk::Offset3D::default(),
+                image_extent: vk::Extent3D {
+                    width: extent[0],
+                    height: extent[1],
+                    depth: extent[2],
+                },
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn test_buffer_image_copies() {
+    let reader = Reader::new(&include_bytes!("../data/test_tex.ktx2")[..]).unwrap();
+    let create_info = image_create_info(&reader.header(), vk::ImageUsageFlags::SAMPLED).unwrap();
+    assert!(create_info.format == vk::Format::R8G8B8A8_UINT);
+    assert!(create_info.image_type == vk::ImageType::TYPE_2D);
+    assert_eq!(create_info.mip_levels, 11);
+    assert_eq!(format(create_info.format), Some(Format::R8G8B8A8_UINT));
+
+    let copies = buffer_image_copies(&reader).unwrap();
+    assert_eq!(copies.len(), 11);
+    for (copy, level) in copies.iter().zip(reader.levels()) {
+        let start = copy.buffer_offset as usize;
+        assert_eq!(&reader.data()[start..start + level.data.len()], level.data);
+    }
+}
@@ -0,0 +1,590 @@
+//! Decoder for 2D ASTC blocks of the LDR profile, following the
+//! [Khronos Data Format Specification](https://registry.khronos.org/DataFormat/specs/1.3/dataformat.1.3.html#ASTC).
+//!
+//! Texels are written in row-major order as RGBA values. Blocks that are invalid or use HDR color endpoint modes
+//! decode to the error color, opaque magenta.
+
+use core::convert::TryInto;
+use std::vec::Vec;
+
+const ERROR_COLOR: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+
+/// Ranges of integer sequence encoded values as `(base, bits)`, where `base` is 3 for trits, 5 for quints and 1
+/// otherwise, in increasing order of their `base << bits` levels
+#[rustfmt::skip]
+const RANGES: [(u32, u32); 21] = [
+    (1, 1), (3, 0), (1, 2), (5, 0), (3, 1), (1, 3), (5, 1), (3, 2), (1, 4), (5, 2), (3, 3), (1, 5), (5, 3), (3, 4),
+    (1, 6), (5, 4), (3, 5), (1, 7), (5, 5), (3, 6), (1, 8),
+];
+
+/// Index of the smallest range usable for color endpoints, with 6 levels
+const MIN_COLOR_RANGE: usize = 4;
+
+/// Weight grid of a block
+struct BlockMode {
+    width: usize,
+    height: usize,
+    dual_plane: bool,
+    weight_range: usize,
+}
+
+/// Reader of bits in increasing order, which reads zeros past its end
+struct Bits {
+    value: u128,
+    position: u32,
+    end: u32,
+}
+
+impl Bits {
+    fn read(&mut self, count: u32) -> u32 {
+        let mut value = 0;
+        for bit in 0..count {
+            let position = self.position + bit;
+            if position < self.end.min(128) {
+                value |= (((self.value >> position) & 1) as u32) << bit;
+            }
+        }
+        self.position += count;
+        value
+    }
+}
+
+/// Decode a 16-byte block of `block_width` by `block_height` texels
+///
+/// With `srgb`, endpoints are expanded as for sRGB formats and texels keep 8 bits of precision, without being
+/// linearized.
+pub(crate) fn decode_block(block: &[u8], [block_width, block_height]: [usize; 2], srgb: bool, texels: &mut [[f32; 4]]) {
+    let bits = u128::from_le_bytes(block.try_into().unwrap());
+    if decode_texels(bits, block_width, block_height, srgb, texels).is_none() {
+        texels.fill(ERROR_COLOR);
+    }
+}
+
+fn decode_texels(
+    bits: u128,
+    block_width: usize,
+    block_height: usize,
+    srgb: bool,
+    texels: &mut [[f32; 4]],
+) -> Option<()> {
+    let field = |start: u32, count: u32| (bits >> start & ((1 << count) - 1)) as u32;
+    let finish = |value: u32| {
+        if srgb {
+            (value >> 8) as f32 / 255.0
+        } else {
+            value as f32 / 65535.0
+        }
+    };
+
+    if field(0, 9) == 0x1FC {
+        // Void-extent blocks have a single UNORM16 color, and are HDR if bit 9 is set
+        if field(9, 1) != 0 {
+            return None;
+        }
+        let color = [0, 1, 2, 3].map(|channel| finish(field(64 + channel * 16, 16)));
+        texels.fill(color);
+        return Some(());
+    }
+
+    let mode = block_mode(field(0, 11))?;
+    let planes = if mode.dual_plane { 2 } else { 1 };
+    let weight_count = mode.width * mode.height * planes;
+    let weight_bits = ise_bit_count(mode.weight_range, weight_count);
+    if mode.width > block_width || mode.height > block_height || weight_count > 64 || !(24..=96).contains(&weight_bits)
+    {
+        return None;
+    }
+    let partitions = field(11, 2) as usize + 1;
+    if partitions == 4 && mode.dual_plane {
+        return None;
+    }
+
+    // Additional endpoint mode bits and the dual plane's component sit below the weights
+    let mut below_weights = 128 - weight_bits;
+    let (modes, color_start) = if partitions == 1 {
+        (std::vec![field(13, 4)], 17)
+    } else {
+        let mode_field = field(23, 6);
+        let class = mode_field & 0x3;
+        if class == 0 {
+            (std::vec![mode_field >> 2; partitions], 29)
+        } else {
+            let extra_bits = 3 * partitions as u32 - 4;
+            below_weights -= extra_bits;
+            let combined = mode_field >> 2 | field(below_weights, extra_bits) << 4;
+            let modes = (0..partitions)
+                .map(|partition| {
+                    let class_offset = combined >> partition & 1;
+                    let mode = combined >> (partitions + partition * 2) & 0x3;
+                    (class - 1 + class_offset) << 2 | mode
+                })
+                .collect();
+            (modes, 29)
+        }
+    };
+    let plane_component = if mode.dual_plane {
+        below_weights -= 2;
+        Some(field(below_weights, 2) as usize)
+    } else {
+        None
+    };
+
+    if modes.iter().any(|&mode| matches!(mode, 2 | 3 | 7 | 11 | 14 | 15)) {
+        return None;
+    }
+    let value_count = modes.iter().map(|&mode| 2 * ((mode >> 2) + 1) as usize).sum::<usize>();
+    let color_bits = below_weights.checked_sub(color_start)?;
+    if value_count > 18 {
+        return None;
+    }
+    let color_range = (MIN_COLOR_RANGE..RANGES.len())
+        .rev()
+        .find(|&range| ise_bit_count(range, value_count) <= color_bits)?;
+    let values = decode_ise(bits, color_start, color_range, value_count)
+        .into_iter()
+        .map(|value| unquantize_color(color_range, value) as i32)
+        .collect::<Vec<_>>();
+    let mut offset = 0;
+    let endpoints = modes
+        .iter()
+        .map(|&mode| {
+            let count = 2 * ((mode >> 2) + 1) as usize;
+            offset += count;
+            decode_endpoints(mode, &values[offset - count..offset])
+        })
+        .collect::<Vec<_>>();
+
+    // Weights are stored in reverse from the end of the block
+    let weights = decode_ise(bits.reverse_bits(), 0, mode.weight_range, weight_count)
+        .into_iter()
+        .map(|value| unquantize_weight(mode.weight_range, value))
+        .collect::<Vec<_>>();
+
+    let seed = field(13, 10);
+    let small_block = block_width * block_height < 31;
+    let scale = |size: usize| (1024 + size / 2) / (size - 1);
+    let (scale_s, scale_t) = (scale(block_width), scale(block_height));
+    for (index, texel) in texels.iter_mut().enumerate().take(block_width * block_height) {
+        let (x, y) = (index % block_width, index / block_width);
+        let partition = if partitions == 1 {
+            0
+        } else {
+            select_partition(seed, x as u32, y as u32, partitions as u32, small_block)
+        };
+        let [start, end] = endpoints[partition];
+
+        // Bilinear infill of the weight grid
+        let gs = (scale_s * x * (mode.width - 1) + 32) >> 6;
+        let gt = (scale_t * y * (mode.height - 1) + 32) >> 6;
+        let (js, fs, jt, ft) = (gs >> 4, gs & 0xF, gt >> 4, gt & 0xF);
+        let w11 = (fs * ft + 8) >> 4;
+        let factors = [16 + w11 - fs - ft, fs - w11, ft - w11, w11];
+        let grid = [
+            js + jt * mode.width,
+            js + 1 + jt * mode.width,
+            js + (jt + 1) * mode.width,
+            js + 1 + (jt + 1) * mode.width,
+        ];
+        let weight = |plane: usize| {
+            let sum = grid
+                .iter()
+                .zip(factors)
+                .map(|(&point, factor)| weights.get(point * planes + plane).copied().unwrap_or(0) * factor as u32)
+                .sum::<u32>();
+            (sum + 8) >> 4
+        };
+        let weights = [weight(0), if mode.dual_plane { weight(1) } else { 0 }];
+
+        for (channel, value) in texel.iter_mut().enumerate() {
+            let weight = weights[usize::from(plane_component == Some(channel))];
+            let expand = |value: i32| {
+                let value = value as u32;
+                if srgb {
+                    value << 8 | 0x80
+                } else {
+                    value << 8 | value
+                }
+            };
+            let (c0, c1) = (expand(start[channel]), expand(end[channel]));
+            *value = finish((c0 * (64 - weight) + c1 * weight + 32) >> 6);
+        }
+    }
+    Some(())
+}
+
+/// Decode the 11-bit block mode into the weight grid size, plane count and weight range
+fn block_mode(mode: u32) -> Option<BlockMode> {
+    let field = |start: u32, count: u32| (mode >> start & ((1 << count) - 1)) as usize;
+    let (a, b) = (field(5, 2), field(7, 2));
+    let (width, height, range, high_precision, dual_plane) = if field(0, 2) != 0 {
+        let range = field(4, 1) | field(0, 2) << 1;
+        let (width, height) = match field(2, 2) {
+            0 => (b + 4, a + 2),
+            1 => (b + 8, a + 2),
+            2 => (a + 2, b + 8),
+            _ if field(8, 1) == 0 => (a + 2, field(7, 1) + 6),
+            _ => (field(7, 1) + 2, a + 2),
+        };
+        (width, height, range, field(9, 1), field(10, 1) != 0)
+    } else {
+        let range = field(4, 1) | field(2, 2) << 1;
+        if range < 2 {
+            return None;
+        }
+        let (high_precision, dual_plane) = (field(9, 1), field(10, 1) != 0);
+        match b {
+            0 => (12, a + 2, range, high_precision, dual_plane),
+            1 => (a + 2, 12, range, high_precision, dual_plane),
+            2 => (a + 6, field(9, 2) + 6, range, 0, false),
+            _ => match a {
+                0 => (6, 10, range, high_precision, dual_plane),
+                1 => (10, 6, range, high_precision, dual_plane),
+                _ => return None,
+            },
+        }
+    };
+    Some(BlockMode {
+        width,
+        height,
+        dual_plane,
+        weight_range: range - 2 + high_precision * 6,
+    })
+}
+
+/// Number of bits taken by `count` values of a range
+fn ise_bit_count(range: usize, count: usize) -> u32 {
+    let (base, bits) = RANGES[range];
+    let count = count as u32;
+    count * bits
+        + match base {
+            3 => (count * 8).div_ceil(5),
+            5 => (count * 7).div_ceil(3),
+            _ => 0,
+        }
+}
+
+/// Decode `count` integer sequence encoded values of a range starting at bit `start`
+fn decode_ise(bits: u128, start: u32, range: usize, count: usize) -> Vec<u32> {
+    let (base, value_bits) = RANGES[range];
+    let mut reader = Bits {
+        value: bits,
+        position: start,
+        end: start + ise_bit_count(range, count),
+    };
+    let mut values = Vec::with_capacity(count + 4);
+    while values.len() < count {
+        match base {
+            3 => {
+                let mut low = [0; 5];
+                let mut packed = 0;
+                for (index, (shift, packed_bits)) in [(0, 2), (2, 2), (4, 1), (5, 2), (7, 1)].iter().enumerate() {
+                    low[index] = reader.read(value_bits);
+                    packed |= reader.read(*packed_bits) << shift;
+                }
+                for (trit, low) in decode_trits(packed).iter().zip(low) {
+                    values.push(trit << value_bits | low);
+                }
+            }
+            5 => {
+                let mut low = [0; 3];
+                let mut packed = 0;
+                for (index, (shift, packed_bits)) in [(0, 3), (3, 2), (5, 2)].iter().enumerate() {
+                    low[index] = reader.read(value_bits);
+                    packed |= reader.read(*packed_bits) << shift;
+                }
+                for (quint, low) in decode_quints(packed).iter().zip(low) {
+                    values.push(quint << value_bits | low);
+                }
+            }
+            _ => values.push(reader.read(value_bits)),
+        }
+    }
+    values.truncate(count);
+    values
+}
+
+/// Unpack five trits from 8 bits
+fn decode_trits(packed: u32) -> [u32; 5] {
+    let bit = |value: u32, index: u32| value >> index & 1;
+    let (c, t4, t3) = if packed >> 2 & 0x7 == 0x7 {
+        ((packed >> 5 & 0x7) << 2 | (packed & 0x3), 2, 2)
+    } else if packed >> 5 & 0x3 == 0x3 {
+        (packed & 0x1F, 2, bit(packed, 7))
+    } else {
+        (packed & 0x1F, bit(packed, 7), packed >> 5 & 0x3)
+    };
+    let (t2, t1, t0) = if c & 0x3 == 0x3 {
+        (2, bit(c, 4), bit(c, 3) << 1 | (bit(c, 2) & !bit(c, 3) & 1))
+    } else if c >> 2 & 0x3 == 0x3 {
+        (2, 2, c & 0x3)
+    } else {
+        (bit(c, 4), c >> 2 & 0x3, bit(c, 1) << 1 | (bit(c, 0) & !bit(c, 1) & 1))
+    };
+    [t0, t1, t2, t3, t4]
+}
+
+/// Unpack three quints from 7 bits
+fn decode_quints(packed: u32) -> [u32; 3] {
+    let bit = |value: u32, index: u32| value >> index & 1;
+    if packed >> 1 & 0x3 == 0x3 && packed >> 5 & 0x3 == 0 {
+        let q0 = bit(packed, 0);
+        let q2 = q0 << 2 | (bit(packed, 4) & !q0 & 1) << 1 | (bit(packed, 3) & !q0 & 1);
+        return [4, 4, q2];
+    }
+    let (q2, c) = if packed >> 1 & 0x3 == 0x3 {
+        (4, (packed >> 3 & 0x3) << 3 | (!packed >> 5 & 0x3) << 1 | bit(packed, 0))
+    } else {
+        (packed >> 5 & 0x3, packed & 0x1F)
+    };
+    let (q1, q0) = if c & 0x7 == 0x5 {
+        (4, c >> 3 & 0x3)
+    } else {
+        (c >> 3 & 0x3, c & 0x7)
+    };
+    [q0, q1, q2]
+}
+
+/// Repeat the low `bits` bits of `value` to fill `size` bits
+fn replicate(value: u32, bits: u32, size: u32) -> u32 {
+    let (mut result, mut filled) = (0, 0);
+    while filled < size {
+        result = result << bits | value;
+        filled += bits;
+    }
+    result >> (filled - size)
+}
+
+/// Unquantize a color endpoint value to 8 bits
+fn unquantize_color(range: usize, value: u32) -> u32 {
+    let (base, bits) = RANGES[range];
+    if base == 1 {
+        return replicate(value, bits, 8);
+    }
+    let (low, digit) = (value & ((1 << bits) - 1), value >> bits);
+    let a = if low & 1 != 0 { 0x1FF } else { 0 };
+    let high = low >> 1;
+    let (b, c) = match (base, bits) {
+        (3, 1) => (0, 204),
+        (5, 1) => (0, 113),
+        (3, 2) => (high << 8 | high << 4 | high << 2 | high << 1, 93),
+        (5, 2) => (high << 8 | high << 3 | high << 2, 54),
+        (3, 3) => (high << 7 | high << 2 | high, 44),
+        (5, 3) => (high << 7 | high << 1 | high >> 1, 26),
+        (3, 4) => (high << 6 | high, 22),
+        (5, 4) => (high << 6 | high >> 1, 13),
+        (3, 5) => (high << 5 | high >> 2, 11),
+        (5, 5) => (high << 5 | high >> 3, 6),
+        _ => (high << 4 | high >> 4, 5),
+    };
+    let t = (digit * c + b) ^ a;
+    (a & 0x80) | t >> 2
+}
+
+/// Unquantize a weight to the range 0 to 64
+fn unquantize_weight(range: usize, value: u32) -> u32 {
+    let (base, bits) = RANGES[range];
+    let weight = if base == 1 {
+        replicate(value, bits, 6)
+    } else if bits == 0 {
+        if base == 3 {
+            [0, 32, 63][value as usize]
+        } else {
+            [0, 16, 32, 47, 63][value as usize]
+        }
+    } else {
+        let (low, digit) = (value & ((1 << bits) - 1), value >> bits);
+        let a = if low & 1 != 0 { 0x7F } else { 0 };
+        let high = low >> 1;
+        let (b, c) = match (base, bits) {
+            (3, 1) => (0, 50),
+            (5, 1) => (0, 28),
+            (3, 2) => (high << 6 | high << 2 | high, 23),
+            (5, 2) => (high << 6 | high << 1, 13),
+            _ => (high << 5 | high, 11),
+        };
+        let t = (digit * c + b) ^ a;
+        (a & 0x20) | t >> 2
+    };
+    if weight > 32 {
+        weight + 1
+    } else {
+        weight
+    }
+}
+
+/// Decode the two RGBA endpoints of an LDR color endpoint mode from its unquantized values
+fn decode_endpoints(mode: u32, v: &[i32]) -> [[i32; 4]; 2] {
+    let blue_contract = |[r, g, b, a]: [i32; 4]| [(r + b) >> 1, (g + b) >> 1, b, a];
+    // Moves the top bit of `b` to `a`, returning `a` as a signed 6-bit offset and `b` as a base
+    let bit_transfer_signed = |a: i32, b: i32| {
+        let b = b >> 1 | (a & 0x80);
+        let a = (a >> 1) & 0x3F;
+        (if a & 0x20 != 0 { a - 0x40 } else { a }, b)
+    };
+    let endpoints = match mode {
+        0 => [[v[0], v[0], v[0], 255], [v[1], v[1], v[1], 255]],
+        1 => {
+            let l0 = (v[0] >> 2) | (v[1] & 0xC0);
+            let l1 = (l0 + (v[1] & 0x3F)).min(255);
+            [[l0, l0, l0, 255], [l1, l1, l1, 255]]
+        }
+        4 => [[v[0], v[0], v[0], v[2]], [v[1], v[1], v[1], v[3]]],
+        5 => {
+            let (l1, l0) = bit_transfer_signed(v[1], v[0]);
+            let (a1, a0) = bit_transfer_signed(v[3], v[2]);
+            [[l0, l0, l0, a0], [l0 + l1, l0 + l1, l0 + l1, a0 + a1]]
+        }
+        6 => [
+            [(v[0] * v[3]) >> 8, (v[1] * v[3]) >> 8, (v[2] * v[3]) >> 8, 255],
+            [v[0], v[1], v[2], 255],
+        ],
+        10 => [
+            [(v[0] * v[3]) >> 8, (v[1] * v[3]) >> 8, (v[2] * v[3]) >> 8, v[4]],
+            [v[0], v[1], v[2], v[5]],
+        ],
+        8 | 12 => {
+            let (a0, a1) = if mode == 12 { (v[6], v[7]) } else { (255, 255) };
+            if v[1] + v[3] + v[5] >= v[0] + v[2] + v[4] {
+                [[v[0], v[2], v[4], a0], [v[1], v[3], v[5], a1]]
+            } else {
+                [
+                    blue_contract([v[1], v[3], v[5], a1]),
+                    blue_contract([v[0], v[2], v[4], a0]),
+                ]
+            }
+        }
+        _ => {
+            // Modes 9 and 13, base and offset
+            let (r1, r0) = bit_transfer_signed(v[1], v[0]);
+            let (g1, g0) = bit_transfer_signed(v[3], v[2]);
+            let (b1, b0) = bit_transfer_signed(v[5], v[4]);
+            let (a1, a0) = if mode == 13 {
+                bit_transfer_signed(v[7], v[6])
+            } else {
+                (0, 255)
+            };
+            if r1 + g1 + b1 >= 0 {
+                [[r0, g0, b0, a0], [r0 + r1, g0 + g1, b0 + b1, a0 + a1]]
+            } else {
+                [
+                    blue_contract([r0 + r1, g0 + g1, b0 + b1, a0 + a1]),
+                    blue_contract([r0, g0, b0, a0]),
+                ]
+            }
+        }
+    };
+    endpoints.map(|endpoint| endpoint.map(|value| value.clamp(0, 255)))
+}
+
+/// Partition of a texel given by the partition pattern hash
+fn select_partition(seed: u32, x: u32, y: u32, partitions: u32, small_block: bool) -> usize {
+    let (x, y) = if small_block { (x << 1, y << 1) } else { (x, y) };
+    let seed = seed + (partitions - 1) * 1024;
+    let mut random = seed;
+    random ^= random >> 15;
+    random = random.wrapping_sub(random << 17);
+    random = random.wrapping_add(random << 7);
+    random = random.wrapping_add(random << 4);
+    random ^= random >> 5;
+    random = random.wrapping_add(random << 16);
+    random ^= random >> 7;
+    random ^= random >> 3;
+    random ^= random << 6;
+    random ^= random >> 17;
+
+    let mut seeds = [0; 8];
+    for (index, value) in seeds.iter_mut().enumerate() {
+        let nibble = random >> (index * 4) & 0xF;
+        *value = nibble * nibble;
+    }
+    let (even_shift, odd_shift) = if seed & 1 != 0 {
+        (if seed & 2 != 0 { 4 } else { 5 }, if partitions == 3 { 6 } else { 5 })
+    } else {
+        (if partitions == 3 { 6 } else { 5 }, if seed & 2 != 0 { 4 } else { 5 })
+    };
+    for (index, value) in seeds.iter_mut().enumerate() {
+        *value >>= if index % 2 == 0 { even_shift } else { odd_shift };
+    }
+
+    // The z coordinate of 2D blocks is 0, leaving out the remaining seeds
+    let mut distances = [
+        seeds[0] * x + seeds[1] * y + (random >> 14),
+        seeds[2] * x + seeds[3] * y + (random >> 10),
+        seeds[4] * x + seeds[5] * y + (random >> 6),
+        seeds[6] * x + seeds[7] * y + (random >> 2),
+    ]
+    .map(|distance| distance & 0x3F);
+    distances[partitions as usize..].fill(0);
+    let [a, b, c, d] = distances;
+    if a >= b && a >= c && a >= d {
+        0
+    } else if b >= c && b >= d {
+        1
+    } else if c >= d {
+        2
+    } else {
+        3
+    }
+}
+
+#[test]
+fn test_decode_trits_and_quints() {
+    // Every combination of digits has an encoding
+    let mut trits = (0..256).map(decode_trits).collect::<Vec<_>>();
+    trits.sort_unstable();
+    trits.dedup();
+    assert_eq!(trits.len(), 243);
+    assert!(trits.iter().flatten().all(|&trit| trit < 3));
+    let mut quints = (0..128).map(decode_quints).collect::<Vec<_>>();
+    quints.sort_unstable();
+    quints.dedup();
+    assert_eq!(quints.len(), 125);
+    assert!(quints.iter().flatten().all(|&quint| quint < 5));
+
+    // Each range unquantizes to distinct values spanning the endpoint and weight ranges
+    let unquantized = |range: usize, unquantize: fn(usize, u32) -> u32| {
+        let (base, bits) = RANGES[range];
+        let mut values = (0..base << bits)
+            .map(|value| unquantize(range, value))
+            .collect::<Vec<_>>();
+        values.sort_unstable();
+        values.dedup();
+        (values.len() as u32 == base << bits).then(|| [values[0], values[values.len() - 1]])
+    };
+    for range in MIN_COLOR_RANGE..RANGES.len() {
+        assert_eq!(unquantized(range, unquantize_color), Some([0, 255]));
+    }
+    for range in 0..12 {
+        assert_eq!(unquantized(range, unquantize_weight), Some([0, 64]));
+    }
+}
+
+#[test]
+fn test_decode_astc_block() {
+    let decode = |bits: u128| {
+        let mut texels = [[0.0; 4]; 16];
+        decode_block(&bits.to_le_bytes(), [4, 4], false, &mut texels);
+        texels
+    };
+
+    // A 4x4 grid of 2-bit weights between black and white endpoints of mode 8, stored as raw bytes
+    let mut block = 0x42 | 8 << 13;
+    for (index, value) in [0u128, 255, 0, 255, 0, 255].iter().enumerate() {
+        block |= value << (17 + index * 8);
+    }
+    let weights: u128 = 3 << 2 | 1 << 4 | 2 << 6;
+    let texels = decode(block | weights.reverse_bits());
+    assert_eq!(texels[0], [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(texels[1], [1.0; 4]);
+    assert_eq!(
+        texels[2],
+        [21504.0 / 65535.0, 21504.0 / 65535.0, 21504.0 / 65535.0, 1.0]
+    );
+
+    // Void-extent blocks fill the block with one color
+    let void_extent = 0xFFFF_FFFF_FFFF_FDFC | (0xFFFF_8000_0000_FFFF << 64);
+    assert_eq!(decode(void_extent)[15], [1.0, 0.0, 32768.0 / 65535.0, 1.0]);
+
+    // Reserved block modes
+    assert_eq!(decode(0), [ERROR_COLOR; 16]);
+}
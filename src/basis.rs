@@ -0,0 +1,407 @@
+//! Support for the Basis Universal texture formats: [`ColorModel::UASTC`] and [`ColorModel::ETC1S`] with
+//! [`BasisLZ`](crate::SupercompressionScheme::BasisLZ) supercompression.
+//!
+//! [`Reader::encode_uastc`] and [`Reader::encode_etc1s`] encode uncompressed textures as `toktx --encode` does, and
+//! the data format descriptors and BasisLZ global data can also be used to store the output of other encoders with
+//! [`crate::Writer`].
+
+use crate::{
+    format::{Numeric, ALPHA, BLUE, GREEN, RED},
+    texel::{DecodeOptions, Swizzle, SwizzleComponent},
+    BasicDataFormatDescriptorHeader, ChannelTypeQualifiers, ColorModel, ColorPrimaries, DataFormatFlags, ParseError,
+    Reader, SampleInformation, SupercompressionScheme, TextureError, TransferFunction, Writer, WriterLevel,
+};
+use core::convert::TryInto;
+use std::{string::String, vec::Vec};
+
+/// Channels stored in UASTC blocks
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UastcChannels {
+    Rgb,
+    Rgba,
+    Rrr,
+    Rrrg,
+    Rg,
+}
+
+/// Channels stored in ETC1S slices
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Etc1sChannels {
+    /// A single RGB slice
+    Rgb,
+    /// An RGB slice followed by an alpha slice
+    RgbAaa,
+    /// A single red slice
+    Rrr,
+    /// A red slice followed by a green slice
+    RrrGgg,
+}
+
+/// Data format descriptor blocks for UASTC texture data, for use in [`crate::Writer::data_format_descriptors`].
+///
+/// `bytesPlane0` is the 16 byte block size, or 0 if the data is supercompressed as the specification requires.
+pub fn uastc_data_format_descriptors(
+    channels: UastcChannels,
+    srgb: bool,
+    supercompression_scheme: Option<SupercompressionScheme>,
+) -> Vec<u8> {
+    let channel_type = match channels {
+        UastcChannels::Rgb => 0,
+        UastcChannels::Rgba => 3,
+        UastcChannels::Rrr => 4,
+        UastcChannels::Rrrg => 5,
+        UastcChannels::Rg => 6,
+    };
+    let mut bytes_planes = [0; 8];
+    if supercompression_scheme.is_none() {
+        bytes_planes[0] = 16;
+    }
+
+    crate::basic_data_format_descriptor_block(
+        &block_header(ColorModel::UASTC, srgb, bytes_planes),
+        &[block_sample(0, 128, channel_type)],
+    )
+}
+
+/// Data format descriptor blocks for ETC1S texture data, for use in [`crate::Writer::data_format_descriptors`].
+///
+/// ETC1S data must be supercompressed with [`SupercompressionScheme::BasisLZ`](crate::SupercompressionScheme::BasisLZ).
+pub fn etc1s_data_format_descriptors(channels: Etc1sChannels, srgb: bool) -> Vec<u8> {
+    let samples: &[_] = match channels {
+        Etc1sChannels::Rgb => &[block_sample(0, 64, 0)],
+        Etc1sChannels::RgbAaa => &[block_sample(0, 64, 0), block_sample(64, 64, 15)],
+        Etc1sChannels::Rrr => &[block_sample(0, 64, 3)],
+        Etc1sChannels::RrrGgg => &[block_sample(0, 64, 3), block_sample(64, 64, 4)],
+    };
+
+    // Sizes of BasisLZ supercompressed planes are unknown
+    crate::basic_data_format_descriptor_block(&block_header(ColorModel::ETC1S, srgb, [0; 8]), samples)
+}
+
+fn block_header(color_model: ColorModel, srgb: bool, bytes_planes: [u32; 8]) -> BasicDataFormatDescriptorHeader {
+    BasicDataFormatDescriptorHeader {
+        color_model: Some(color_model),
+        color_primaries: Some(ColorPrimaries::BT709),
+        transfer_function: Some(if srgb {
+            TransferFunction::SRGB
+        } else {
+            TransferFunction::Linear
+        }),
+        flags: DataFormatFlags::STRAIGHT_ALPHA,
+        texel_block_dimensions: [4, 4, 1, 1],
+        bytes_planes,
+    }
+}
+
+fn block_sample(bit_offset: u32, bit_length: u32, channel_type: u32) -> SampleInformation {
+    SampleInformation {
+        bit_offset,
+        bit_length,
+        channel_type,
+        channel_type_qualifiers: ChannelTypeQualifiers::empty(),
+        sample_positions: [0; 4],
+        lower: 0,
+        upper: u32::MAX,
+    }
+}
+
+/// 4x4 block of 8-bit RGBA texels in row-major order
+pub(crate) type Block = [[u8; 4]; 16];
+
+/// Channels of an uncompressed texture to be encoded
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum SourceChannels {
+    R,
+    Rg,
+    Rgb,
+    Rgba,
+}
+
+/// Uncompressed texture to be encoded, split into blocks
+pub(crate) struct Source {
+    pub channels: SourceChannels,
+    pub srgb: bool,
+    /// Blocks of each image in storage order, for each level starting with the base level
+    pub levels: Vec<Vec<Vec<Block>>>,
+}
+
+impl Source {
+    /// Decode the levels of a texture of a normalized or sRGB format, quantizing values to 8 bits
+    pub fn new<Data: AsRef<[u8]>>(reader: &Reader<Data>) -> Result<Self, TextureError> {
+        let header = reader.header();
+        let format = header.format.ok_or(TextureError::UnsupportedFormat)?;
+        let components = format.components().ok_or(TextureError::UnsupportedFormat)?;
+        if !components
+            .iter()
+            .all(|component| matches!(component.numeric, Numeric::UNorm | Numeric::SRgb))
+        {
+            return Err(TextureError::UnsupportedFormat);
+        }
+        let has = |channel| components.iter().any(|component| component.channel == channel);
+        let channels = if has(ALPHA) {
+            SourceChannels::Rgba
+        } else if has(BLUE) {
+            SourceChannels::Rgb
+        } else if has(GREEN) {
+            SourceChannels::Rg
+        } else if has(RED) {
+            SourceChannels::R
+        } else {
+            return Err(TextureError::UnsupportedFormat);
+        };
+
+        let levels = (0..header.level_count.max(1))
+            .map(|level| {
+                let [width, height, depth] = header.level_extent(level).map(|dimension| dimension as usize);
+                let texels = reader.decode_level(level, DecodeOptions::default())?;
+                let image_count = (header.layer_count.max(1) as usize)
+                    .checked_mul(header.face_count as usize)
+                    .and_then(|count| count.checked_mul(depth));
+                if image_count.and_then(|count| count.checked_mul(width * height)) != Some(texels.len()) {
+                    return Err(TextureError::InvalidLength);
+                }
+                Ok(texels
+                    .chunks_exact(width * height)
+                    .map(|image| blocks(image, width, height))
+                    .collect())
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            channels,
+            srgb: format.is_srgb(),
+            levels,
+        })
+    }
+
+    /// Copy of the source texture with its level data replaced by encoded data
+    ///
+    /// One and two channel sources are encoded with red in RGB and green in alpha, so their `KTXswizzle` entry is
+    /// updated to read the same values as before.
+    pub fn writer<Data: AsRef<[u8]>>(
+        &self,
+        reader: &Reader<Data>,
+        data_format_descriptors: Vec<u8>,
+        supercompression_scheme: Option<SupercompressionScheme>,
+        supercompression_global_data: Vec<u8>,
+        levels: Vec<WriterLevel>,
+    ) -> Writer {
+        let mut writer = Writer::from_reader(reader);
+        writer.header.format = None;
+        writer.header.type_size = 1;
+        writer.header.supercompression_scheme = supercompression_scheme;
+        writer.data_format_descriptors = data_format_descriptors;
+        writer.supercompression_global_data = supercompression_global_data;
+        writer.levels = levels;
+
+        if matches!(self.channels, SourceChannels::R | SourceChannels::Rg) {
+            let swizzle = reader.swizzle().unwrap_or(Swizzle::IDENTITY);
+            let swizzle = swizzle.0.map(|component| match component {
+                SwizzleComponent::Green if self.channels == SourceChannels::Rg => SwizzleComponent::Alpha,
+                SwizzleComponent::Green | SwizzleComponent::Blue => SwizzleComponent::Zero,
+                SwizzleComponent::Alpha => SwizzleComponent::One,
+                component => component,
+            });
+            let mut value = swizzle
+                .iter()
+                .map(|component| match component {
+                    SwizzleComponent::Red => 'r',
+                    SwizzleComponent::Green => 'g',
+                    SwizzleComponent::Blue => 'b',
+                    SwizzleComponent::Alpha => 'a',
+                    SwizzleComponent::Zero => '0',
+                    SwizzleComponent::One => '1',
+                })
+                .collect::<String>()
+                .into_bytes();
+            value.push(0);
+            writer.key_value_data.insert(String::from("KTXswizzle"), value);
+        }
+        writer
+    }
+}
+
+/// Split an image into blocks in row-major order, repeating the last row and column in blocks extending past its edge
+fn blocks(texels: &[[f32; 4]], width: usize, height: usize) -> Vec<Block> {
+    let quantize = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let mut blocks = Vec::with_capacity(width.div_ceil(4) * height.div_ceil(4));
+    for block_y in 0..height.div_ceil(4) {
+        for block_x in 0..width.div_ceil(4) {
+            let mut block = [[0; 4]; 16];
+            for (index, texel) in block.iter_mut().enumerate() {
+                let x = (block_x * 4 + index % 4).min(width - 1);
+                let y = (block_y * 4 + index / 4).min(height - 1);
+                *texel = texels[y * width + x].map(quantize);
+            }
+            blocks.push(block);
+        }
+    }
+    blocks
+}
+
+/// Supercompression global data of [`SupercompressionScheme::BasisLZ`](crate::SupercompressionScheme::BasisLZ) textures
+///
+/// Contains one [`ImageDesc`] per image, ordered by level (starting with the base level), layer, face and z slice.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BasisLzGlobalData {
+    pub endpoint_count: u16,
+    pub selector_count: u16,
+    pub image_descs: Vec<ImageDesc>,
+    pub endpoints_data: Vec<u8>,
+    pub selectors_data: Vec<u8>,
+    pub tables_data: Vec<u8>,
+    pub extended_data: Vec<u8>,
+}
+
+impl BasisLzGlobalData {
+    const HEADER_LENGTH: usize = 20;
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, ParseError> {
+        if bytes.len() < Self::HEADER_LENGTH {
+            return Err(ParseError::UnexpectedEnd);
+        }
+        let endpoint_count = u16::from_le_bytes(bytes[0..2].try_into().unwrap());
+        let selector_count = u16::from_le_bytes(bytes[2..4].try_into().unwrap());
+        let mut offset = 4;
+        let endpoints_byte_length = crate::bytes_to_u32(bytes, &mut offset)? as usize;
+        let selectors_byte_length = crate::bytes_to_u32(bytes, &mut offset)? as usize;
+        let tables_byte_length = crate::bytes_to_u32(bytes, &mut offset)? as usize;
+        let extended_byte_length = crate::bytes_to_u32(bytes, &mut offset)? as usize;
+
+        // The image count isn't stored, so derive it from the remaining length
+        let data_length = endpoints_byte_length
+            .checked_add(selectors_byte_length)
+            .and_then(|length| length.checked_add(tables_byte_length))
+            .and_then(|length| length.checked_add(extended_byte_length))
+            .ok_or(ParseError::UnexpectedEnd)?;
+        let image_descs_length = (bytes.len() - Self::HEADER_LENGTH)
+            .checked_sub(data_length)
+            .ok_or(ParseError::UnexpectedEnd)?;
+        if image_descs_length % ImageDesc::LENGTH != 0 {
+            return Err(ParseError::UnexpectedEnd);
+        }
+        let image_descs = bytes[Self::HEADER_LENGTH..Self::HEADER_LENGTH + image_descs_length]
+            .chunks_exact(ImageDesc::LENGTH)
+            .map(|data| ImageDesc::from_bytes(data.try_into().unwrap()))
+            .collect();
+
+        let mut data = &bytes[bytes.len() - data_length..];
+        let mut take = |length: usize| {
+            let (taken, rest) = data.split_at(length);
+            data = rest;
+            taken.to_vec()
+        };
+
+        Ok(Self {
+            endpoint_count,
+            selector_count,
+            image_descs,
+            endpoints_data: take(endpoints_byte_length),
+            selectors_data: take(selectors_byte_length),
+            tables_data: take(tables_byte_length),
+            extended_data: take(extended_byte_length),
+        })
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.endpoint_count.to_le_bytes());
+        bytes.extend_from_slice(&self.selector_count.to_le_bytes());
+        bytes.extend_from_slice(&(self.endpoints_data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.selectors_data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.tables_data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.extended_data.len() as u32).to_le_bytes());
+        for image_desc in &self.image_descs {
+            bytes.extend_from_slice(&image_desc.as_bytes());
+        }
+        bytes.extend_from_slice(&self.endpoints_data);
+        bytes.extend_from_slice(&self.selectors_data);
+        bytes.extend_from_slice(&self.tables_data);
+        bytes.extend_from_slice(&self.extended_data);
+        bytes
+    }
+}
+
+bitflags::bitflags! {
+    #[derive(Default)]
+    #[repr(transparent)]
+    pub struct ImageFlags: u32 {
+        const IS_P_FRAME = (1 << 1);
+    }
+}
+
+/// Location of the slices of a single image within its level
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ImageDesc {
+    pub image_flags: ImageFlags,
+    pub rgb_slice_byte_offset: u32,
+    pub rgb_slice_byte_length: u32,
+    pub alpha_slice_byte_offset: u32,
+    pub alpha_slice_byte_length: u32,
+}
+
+impl ImageDesc {
+    pub const LENGTH: usize = 20;
+
+    pub fn from_bytes(data: &[u8; Self::LENGTH]) -> Self {
+        Self {
+            image_flags: ImageFlags::from_bits_truncate(u32::from_le_bytes(data[0..4].try_into().unwrap())),
+            rgb_slice_byte_offset: u32::from_le_bytes(data[4..8].try_into().unwrap()),
+            rgb_slice_byte_length: u32::from_le_bytes(data[8..12].try_into().unwrap()),
+            alpha_slice_byte_offset: u32::from_le_bytes(data[12..16].try_into().unwrap()),
+            alpha_slice_byte_length: u32::from_le_bytes(data[16..20].try_into().unwrap()),
+        }
+    }
+
+    pub fn as_bytes(&self) -> [u8; Self::LENGTH] {
+        let mut bytes = [0; Self::LENGTH];
+
+        bytes[0..4].copy_from_slice(&self.image_flags.bits().to_le_bytes()[..]);
+        bytes[4..8].copy_from_slice(&self.rgb_slice_byte_offset.to_le_bytes()[..]);
+        bytes[8..12].copy_from_slice(&self.rgb_slice_byte_length.to_le_bytes()[..]);
+        bytes[12..16].copy_from_slice(&self.alpha_slice_byte_offset.to_le_bytes()[..]);
+        bytes[16..20].copy_from_slice(&self.alpha_slice_byte_length.to_le_bytes()[..]);
+
+        bytes
+    }
+}
+
+#[test]
+fn test_basis_lz_global_data_round_trip() {
+    let global_data = BasisLzGlobalData {
+        endpoint_count: 3,
+        selector_count: 5,
+        image_descs: std::vec![
+            ImageDesc {
+                image_flags: ImageFlags::empty(),
+                rgb_slice_byte_offset: 0,
+                rgb_slice_byte_length: 40,
+                alpha_slice_byte_offset: 40,
+                alpha_slice_byte_length: 12,
+            },
+            ImageDesc {
+                image_flags: ImageFlags::IS_P_FRAME,
+                rgb_slice_byte_offset: 0,
+                rgb_slice_byte_length: 8,
+                alpha_slice_byte_offset: 8,
+                alpha_slice_byte_length: 4,
+            },
+        ],
+        endpoints_data: std::vec![1; 7],
+        selectors_data: std::vec![2; 9],
+        tables_data: std::vec![3; 2],
+        extended_data: std::vec![],
+    };
+
+    let bytes = global_data.as_bytes();
+    assert_eq!(bytes.len(), 20 + 2 * 20 + 7 + 9 + 2);
+    assert_eq!(BasisLzGlobalData::parse(&bytes).unwrap(), global_data);
+
+    // A trailing partial image descriptor
+    let mut truncated = bytes[..20 + 20].to_vec();
+    truncated.extend_from_slice(&[0; 7 + 9 + 2 + 1]);
+    assert!(matches!(
+        BasisLzGlobalData::parse(&truncated),
+        Err(ParseError::UnexpectedEnd)
+    ));
+}
@@ -0,0 +1,158 @@
+//! Decoders for BC1 to BC5 blocks, following the
+//! [Direct3D 11 specification](https://learn.microsoft.com/en-us/windows/win32/direct3d10/d3d10-graphics-programming-guide-resources-block-compression).
+//!
+//! Texels are written in row-major order as RGBA values in the format's range, with missing channels set to 0 and
+//! missing alpha to 1.
+
+use core::convert::TryInto;
+
+/// Decode a BC1 block, whose fourth color in three-color mode is transparent black if `alpha` is set and opaque
+/// black otherwise
+pub(crate) fn decode_bc1(block: &[u8], alpha: bool, texels: &mut [[f32; 4]]) {
+    decode_color(block, false, texels);
+    if !alpha {
+        texels.iter_mut().for_each(|texel| texel[3] = 1.0);
+    }
+}
+
+/// Decode a BC2 block, with explicit 4-bit alpha
+pub(crate) fn decode_bc2(block: &[u8], texels: &mut [[f32; 4]]) {
+    decode_color(&block[8..], true, texels);
+    let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+    for (index, texel) in texels.iter_mut().enumerate() {
+        texel[3] = (alpha >> (index * 4) & 0xF) as f32 / 15.0;
+    }
+}
+
+/// Decode a BC3 block, with interpolated alpha
+pub(crate) fn decode_bc3(block: &[u8], texels: &mut [[f32; 4]]) {
+    decode_color(&block[8..], true, texels);
+    for (texel, alpha) in texels.iter_mut().zip(decode_channel(&block[..8], false)) {
+        texel[3] = alpha;
+    }
+}
+
+/// Decode a BC4 block into red
+pub(crate) fn decode_bc4(block: &[u8], signed: bool, texels: &mut [[f32; 4]]) {
+    for (texel, red) in texels.iter_mut().zip(decode_channel(block, signed)) {
+        *texel = [red, 0.0, 0.0, 1.0];
+    }
+}
+
+/// Decode a BC5 block into red and green
+pub(crate) fn decode_bc5(block: &[u8], signed: bool, texels: &mut [[f32; 4]]) {
+    let red = decode_channel(&block[..8], signed);
+    let green = decode_channel(&block[8..], signed);
+    for (index, texel) in texels.iter_mut().enumerate() {
+        *texel = [red[index], green[index], 0.0, 1.0];
+    }
+}
+
+/// Decode the 8-byte color part of BC1, BC2 and BC3 blocks
+///
+/// BC2 and BC3 always use four colors, while BC1 uses three colors and transparent black when the first endpoint
+/// isn't greater than the second.
+fn decode_color(block: &[u8], four_colors: bool, texels: &mut [[f32; 4]]) {
+    let endpoints = [
+        u16::from_le_bytes([block[0], block[1]]),
+        u16::from_le_bytes([block[2], block[3]]),
+    ];
+    let [c0, c1] = endpoints.map(|color| {
+        let (r, g, b) = (
+            u32::from(color >> 11),
+            u32::from(color >> 5 & 0x3F),
+            u32::from(color & 0x1F),
+        );
+        [r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2, 255]
+    });
+    let mix = |weight0: u32, weight1: u32, divisor: u32| {
+        [0, 1, 2].map(|channel| (c0[channel] * weight0 + c1[channel] * weight1) / divisor)
+    };
+    let colors = if four_colors || endpoints[0] > endpoints[1] {
+        let [c2, c3] = [mix(2, 1, 3), mix(1, 2, 3)];
+        [c0, c1, [c2[0], c2[1], c2[2], 255], [c3[0], c3[1], c3[2], 255]]
+    } else {
+        let c2 = mix(1, 1, 2);
+        [c0, c1, [c2[0], c2[1], c2[2], 255], [0; 4]]
+    };
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    for (index, texel) in texels.iter_mut().enumerate() {
+        *texel = colors[(indices >> (index * 2) & 0x3) as usize].map(|value| value as f32 / 255.0);
+    }
+}
+
+/// Decode an 8-byte block of a single interpolated channel, as used for alpha in BC3 and for BC4 and BC5
+fn decode_channel(block: &[u8], signed: bool) -> [f32; 16] {
+    let endpoint = |byte: u8| {
+        if signed {
+            i32::from(byte as i8).max(-127)
+        } else {
+            i32::from(byte)
+        }
+    };
+    let (e0, e1) = (endpoint(block[0]), endpoint(block[1]));
+    let (min, max) = if signed { (-127, 127) } else { (0, 255) };
+    let mut values = [e0, e1, 0, 0, 0, 0, 0, 0];
+    if e0 > e1 {
+        for (index, value) in values.iter_mut().enumerate().skip(2) {
+            let index = index as i32;
+            *value = ((8 - index) * e0 + (index - 1) * e1) / 7;
+        }
+    } else {
+        for (index, value) in values.iter_mut().enumerate().take(6).skip(2) {
+            let index = index as i32;
+            *value = ((6 - index) * e0 + (index - 1) * e1) / 5;
+        }
+        values[6] = min;
+        values[7] = max;
+    }
+
+    let mut indices = [0; 8];
+    indices[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(indices);
+    let mut texels = [0.0; 16];
+    for (index, texel) in texels.iter_mut().enumerate() {
+        *texel = values[(indices >> (index * 3) & 0x7) as usize] as f32 / max as f32;
+    }
+    texels
+}
+
+#[test]
+fn test_decode_bc_blocks() {
+    // Red and blue endpoints, with the second row selecting the first interpolated color
+    let mut block = [0x00, 0xF8, 0x1F, 0x00, 0, 0xAA, 0, 0];
+    let mut texels = [[0.0; 4]; 16];
+    decode_bc1(&block, false, &mut texels);
+    assert_eq!(texels[0], [1.0, 0.0, 0.0, 1.0]);
+    assert_eq!(texels[4], [170.0 / 255.0, 0.0, 85.0 / 255.0, 1.0]);
+
+    // Swapped endpoints select three-color mode, whose fourth color is transparent only with alpha
+    block[..4].copy_from_slice(&[0x1F, 0x00, 0x00, 0xF8]);
+    block[4] = 0xFF;
+    decode_bc1(&block, true, &mut texels);
+    assert_eq!(texels[0], [0.0; 4]);
+    assert_eq!(texels[4], [127.0 / 255.0, 0.0, 127.0 / 255.0, 1.0]);
+    decode_bc1(&block, false, &mut texels);
+    assert_eq!(texels[0], [0.0, 0.0, 0.0, 1.0]);
+
+    let mut bc2 = [0; 16];
+    bc2[0] = 0x5F;
+    decode_bc2(&bc2, &mut texels);
+    assert_eq!([texels[0][3], texels[1][3], texels[2][3]], [1.0, 5.0 / 15.0, 0.0]);
+
+    // Eight interpolated values, then six with both extremes
+    let mut bc4 = [255, 0, 0b0001_1010, 0, 0, 0, 0, 0];
+    decode_bc4(&bc4, false, &mut texels);
+    assert_eq!(texels[0], [218.0 / 255.0, 0.0, 0.0, 1.0]);
+    assert_eq!(texels[1], [182.0 / 255.0, 0.0, 0.0, 1.0]);
+    assert_eq!(texels[2], [1.0, 0.0, 0.0, 1.0]);
+    bc4[..3].copy_from_slice(&[0x81, 0x7F, 0b0011_1110]);
+    decode_bc4(&bc4, true, &mut texels);
+    assert_eq!([texels[0][0], texels[1][0]], [-1.0, 1.0]);
+
+    let mut bc5 = [0; 16];
+    bc5[0] = 255;
+    decode_bc5(&bc5, false, &mut texels);
+    assert_eq!(texels[15], [1.0, 0.0, 0.0, 1.0]);
+}
@@ -0,0 +1,293 @@
+//! Decoder for BC6H blocks, following the
+//! [Direct3D 11 specification](https://learn.microsoft.com/en-us/windows/win32/direct3d11/bc6h-format).
+
+// Endpoint components, indexed by endpoint * 3 + channel
+const R0: u8 = 0;
+const G0: u8 = 1;
+const B0: u8 = 2;
+const R1: u8 = 3;
+const G1: u8 = 4;
+const B1: u8 = 5;
+const R2: u8 = 6;
+const G2: u8 = 7;
+const B2: u8 = 8;
+const R3: u8 = 9;
+const G3: u8 = 10;
+const B3: u8 = 11;
+
+/// Layout of a block mode
+struct Mode {
+    code: u8,
+    two_regions: bool,
+    transformed: bool,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    /// Endpoint bits in stream order as `(component, a, b)`, reading bits `b` to `a` of the component. `a < b` for
+    /// fields whose bits are stored in reverse.
+    fields: &'static [(u8, u8, u8)],
+}
+
+#[rustfmt::skip]
+const MODES: [Mode; 14] = [
+    Mode { code: 0b00, two_regions: true, transformed: true, endpoint_bits: 10, delta_bits: [5, 5, 5], fields: &[
+        (G2, 4, 4), (B2, 4, 4), (B3, 4, 4), (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 4, 0), (G3, 4, 4), (G2, 3, 0),
+        (G1, 4, 0), (B3, 0, 0), (G3, 3, 0), (B1, 4, 0), (B3, 1, 1), (B2, 3, 0), (R2, 4, 0), (B3, 2, 2), (R3, 4, 0),
+        (B3, 3, 3),
+    ] },
+    Mode { code: 0b01, two_regions: true, transformed: true, endpoint_bits: 7, delta_bits: [6, 6, 6], fields: &[
+        (G2, 5, 5), (G3, 4, 4), (G3, 5, 5), (R0, 6, 0), (B3, 0, 0), (B3, 1, 1), (B2, 4, 4), (G0, 6, 0), (B2, 5, 5),
+        (B3, 2, 2), (G2, 4, 4), (B0, 6, 0), (B3, 3, 3), (B3, 5, 5), (B3, 4, 4), (R1, 5, 0), (G2, 3, 0), (G1, 5, 0),
+        (G3, 3, 0), (B1, 5, 0), (B2, 3, 0), (R2, 5, 0), (R3, 5, 0),
+    ] },
+    Mode { code: 0b00010, two_regions: true, transformed: true, endpoint_bits: 11, delta_bits: [5, 4, 4], fields: &[
+        (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 4, 0), (R0, 10, 10), (G2, 3, 0), (G1, 3, 0), (G0, 10, 10),
+        (B3, 0, 0), (G3, 3, 0), (B1, 3, 0), (B0, 10, 10), (B3, 1, 1), (B2, 3, 0), (R2, 4, 0), (B3, 2, 2), (R3, 4, 0),
+        (B3, 3, 3),
+    ] },
+    Mode { code: 0b00110, two_regions: true, transformed: true, endpoint_bits: 11, delta_bits: [4, 5, 4], fields: &[
+        (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 3, 0), (R0, 10, 10), (G3, 4, 4), (G2, 3, 0), (G1, 4, 0),
+        (G0, 10, 10), (G3, 3, 0), (B1, 3, 0), (B0, 10, 10), (B3, 1, 1), (B2, 3, 0), (R2, 3, 0), (B3, 0, 0),
+        (B3, 2, 2), (R3, 3, 0), (G2, 4, 4), (B3, 3, 3),
+    ] },
+    Mode { code: 0b01010, two_regions: true, transformed: true, endpoint_bits: 11, delta_bits: [4, 4, 5], fields: &[
+        (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 3, 0), (R0, 10, 10), (B2, 4, 4), (G2, 3, 0), (G1, 3, 0),
+        (G0, 10, 10), (B3, 0, 0), (G3, 3, 0), (B1, 4, 0), (B0, 10, 10), (B2, 3, 0), (R2, 3, 0), (B3, 1, 1),
+        (B3, 2, 2), (R3, 3, 0), (B3, 4, 4), (B3, 3, 3),
+    ] },
+    Mode { code: 0b01110, two_regions: true, transformed: true, endpoint_bits: 9, delta_bits: [5, 5, 5], fields: &[
+        (R0, 8, 0), (B2, 4, 4), (G0, 8, 0), (G2, 4, 4), (B0, 8, 0), (B3, 4, 4), (R1, 4, 0), (G3, 4, 4), (G2, 3, 0),
+        (G1, 4, 0), (B3, 0, 0), (G3, 3, 0), (B1, 4, 0), (B3, 1, 1), (B2, 3, 0), (R2, 4, 0), (B3, 2, 2), (R3, 4, 0),
+        (B3, 3, 3),
+    ] },
+    Mode { code: 0b10010, two_regions: true, transformed: true, endpoint_bits: 8, delta_bits: [6, 5, 5], fields: &[
+        (R0, 7, 0), (G3, 4, 4), (B2, 4, 4), (G0, 7, 0), (B3, 2, 2), (G2, 4, 4), (B0, 7, 0), (B3, 3, 3), (B3, 4, 4),
+        (R1, 5, 0), (G2, 3, 0), (G1, 4, 0), (B3, 0, 0), (G3, 3, 0), (B1, 4, 0), (B3, 1, 1), (B2, 3, 0), (R2, 5, 0),
+        (R3, 5, 0),
+    ] },
+    Mode { code: 0b10110, two_regions: true, transformed: true, endpoint_bits: 8, delta_bits: [5, 6, 5], fields: &[
+        (R0, 7, 0), (B3, 0, 0), (B2, 4, 4), (G0, 7, 0), (G2, 5, 5), (G2, 4, 4), (B0, 7, 0), (G3, 5, 5), (B3, 4, 4),
+        (R1, 4, 0), (G3, 4, 4), (G2, 3, 0), (G1, 5, 0), (G3, 3, 0), (B1, 4, 0), (B3, 1, 1), (B2, 3, 0), (R2, 4, 0),
+        (B3, 2, 2), (R3, 4, 0), (B3, 3, 3),
+    ] },
+    Mode { code: 0b11010, two_regions: true, transformed: true, endpoint_bits: 8, delta_bits: [5, 5, 6], fields: &[
+        (R0, 7, 0), (B3, 1, 1), (B2, 4, 4), (G0, 7, 0), (B2, 5, 5), (G2, 4, 4), (B0, 7, 0), (B3, 5, 5), (B3, 4, 4),
+        (R1, 4, 0), (G3, 4, 4), (G2, 3, 0), (G1, 4, 0), (B3, 0, 0), (G3, 3, 0), (B1, 5, 0), (B2, 3, 0), (R2, 4, 0),
+        (B3, 2, 2), (R3, 4, 0), (B3, 3, 3),
+    ] },
+    Mode { code: 0b11110, two_regions: true, transformed: false, endpoint_bits: 6, delta_bits: [6, 6, 6], fields: &[
+        (R0, 5, 0), (G3, 4, 4), (B3, 0, 0), (B3, 1, 1), (B2, 4, 4), (G0, 5, 0), (G2, 5, 5), (B2, 5, 5), (B3, 2, 2),
+        (G2, 4, 4), (B0, 5, 0), (G3, 5, 5), (B3, 3, 3), (B3, 5, 5), (B3, 4, 4), (R1, 5, 0), (G2, 3, 0), (G1, 5, 0),
+        (G3, 3, 0), (B1, 5, 0), (B2, 3, 0), (R2, 5, 0), (R3, 5, 0),
+    ] },
+    Mode { code: 0b00011, two_regions: false, transformed: false, endpoint_bits: 10, delta_bits: [10, 10, 10], fields: &[
+        (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 9, 0), (G1, 9, 0), (B1, 9, 0),
+    ] },
+    Mode { code: 0b00111, two_regions: false, transformed: true, endpoint_bits: 11, delta_bits: [9, 9, 9], fields: &[
+        (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 8, 0), (R0, 10, 10), (G1, 8, 0), (G0, 10, 10), (B1, 8, 0),
+        (B0, 10, 10),
+    ] },
+    Mode { code: 0b01011, two_regions: false, transformed: true, endpoint_bits: 12, delta_bits: [8, 8, 8], fields: &[
+        (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 7, 0), (R0, 10, 11), (G1, 7, 0), (G0, 10, 11), (B1, 7, 0),
+        (B0, 10, 11),
+    ] },
+    Mode { code: 0b01111, two_regions: false, transformed: true, endpoint_bits: 16, delta_bits: [4, 4, 4], fields: &[
+        (R0, 9, 0), (G0, 9, 0), (B0, 9, 0), (R1, 3, 0), (R0, 10, 15), (G1, 3, 0), (G0, 10, 15), (B1, 3, 0),
+        (B0, 10, 15),
+    ] },
+];
+
+/// Two-region partitions, with a set bit for each texel in the second region
+const PARTITIONS: [u16; 32] = [
+    0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80, 0xC800, 0xFFEC, 0xFE80, 0xE800, 0xFFE8, 0xFF00,
+    0xFFF0, 0xF000, 0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE, 0x088C, 0x3110, 0x6666, 0x366C,
+    0x17E8, 0x0FF0, 0x718E, 0x399C,
+];
+
+/// Index of the texel in the second region whose index has an implicit leading zero
+const ANCHORS: [usize; 32] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+];
+
+const WEIGHTS_3: [i32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [i32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+struct Bits {
+    value: u128,
+}
+
+impl Bits {
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.value & ((1 << count) - 1)) as u32;
+        self.value >>= count;
+        value
+    }
+}
+
+/// Decode a 16-byte block into half float RGB texels in row-major order
+///
+/// Blocks with reserved modes decode to black, as required by the specification.
+pub(crate) fn decode_block(block: &[u8; 16], signed: bool) -> [[u16; 3]; 16] {
+    let mut bits = Bits {
+        value: u128::from_le_bytes(*block),
+    };
+    let mut code = bits.read(2) as u8;
+    if code & 0b10 != 0 {
+        code |= (bits.read(3) as u8) << 2;
+    }
+    let mode = match MODES.iter().find(|mode| mode.code == code) {
+        Some(mode) => mode,
+        None => return [[0; 3]; 16],
+    };
+
+    let mut endpoints = [0i32; 12];
+    for &(component, a, b) in mode.fields {
+        let (a, b) = (i32::from(a), i32::from(b));
+        let step = if a >= b { 1 } else { -1 };
+        let mut bit = b;
+        loop {
+            endpoints[component as usize] |= (bits.read(1) as i32) << bit;
+            if bit == a {
+                break;
+            }
+            bit += step;
+        }
+    }
+
+    let endpoint_count = if mode.two_regions { 4 } else { 2 };
+    let endpoint_mask = (1 << mode.endpoint_bits) - 1;
+    for channel in 0..3 {
+        if signed {
+            endpoints[channel] = sign_extend(endpoints[channel], mode.endpoint_bits);
+        }
+        let base = endpoints[channel];
+        for endpoint in 1..endpoint_count {
+            let value = &mut endpoints[endpoint * 3 + channel];
+            if mode.transformed {
+                *value = (sign_extend(*value, mode.delta_bits[channel]) + base) & endpoint_mask;
+                if signed {
+                    *value = sign_extend(*value, mode.endpoint_bits);
+                }
+            } else if signed {
+                *value = sign_extend(*value, mode.endpoint_bits);
+            }
+        }
+    }
+    let endpoints = endpoints.map(|value| unquantize(value, mode.endpoint_bits, signed));
+
+    let (partition, index_bits) = if mode.two_regions {
+        (bits.read(5) as usize, 3)
+    } else {
+        (0, 4)
+    };
+    let mut texels = [[0; 3]; 16];
+    for (texel_index, texel) in texels.iter_mut().enumerate() {
+        let region = if mode.two_regions {
+            usize::from(PARTITIONS[partition] >> texel_index & 1 != 0)
+        } else {
+            0
+        };
+        let anchor = texel_index == 0 || (mode.two_regions && texel_index == ANCHORS[partition]);
+        let index = bits.read(index_bits - anchor as u32) as usize;
+        let weight = if mode.two_regions {
+            WEIGHTS_3[index]
+        } else {
+            WEIGHTS_4[index]
+        };
+
+        for (channel, value) in texel.iter_mut().enumerate() {
+            let start = endpoints[region * 6 + channel];
+            let end = endpoints[region * 6 + 3 + channel];
+            *value = finish_unquantize((start * (64 - weight) + end * weight + 32) >> 6, signed);
+        }
+    }
+    texels
+}
+
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    (value << shift) >> shift
+}
+
+fn unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+    if !signed {
+        if bits >= 15 {
+            value
+        } else if value == 0 {
+            0
+        } else if value == (1 << bits) - 1 {
+            0xFFFF
+        } else {
+            ((value << 16) + 0x8000) >> bits
+        }
+    } else if bits >= 16 {
+        value
+    } else {
+        let magnitude = value.abs();
+        let unquantized = if magnitude == 0 {
+            0
+        } else if magnitude >= (1 << (bits - 1)) - 1 {
+            0x7FFF
+        } else {
+            ((magnitude << 15) + 0x4000) >> (bits - 1)
+        };
+        unquantized * value.signum()
+    }
+}
+
+/// Scale an interpolated value into the bit pattern of a half float
+fn finish_unquantize(value: i32, signed: bool) -> u16 {
+    if !signed {
+        ((value * 31) >> 6) as u16
+    } else {
+        let magnitude = (value.abs() * 31) >> 5;
+        if value < 0 {
+            0x8000 | magnitude as u16
+        } else {
+            magnitude as u16
+        }
+    }
+}
+
+#[test]
+fn test_decode_bc6h_block() {
+    // Mode 11 with a maximum first endpoint, a zero second endpoint and all indices selecting the first endpoint
+    let block = (0b00011u128 | (0x3FF << 5) | (0x3FF << 15) | (0x3FF << 25)).to_le_bytes();
+    assert_eq!(decode_block(&block, false), [[0x7BFF; 3]; 16]);
+
+    // Mode 1 with deltas: the second region's first endpoint is 16 below the base endpoint of 512
+    let mut value = 0u128;
+    let mut offset = 2;
+    let mut push = |bits: u128, count: u32| {
+        value |= bits << offset;
+        offset += count;
+    };
+    push(0, 3);
+    for _ in 0..3 {
+        push(512, 10);
+    }
+    // r1, g3[4], g2, g1, b3[0], g3, b1, b3[1], b2, r2, b3[2], r3, b3[3]
+    for (field, count) in [
+        (0, 5),
+        (0, 1),
+        (0, 4),
+        (0, 5),
+        (0, 1),
+        (0, 4),
+        (0, 5),
+        (0, 1),
+        (0, 4),
+        (0b10000, 5),
+        (0, 1),
+        (0, 5),
+        (0, 1),
+    ] {
+        push(field, count);
+    }
+    assert_eq!(offset, 77);
+    value |= 13 << 77;
+    let texels = decode_block(&value.to_le_bytes(), false);
+    let expected = |endpoint: i32| finish_unquantize(unquantize(endpoint, 10, false), false);
+    // Partition 13 places the lower two rows in the second region
+    assert_eq!(texels[0], [expected(512); 3]);
+    assert_eq!(texels[15][0], expected(496));
+}
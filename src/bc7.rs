@@ -0,0 +1,268 @@
+//! Decoder for BC7 blocks, following the
+//! [Direct3D 11 specification](https://learn.microsoft.com/en-us/windows/win32/direct3d11/bc7-format-mode-reference).
+
+use core::convert::TryInto;
+
+/// Layout of a block mode
+struct Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    /// Whether each endpoint has its own P-bit
+    endpoint_p_bits: bool,
+    /// Whether each subset has a P-bit shared by its endpoints
+    shared_p_bits: bool,
+    index_bits: u32,
+    secondary_index_bits: u32,
+}
+
+#[rustfmt::skip]
+const MODES: [Mode; 8] = [
+    Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, index_selection_bits: 0, color_bits: 4, alpha_bits: 0,
+        endpoint_p_bits: true, shared_p_bits: false, index_bits: 3, secondary_index_bits: 0 },
+    Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 6, alpha_bits: 0,
+        endpoint_p_bits: false, shared_p_bits: true, index_bits: 3, secondary_index_bits: 0 },
+    Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 0,
+        endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+    Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 0,
+        endpoint_p_bits: true, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+    Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 1, color_bits: 5, alpha_bits: 6,
+        endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 3 },
+    Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 0, color_bits: 7, alpha_bits: 8,
+        endpoint_p_bits: false, shared_p_bits: false, index_bits: 2, secondary_index_bits: 2 },
+    Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 7,
+        endpoint_p_bits: true, shared_p_bits: false, index_bits: 4, secondary_index_bits: 0 },
+    Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 5,
+        endpoint_p_bits: true, shared_p_bits: false, index_bits: 2, secondary_index_bits: 0 },
+];
+
+/// Two-subset partitions, with a set bit for each texel in the second subset
+const PARTITIONS_2: [u16; 64] = [
+    0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80, 0xC800, 0xFFEC, 0xFE80, 0xE800, 0xFFE8, 0xFF00,
+    0xFFF0, 0xF000, 0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE, 0x088C, 0x3110, 0x6666, 0x366C,
+    0x17E8, 0x0FF0, 0x718E, 0x399C, 0xAAAA, 0xF0F0, 0x5A5A, 0x33CC, 0x3C3C, 0x55AA, 0x9696, 0xA55A, 0x73CE, 0x13C8,
+    0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660, 0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C,
+    0x9336, 0x9CC6, 0x817E, 0xE718, 0xCCF0, 0x0FCC, 0x7744, 0xEE22,
+];
+
+/// Three-subset partitions, with two bits holding the subset of each texel
+const PARTITIONS_3: [u32; 64] = [
+    0xAA685050, 0x6A5A5040, 0x5A5A4200, 0x5450A0A8, 0xA5A50000, 0xA0A05050, 0x5555A0A0, 0x5A5A5050, 0xAA550000,
+    0xAA555500, 0xAAAA5500, 0x90909090, 0x94949494, 0xA4A4A4A4, 0xA9A59450, 0x2A0A4250, 0xA5945040, 0x0A425054,
+    0xA5A5A500, 0x55A0A0A0, 0xA8A85454, 0x6A6A4040, 0xA4A45000, 0x1A1A0500, 0x0050A4A4, 0xAAA59090, 0x14696914,
+    0x69691400, 0xA08585A0, 0xAA821414, 0x50A4A450, 0x6A5A0200, 0xA9A58000, 0x5090A0A8, 0xA8A09050, 0x24242424,
+    0x00AA5500, 0x24924924, 0x24499224, 0x50A50A50, 0x500AA550, 0xAAAA4444, 0x66660000, 0xA5A0A5A0, 0x50A050A0,
+    0x69286928, 0x44AAAA44, 0x66666600, 0xAA444444, 0x54A854A8, 0x95809580, 0x96969600, 0xA85454A8, 0x80959580,
+    0xAA141414, 0x96960000, 0xAAAA1414, 0xA05050A0, 0xA0A5A5A0, 0x96000000, 0x40804080, 0xA9A8A9A8, 0xAAAAAA44,
+    0x2A4A5254,
+];
+
+/// Index of the texel in the second subset of two-subset partitions whose index has an implicit leading zero
+#[rustfmt::skip]
+const ANCHORS_2: [usize; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+    15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2, 2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// Anchor texels of the second and third subsets of three-subset partitions
+#[rustfmt::skip]
+const ANCHORS_3: [[usize; 64]; 2] = [
+    [
+        3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3, 3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5, 15, 15,
+        8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15, 3, 15, 5, 5, 5, 8, 5, 10, 5, 10, 8, 13, 15, 12, 3, 3,
+    ],
+    [
+        15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8, 15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6, 10, 15, 15, 10,
+        8, 15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8, 15, 3, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 3, 15,
+        15, 8,
+    ],
+];
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+struct Bits {
+    value: u128,
+}
+
+impl Bits {
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.value & ((1 << count) - 1)) as u32;
+        self.value >>= count;
+        value
+    }
+}
+
+/// Subset of a texel in a partition
+fn subset(subsets: usize, partition: usize, texel: usize) -> usize {
+    match subsets {
+        1 => 0,
+        2 => usize::from(PARTITIONS_2[partition] >> texel & 1 != 0),
+        _ => (PARTITIONS_3[partition] >> (texel * 2) & 0x3) as usize,
+    }
+}
+
+/// Decode a 16-byte block into RGBA texels in row-major order
+///
+/// Blocks with the reserved mode decode to transparent black, as required by the specification.
+pub(crate) fn decode_block(block: &[u8], texels: &mut [[f32; 4]]) {
+    let mut bits = Bits {
+        value: u128::from_le_bytes(block.try_into().unwrap()),
+    };
+    let mode_index = block[0].trailing_zeros();
+    let mode = match MODES.get(mode_index as usize) {
+        Some(mode) => mode,
+        None => return texels.fill([0.0; 4]),
+    };
+    bits.read(mode_index + 1);
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let index_selection = bits.read(mode.index_selection_bits);
+
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..4 {
+        let channel_bits = if channel < 3 { mode.color_bits } else { mode.alpha_bits };
+        for endpoint in &mut endpoints[..endpoint_count] {
+            endpoint[channel] = bits.read(channel_bits);
+        }
+    }
+    let mut p_bits = [0; 6];
+    if mode.endpoint_p_bits {
+        p_bits[..endpoint_count]
+            .iter_mut()
+            .for_each(|p_bit| *p_bit = bits.read(1));
+    } else if mode.shared_p_bits {
+        for subset in 0..mode.subsets {
+            let p_bit = bits.read(1);
+            p_bits[subset * 2..subset * 2 + 2].copy_from_slice(&[p_bit; 2]);
+        }
+    }
+    for (endpoint, p_bit) in endpoints[..endpoint_count].iter_mut().zip(p_bits) {
+        for (channel, value) in endpoint.iter_mut().enumerate() {
+            let mut value_bits = if channel < 3 { mode.color_bits } else { mode.alpha_bits };
+            if value_bits == 0 {
+                *value = 255;
+                continue;
+            }
+            if mode.endpoint_p_bits || mode.shared_p_bits {
+                *value = *value << 1 | p_bit;
+                value_bits += 1;
+            }
+            *value <<= 8 - value_bits;
+            *value |= *value >> value_bits;
+        }
+    }
+
+    let anchor = |texel: usize| match mode.subsets {
+        1 => texel == 0,
+        2 => texel == 0 || texel == ANCHORS_2[partition],
+        _ => texel == 0 || ANCHORS_3.iter().any(|anchors| texel == anchors[partition]),
+    };
+    let mut indices = [0; 16];
+    for (texel, index) in indices.iter_mut().enumerate() {
+        *index = bits.read(mode.index_bits - u32::from(anchor(texel))) as usize;
+    }
+    let mut secondary_indices = [0; 16];
+    if mode.secondary_index_bits != 0 {
+        for (texel, index) in secondary_indices.iter_mut().enumerate() {
+            *index = bits.read(mode.secondary_index_bits - u32::from(texel == 0)) as usize;
+        }
+    }
+
+    let weights = |index_bits: u32| match index_bits {
+        2 => &WEIGHTS_2[..],
+        3 => &WEIGHTS_3[..],
+        _ => &WEIGHTS_4[..],
+    };
+    for (texel_index, texel) in texels.iter_mut().enumerate().take(16) {
+        let subset = subset(mode.subsets, partition, texel_index);
+        let (start, end) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+        let primary = weights(mode.index_bits)[indices[texel_index]];
+        let (color_weight, alpha_weight) = if mode.secondary_index_bits == 0 {
+            (primary, primary)
+        } else {
+            let secondary = weights(mode.secondary_index_bits)[secondary_indices[texel_index]];
+            if index_selection == 0 {
+                (primary, secondary)
+            } else {
+                (secondary, primary)
+            }
+        };
+
+        let mut rgba = [0; 4];
+        for (channel, value) in rgba.iter_mut().enumerate() {
+            let weight = if channel < 3 { color_weight } else { alpha_weight };
+            *value = (start[channel] * (64 - weight) + end[channel] * weight + 32) >> 6;
+        }
+        if rotation != 0 {
+            rgba.swap(rotation as usize - 1, 3);
+        }
+        *texel = rgba.map(|value| value as f32 / 255.0);
+    }
+}
+
+#[test]
+fn test_partitions_contain_anchors() {
+    for partition in 0..64 {
+        assert_eq!(subset(2, partition, ANCHORS_2[partition]), 1);
+        assert_eq!(subset(3, partition, ANCHORS_3[0][partition]), 1);
+        assert_eq!(subset(3, partition, ANCHORS_3[1][partition]), 2);
+        assert_eq!(subset(3, partition, 0), 0);
+    }
+}
+
+#[test]
+fn test_decode_bc7_block() {
+    let mut value = 0u128;
+    let mut offset = 0;
+    let mut push = |bits: u128, count: u32| {
+        value |= bits << offset;
+        offset += count;
+    };
+
+    // Mode 6 with white and black endpoints, the second texel selecting the second endpoint
+    push(1 << 6, 7);
+    for _ in 0..4 {
+        push(0x7F, 7);
+        push(0, 7);
+    }
+    push(1, 1);
+    push(0, 1);
+    push(0, 3);
+    push(0xF, 4);
+    let mut texels = [[0.0; 4]; 16];
+    decode_block(&value.to_le_bytes(), &mut texels);
+    assert_eq!(texels[0], [1.0; 4]);
+    assert_eq!(texels[1], [0.0; 4]);
+
+    // Mode 5 with rotation 1 swaps red and alpha, which uses the secondary indices
+    value = 0;
+    offset = 0;
+    let mut push = |bits: u128, count: u32| {
+        value |= bits << offset;
+        offset += count;
+    };
+    push(1 << 5, 6);
+    push(1, 2);
+    for _ in 0..3 {
+        push(0x7F, 7);
+        push(0, 7);
+    }
+    push(0xFF, 8);
+    push(0x00, 8);
+    push(0, 31);
+    push(0, 1);
+    push(0b11, 2);
+    decode_block(&value.to_le_bytes(), &mut texels);
+    assert_eq!(texels[0], [1.0, 1.0, 1.0, 1.0]);
+    assert_eq!(texels[1], [0.0, 1.0, 1.0, 1.0]);
+
+    // The reserved mode
+    decode_block(&[0; 16], &mut texels);
+    assert_eq!(texels[0], [0.0; 4]);
+}
@@ -0,0 +1,172 @@
+use crate::ColorModel;
+
+/// Meaning of a sample's `channel_type`, which depends on the color model of its data format descriptor
+///
+/// Channels shared between color models, such as [`Channel::Alpha`], use the same variant.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Stencil,
+    Depth,
+    Alpha,
+    /// Luma of YUV, YIQ, YCgCo and YcCbcCrc, and the Y coordinate of XYZ models
+    Y,
+    U,
+    V,
+    I,
+    Q,
+    /// L of CIELAB and HSL
+    Lightness,
+    /// a* of CIELAB
+    GreenRed,
+    /// b* of CIELAB
+    BlueYellow,
+    Cyan,
+    Magenta,
+    Yellow,
+    Black,
+    X,
+    Z,
+    W,
+    Hue,
+    Saturation,
+    Value,
+    Cg,
+    Co,
+    Cb,
+    Cr,
+    Ct,
+    Cp,
+    /// y chromaticity of CIE xyY
+    YChroma,
+    /// Y luminance of CIE xyY
+    YLuma,
+    /// Color of a block-compressed format that encodes all color channels together
+    Color,
+    /// Block-compressed data without a color interpretation, such as BC4 and ASTC
+    Data,
+    /// ETC1S slice holding RGB
+    Rgb,
+    /// ETC1S or UASTC data of a single channel replicated to RGB
+    Rrr,
+    /// ETC1S slice holding green replicated to RGB
+    Ggg,
+    /// ETC1S slice holding alpha replicated to RGB
+    Aaa,
+    /// UASTC data holding RGBA
+    Rgba,
+    /// UASTC data holding red replicated to RGB, with green as alpha
+    Rrrg,
+    /// UASTC data holding red and green
+    Rg,
+    /// Channel type which isn't defined for the color model
+    Unknown(u32),
+}
+
+impl Channel {
+    /// Interpret the `channel_type` of a sample of a descriptor with the given color model
+    pub fn new(color_model: Option<ColorModel>, channel_type: u32) -> Self {
+        let color_model = match color_model {
+            Some(color_model) => color_model,
+            None => return Self::Unknown(channel_type),
+        };
+        match (color_model, channel_type) {
+            (ColorModel::RGBSDA | ColorModel::YUVSDA | ColorModel::YIQSDA | ColorModel::LabSDA, 13) => Self::Stencil,
+            (ColorModel::RGBSDA | ColorModel::YUVSDA | ColorModel::YIQSDA | ColorModel::LabSDA, 14) => Self::Depth,
+            (
+                ColorModel::RGBSDA
+                | ColorModel::YUVSDA
+                | ColorModel::YIQSDA
+                | ColorModel::LabSDA
+                | ColorModel::CMYKA
+                | ColorModel::HSVAAng
+                | ColorModel::HSLAAng
+                | ColorModel::HSVAHex
+                | ColorModel::HSLAHex
+                | ColorModel::YCgCoA
+                | ColorModel::YcCbcCrc
+                | ColorModel::ICtCp
+                | ColorModel::BC2
+                | ColorModel::BC3
+                | ColorModel::ETC2,
+                15,
+            ) => Self::Alpha,
+            (ColorModel::RGBSDA, 0) => Self::Red,
+            (ColorModel::RGBSDA, 1) => Self::Green,
+            (ColorModel::RGBSDA, 2) => Self::Blue,
+            (ColorModel::YUVSDA | ColorModel::YIQSDA | ColorModel::YCgCoA | ColorModel::YcCbcCrc, 0) => Self::Y,
+            (ColorModel::YUVSDA, 1) => Self::U,
+            (ColorModel::YUVSDA, 2) => Self::V,
+            (ColorModel::YIQSDA, 1) => Self::I,
+            (ColorModel::YIQSDA, 2) => Self::Q,
+            (ColorModel::LabSDA, 0) => Self::Lightness,
+            (ColorModel::LabSDA, 1) => Self::GreenRed,
+            (ColorModel::LabSDA, 2) => Self::BlueYellow,
+            (ColorModel::CMYKA, 0) => Self::Cyan,
+            (ColorModel::CMYKA, 1) => Self::Magenta,
+            (ColorModel::CMYKA, 2) => Self::Yellow,
+            (ColorModel::CMYKA, 3) => Self::Black,
+            (ColorModel::XYZW | ColorModel::CIEXYZ | ColorModel::CIEXYY, 0) => Self::X,
+            (ColorModel::XYZW | ColorModel::CIEXYZ, 1) => Self::Y,
+            (ColorModel::XYZW | ColorModel::CIEXYZ, 2) => Self::Z,
+            (ColorModel::XYZW, 3) => Self::W,
+            (ColorModel::HSVAAng | ColorModel::HSVAHex, 0) => Self::Value,
+            (ColorModel::HSLAAng | ColorModel::HSLAHex, 0) => Self::Lightness,
+            (ColorModel::HSVAAng | ColorModel::HSVAHex | ColorModel::HSLAAng | ColorModel::HSLAHex, 1) => {
+                Self::Saturation
+            }
+            (ColorModel::HSVAAng | ColorModel::HSVAHex | ColorModel::HSLAAng | ColorModel::HSLAHex, 2) => Self::Hue,
+            (ColorModel::YCgCoA, 1) => Self::Cg,
+            (ColorModel::YCgCoA, 2) => Self::Co,
+            (ColorModel::YcCbcCrc, 1) => Self::Cb,
+            (ColorModel::YcCbcCrc, 2) => Self::Cr,
+            (ColorModel::ICtCp, 0) => Self::I,
+            (ColorModel::ICtCp, 1) => Self::Ct,
+            (ColorModel::ICtCp, 2) => Self::Cp,
+            (ColorModel::CIEXYY, 1) => Self::YChroma,
+            (ColorModel::CIEXYY, 2) => Self::YLuma,
+            (ColorModel::BC1A, 0) => Self::Color,
+            (ColorModel::BC1A, 1) => Self::Alpha,
+            (
+                ColorModel::BC2
+                | ColorModel::BC3
+                | ColorModel::BC6H
+                | ColorModel::BC7
+                | ColorModel::ETC1
+                | ColorModel::PVRTC
+                | ColorModel::PVRTC2,
+                0,
+            ) => Self::Color,
+            (ColorModel::BC4 | ColorModel::ASTC, 0) => Self::Data,
+            (ColorModel::BC5 | ColorModel::ETC2, 0) => Self::Red,
+            (ColorModel::BC5 | ColorModel::ETC2, 1) => Self::Green,
+            (ColorModel::ETC2, 2) => Self::Color,
+            (ColorModel::ETC1S | ColorModel::UASTC, 0) => Self::Rgb,
+            (ColorModel::ETC1S, 3) => Self::Rrr,
+            (ColorModel::ETC1S, 4) => Self::Ggg,
+            (ColorModel::ETC1S, 15) => Self::Aaa,
+            (ColorModel::UASTC, 3) => Self::Rgba,
+            (ColorModel::UASTC, 4) => Self::Rrr,
+            (ColorModel::UASTC, 5) => Self::Rrrg,
+            (ColorModel::UASTC, 6) => Self::Rg,
+            _ => Self::Unknown(channel_type),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_channels() {
+    let uastc = crate::uastc_data_format_descriptors(crate::UastcChannels::Rg, false, None);
+    let dfd = crate::BasicDataFormatDescriptor::parse(&uastc[8..]).unwrap();
+    assert_eq!(dfd.channels().collect::<std::vec::Vec<_>>(), [Channel::Rg]);
+
+    assert_eq!(Channel::new(Some(ColorModel::ETC1S), 15), Channel::Aaa);
+    assert_eq!(Channel::new(Some(ColorModel::RGBSDA), 15), Channel::Alpha);
+    assert_eq!(Channel::new(Some(ColorModel::BC1A), 1), Channel::Alpha);
+    assert_eq!(Channel::new(Some(ColorModel::RGBSDA), 7), Channel::Unknown(7));
+    assert_eq!(Channel::new(None, 0), Channel::Unknown(0));
+}
@@ -0,0 +1,316 @@
+//! Conversion of decoded texels between transfer functions and color primaries.
+//!
+//! Texels are converted to linear light with the source transfer function, transformed between primaries through
+//! CIE XYZ with Bradford chromatic adaptation when the white points differ, and encoded with the destination transfer
+//! function. Alpha is left unchanged.
+
+use crate::{BasicDataFormatDescriptorHeader, ColorPrimaries, TransferFunction};
+
+/// Color primaries and transfer function of texel values, as stored in a basic data format descriptor
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ColorSpace {
+    pub primaries: ColorPrimaries,
+    pub transfer_function: TransferFunction,
+}
+
+impl ColorSpace {
+    pub const SRGB: Self = Self {
+        primaries: ColorPrimaries::BT709,
+        transfer_function: TransferFunction::SRGB,
+    };
+
+    pub const LINEAR_SRGB: Self = Self {
+        primaries: ColorPrimaries::BT709,
+        transfer_function: TransferFunction::Linear,
+    };
+
+    /// Color space of a descriptor, or `None` if its primaries or transfer function are unspecified
+    pub fn from_descriptor(header: &BasicDataFormatDescriptorHeader) -> Option<Self> {
+        Some(Self {
+            primaries: header.color_primaries?,
+            transfer_function: header.transfer_function?,
+        })
+    }
+}
+
+/// Conversion of texels from one color space to another
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ColorConversion {
+    from: TransferFunction,
+    to: TransferFunction,
+    matrix: Option<[[f64; 3]; 3]>,
+}
+
+impl ColorConversion {
+    /// Returns `None` if either transfer function or set of primaries isn't supported
+    pub fn new(from: ColorSpace, to: ColorSpace) -> Option<Self> {
+        to_linear(from.transfer_function, 0.0)?;
+        from_linear(to.transfer_function, 0.0)?;
+
+        let matrix = if from.primaries == to.primaries {
+            None
+        } else {
+            let (from_matrix, from_white) = rgb_to_xyz(from.primaries)?;
+            let (to_matrix, to_white) = rgb_to_xyz(to.primaries)?;
+            let adaptation = bradford(from_white, to_white);
+            Some(multiply(&invert(&to_matrix), &multiply(&adaptation, &from_matrix)))
+        };
+        Some(Self {
+            from: from.transfer_function,
+            to: to.transfer_function,
+            matrix,
+        })
+    }
+
+    pub fn convert(&self, texel: [f32; 4]) -> [f32; 4] {
+        let linear = [0, 1, 2].map(|channel| to_linear(self.from, texel[channel]).unwrap());
+        let linear = match &self.matrix {
+            Some(matrix) => matrix.map(|row| {
+                let value = (0..3)
+                    .map(|column| row[column] * f64::from(linear[column]))
+                    .sum::<f64>();
+                value as f32
+            }),
+            None => linear,
+        };
+        let [r, g, b] = linear.map(|value| from_linear(self.to, value).unwrap());
+        [r, g, b, texel[3]]
+    }
+
+    pub fn apply(&self, texels: &mut [[f32; 4]]) {
+        for texel in texels {
+            *texel = self.convert(*texel);
+        }
+    }
+}
+
+// Constants of the SMPTE ST 2084 (PQ) curve
+const PQ_M1: f32 = 2610.0 / 16384.0;
+const PQ_M2: f32 = 2523.0 / 4096.0 * 128.0;
+const PQ_C1: f32 = 3424.0 / 4096.0;
+const PQ_C2: f32 = 2413.0 / 4096.0 * 32.0;
+const PQ_C3: f32 = 2392.0 / 4096.0 * 32.0;
+
+// Constants of the ARIB STD-B67 (HLG) curve
+const HLG_A: f32 = 0.178_832_77;
+const HLG_B: f32 = 0.284_668_92;
+const HLG_C: f32 = 0.559_910_7;
+
+const ADOBE_RGB_GAMMA: f32 = 563.0 / 256.0;
+
+/// Convert an encoded value into linear light
+///
+/// PQ values are linear in units of 10000 cd/m², and the HLG OOTF isn't applied, so both HLG transfer functions use
+/// the scene-referred curve. Returns `None` for unsupported transfer functions.
+pub fn to_linear(transfer_function: TransferFunction, value: f32) -> Option<f32> {
+    Some(match transfer_function {
+        TransferFunction::Linear => value,
+        TransferFunction::SRGB => {
+            if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        }
+        TransferFunction::ITU => {
+            if value < 0.081 {
+                value / 4.5
+            } else {
+                ((value + 0.099) / 1.099).powf(1.0 / 0.45)
+            }
+        }
+        TransferFunction::BT1886 => value.max(0.0).powf(2.4),
+        TransferFunction::DCIP3 => value.max(0.0).powf(2.6),
+        TransferFunction::AdobeRGB => value.max(0.0).powf(ADOBE_RGB_GAMMA),
+        TransferFunction::PQEOTF => {
+            let power = value.max(0.0).powf(1.0 / PQ_M2);
+            ((power - PQ_C1).max(0.0) / (PQ_C2 - PQ_C3 * power)).powf(1.0 / PQ_M1)
+        }
+        TransferFunction::HLGOETF | TransferFunction::HLGEOTF => {
+            if value <= 0.5 {
+                value * value / 3.0
+            } else {
+                (((value - HLG_C) / HLG_A).exp() + HLG_B) / 12.0
+            }
+        }
+        TransferFunction::ACESCC => {
+            if value <= (9.72 - 15.0) / 17.52 {
+                (2.0f32.powf(value * 17.52 - 9.72) - 2.0f32.powi(-16)) * 2.0
+            } else if value < (65504.0f32.log2() + 9.72) / 17.52 {
+                2.0f32.powf(value * 17.52 - 9.72)
+            } else {
+                65504.0
+            }
+        }
+        TransferFunction::ACESCCT => {
+            if value <= 0.155_251_14 {
+                (value - 0.072_905_53) / 10.540_237
+            } else {
+                2.0f32.powf(value * 17.52 - 9.72)
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// Encode a linear light value with a transfer function, the inverse of [`to_linear`]
+pub fn from_linear(transfer_function: TransferFunction, value: f32) -> Option<f32> {
+    Some(match transfer_function {
+        TransferFunction::Linear => value,
+        TransferFunction::SRGB => {
+            if value <= 0.003_130_8 {
+                value * 12.92
+            } else {
+                1.055 * value.powf(1.0 / 2.4) - 0.055
+            }
+        }
+        TransferFunction::ITU => {
+            if value < 0.018 {
+                value * 4.5
+            } else {
+                1.099 * value.powf(0.45) - 0.099
+            }
+        }
+        TransferFunction::BT1886 => value.max(0.0).powf(1.0 / 2.4),
+        TransferFunction::DCIP3 => value.max(0.0).powf(1.0 / 2.6),
+        TransferFunction::AdobeRGB => value.max(0.0).powf(1.0 / ADOBE_RGB_GAMMA),
+        TransferFunction::PQEOTF => {
+            let power = value.max(0.0).powf(PQ_M1);
+            ((PQ_C1 + PQ_C2 * power) / (1.0 + PQ_C3 * power)).powf(PQ_M2)
+        }
+        TransferFunction::HLGOETF | TransferFunction::HLGEOTF => {
+            let value = value.max(0.0);
+            if value <= 1.0 / 12.0 {
+                (3.0 * value).sqrt()
+            } else {
+                HLG_A * (12.0 * value - HLG_B).ln() + HLG_C
+            }
+        }
+        TransferFunction::ACESCC => {
+            if value <= 0.0 {
+                (-16.0 + 9.72) / 17.52
+            } else if value < 2.0f32.powi(-15) {
+                ((2.0f32.powi(-16) + value * 0.5).log2() + 9.72) / 17.52
+            } else {
+                (value.log2() + 9.72) / 17.52
+            }
+        }
+        TransferFunction::ACESCCT => {
+            if value <= 0.007_812_5 {
+                10.540_237 * value + 0.072_905_53
+            } else {
+                (value.log2() + 9.72) / 17.52
+            }
+        }
+        _ => return None,
+    })
+}
+
+const D65: [f64; 2] = [0.3127, 0.3290];
+const ACES_WHITE: [f64; 2] = [0.32168, 0.33767];
+
+/// Matrix converting linear RGB with `primaries` into CIE XYZ, relative to their own white point
+///
+/// Returns `None` for unsupported primaries.
+pub fn rgb_to_xyz_matrix(primaries: ColorPrimaries) -> Option<[[f32; 3]; 3]> {
+    let (matrix, _) = rgb_to_xyz(primaries)?;
+    Some(matrix.map(|row| row.map(|value| value as f32)))
+}
+
+fn rgb_to_xyz(primaries: ColorPrimaries) -> Option<([[f64; 3]; 3], [f64; 3])> {
+    // Chromaticities of red, green, blue and white
+    let [red, green, blue, white] = match primaries {
+        ColorPrimaries::BT709 => [[0.64, 0.33], [0.30, 0.60], [0.15, 0.06], D65],
+        ColorPrimaries::BT601EBU => [[0.64, 0.33], [0.29, 0.60], [0.15, 0.06], D65],
+        ColorPrimaries::BT601SMPTE | ColorPrimaries::PAL525 => [[0.63, 0.34], [0.31, 0.595], [0.155, 0.07], D65],
+        ColorPrimaries::BT2020 => [[0.708, 0.292], [0.170, 0.797], [0.131, 0.046], D65],
+        ColorPrimaries::DISPLAYP3 => [[0.680, 0.320], [0.265, 0.690], [0.150, 0.060], D65],
+        ColorPrimaries::AdobeRGB => [[0.64, 0.33], [0.21, 0.71], [0.15, 0.06], D65],
+        ColorPrimaries::NTSC1953 => [[0.67, 0.33], [0.21, 0.71], [0.14, 0.08], [0.310, 0.316]],
+        ColorPrimaries::ACES => [[0.7347, 0.2653], [0.0, 1.0], [0.0001, -0.0770], ACES_WHITE],
+        ColorPrimaries::ACESCC => [[0.713, 0.293], [0.165, 0.830], [0.128, 0.044], ACES_WHITE],
+        ColorPrimaries::CIEXYZ => {
+            let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+            return Some((identity, [1.0, 1.0, 1.0]));
+        }
+        _ => return None,
+    };
+
+    let xyz = |[x, y]: [f64; 2]| [x / y, 1.0, (1.0 - x - y) / y];
+    let [red, green, blue, white] = [xyz(red), xyz(green), xyz(blue), xyz(white)];
+    let primaries = [0, 1, 2].map(|row| [red[row], green[row], blue[row]]);
+    let inverse = invert(&primaries);
+    let scale = inverse.map(|row| (0..3).map(|column| row[column] * white[column]).sum::<f64>());
+    let matrix = primaries.map(|row| [0, 1, 2].map(|column| row[column] * scale[column]));
+    Some((matrix, white))
+}
+
+/// Bradford chromatic adaptation from one XYZ white point to another
+fn bradford(from: [f64; 3], to: [f64; 3]) -> [[f64; 3]; 3] {
+    const BRADFORD: [[f64; 3]; 3] = [
+        [0.8951, 0.2664, -0.1614],
+        [-0.7502, 1.7135, 0.0367],
+        [0.0389, -0.0685, 1.0296],
+    ];
+    let cone = |white: [f64; 3]| BRADFORD.map(|row| (0..3).map(|column| row[column] * white[column]).sum::<f64>());
+    let (from, to) = (cone(from), cone(to));
+    let mut scale = [[0.0; 3]; 3];
+    for channel in 0..3 {
+        scale[channel][channel] = to[channel] / from[channel];
+    }
+    multiply(&invert(&BRADFORD), &multiply(&scale, &BRADFORD))
+}
+
+fn multiply(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    [0, 1, 2].map(|row| [0, 1, 2].map(|column| (0..3).map(|index| a[row][index] * b[index][column]).sum()))
+}
+
+fn invert(m: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let cofactor = |row: usize, column: usize| {
+        let (r0, r1) = ((row + 1) % 3, (row + 2) % 3);
+        let (c0, c1) = ((column + 1) % 3, (column + 2) % 3);
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+    let determinant = (0..3).map(|column| m[0][column] * cofactor(0, column)).sum::<f64>();
+    [0, 1, 2].map(|row| [0, 1, 2].map(|column| cofactor(column, row) / determinant))
+}
+
+#[test]
+fn test_color_conversion() {
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-3;
+
+    for transfer_function in (1..=18).filter_map(TransferFunction::new) {
+        if let Some(encoded) = from_linear(transfer_function, 0.5) {
+            let decoded = to_linear(transfer_function, encoded).unwrap();
+            assert!(close(decoded, 0.5), "{:?}: {}", transfer_function, decoded);
+        }
+    }
+    assert!(close(from_linear(TransferFunction::PQEOTF, 0.01).unwrap(), 0.508));
+
+    let bt709 = rgb_to_xyz_matrix(ColorPrimaries::BT709).unwrap();
+    assert!(bt709[1].iter().zip([0.2126, 0.7152, 0.0722]).all(|(&a, b)| close(a, b)));
+
+    let to_p3 = ColorConversion::new(
+        ColorSpace::SRGB,
+        ColorSpace {
+            primaries: ColorPrimaries::DISPLAYP3,
+            transfer_function: TransferFunction::SRGB,
+        },
+    )
+    .unwrap();
+    let white = to_p3.convert([1.0, 1.0, 1.0, 0.5]);
+    assert!(white.iter().zip([1.0, 1.0, 1.0, 0.5]).all(|(&a, b)| close(a, b)));
+    let red = to_p3.convert([1.0, 0.0, 0.0, 1.0]);
+    assert!(close(red[0], 0.917) && close(red[1], 0.200) && close(red[2], 0.139));
+
+    // White is preserved between white points by the chromatic adaptation
+    let to_aces = ColorConversion::new(
+        ColorSpace::LINEAR_SRGB,
+        ColorSpace {
+            primaries: ColorPrimaries::ACES,
+            transfer_function: TransferFunction::Linear,
+        },
+    )
+    .unwrap();
+    assert!(to_aces.convert([1.0; 4]).iter().all(|&value| close(value, 1.0)));
+}
@@ -0,0 +1,286 @@
+//! Building cubemaps from separate faces, cross layouts and equirectangular images, and extracting their faces.
+//!
+//! Faces are always in the order +X, -X, +Y, -Y, +Z, -Z and follow the orientation conventions of OpenGL and Vulkan.
+
+use crate::{
+    mipmap::{linear_to_srgb, srgb_to_linear},
+    texel, Format, Header, ImageLayout, Index, Reader, TextureError, Writer, WriterLevel,
+};
+use std::{collections::BTreeMap, string::String, vec::Vec};
+
+/// Key of the metadata entry listing the faces present in an incomplete cubemap
+pub const CUBEMAP_INCOMPLETE_KEY: &str = "KTXcubemapIncomplete";
+
+impl Writer {
+    /// Create a single-level cubemap from the data of its faces, each `size` texels wide and high
+    ///
+    /// Missing faces produce an incomplete cubemap: the present faces are stored as array layers with a
+    /// `KTXcubemapIncomplete` entry recording which faces they are.
+    pub fn cubemap(format: Format, size: u32, faces: [Option<&[u8]>; 6]) -> Result<Self, TextureError> {
+        let layout = ImageLayout::new(format, [size, size, 1])?;
+        let data_format_descriptors = format
+            .data_format_descriptors()
+            .ok_or(TextureError::UnsupportedFormat)?;
+
+        let mut present = 0u8;
+        let mut data = Vec::new();
+        for (index, face) in faces.iter().enumerate() {
+            if let Some(face) = face {
+                if face.len() != layout.byte_length {
+                    return Err(TextureError::InvalidLength);
+                }
+                present |= 1 << index;
+                data.extend_from_slice(face);
+            }
+        }
+        let (layer_count, face_count) = match present.count_ones() {
+            0 => return Err(TextureError::InvalidLength),
+            6 => (0, 6),
+            count => (count, 1),
+        };
+
+        let mut key_value_data = BTreeMap::new();
+        if face_count == 1 {
+            key_value_data.insert(String::from(CUBEMAP_INCOMPLETE_KEY), std::vec![present]);
+        }
+        Ok(Self {
+            header: Header {
+                format: Some(format),
+                type_size: format.type_size(),
+                pixel_width: size,
+                pixel_height: size,
+                pixel_depth: 0,
+                layer_count,
+                face_count,
+                level_count: 1,
+                supercompression_scheme: None,
+                index: Index::default(),
+            },
+            data_format_descriptors,
+            key_value_data,
+            supercompression_global_data: Vec::new(),
+            levels: std::vec![WriterLevel::new(data)],
+        })
+    }
+
+    /// Create a single-level cubemap from an image with its faces laid out as a cross
+    ///
+    /// Horizontal crosses are 4 faces wide and 3 high, with -X, +Z, +X and -Z in the middle row and +Y and -Y above
+    /// and below +Z. Vertical crosses are 3 faces wide and 4 high, with -Z below -Y and rotated by 180 degrees, which
+    /// is only supported for uncompressed formats. Block-compressed faces must be a whole number of blocks.
+    pub fn cubemap_from_cross(format: Format, width: u32, height: u32, data: &[u8]) -> Result<Self, TextureError> {
+        let (size, positions) = if u64::from(width) * 3 == u64::from(height) * 4 {
+            (width / 4, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)])
+        } else if u64::from(width) * 4 == u64::from(height) * 3 {
+            (width / 3, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (1, 3)])
+        } else {
+            return Err(TextureError::InvalidLength);
+        };
+        let vertical = width < height;
+
+        let [block_width, block_height, _] = format.block_dimensions();
+        if size % block_width != 0 || size % block_height != 0 || (vertical && format.is_compressed()) {
+            return Err(TextureError::UnsupportedFormat);
+        }
+        let image = ImageLayout::new(format, [width, height, 1])?;
+        let face = ImageLayout::new(format, [size, size, 1]).unwrap();
+        if data.len() != image.byte_length {
+            return Err(TextureError::InvalidLength);
+        }
+
+        let mut faces = positions.map(|(column, row)| {
+            let mut face_data = Vec::with_capacity(face.byte_length);
+            for block_row in 0..face.rows_per_image as usize {
+                // The offsets are within the image, whose byte length fits in usize
+                let start = (row * face.rows_per_image as usize + block_row) * image.bytes_per_row as usize
+                    + column * face.bytes_per_row as usize;
+                face_data.extend_from_slice(&data[start..start + face.bytes_per_row as usize]);
+            }
+            face_data
+        });
+        if vertical {
+            // Reversing the texel order rotates the face by 180 degrees
+            let texel_size = format.block_size().unwrap() as usize;
+            let texels = faces[5].chunks_exact(texel_size).rev().flatten().copied().collect();
+            faces[5] = texels;
+        }
+
+        Self::cubemap(format, size, faces.each_ref().map(|face| Some(&face[..])))
+    }
+
+    /// Create a single-level cubemap with faces `size` texels wide by resampling an equirectangular image
+    ///
+    /// The center of the image faces -Z, with +Y at the top. Texels are filtered bilinearly in linear space, so only
+    /// uncompressed formats are supported.
+    pub fn cubemap_from_equirectangular(
+        format: Format,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        size: u32,
+    ) -> Result<Self, TextureError> {
+        let mut texels = texel::decode(format, data).ok_or(TextureError::UnsupportedFormat)?;
+        if Some(texels.len()) != (width as usize).checked_mul(height as usize) || width == 0 || height == 0 {
+            return Err(TextureError::InvalidLength);
+        }
+        let face_texel_count = (size as usize)
+            .checked_mul(size as usize)
+            .ok_or(TextureError::InvalidLength)?;
+        if format.is_srgb() {
+            for texel in &mut texels {
+                for channel in &mut texel[..3] {
+                    *channel = srgb_to_linear(*channel);
+                }
+            }
+        }
+
+        let sample = |u: f32, v: f32| {
+            let x = u * width as f32 - 0.5;
+            let y = (v * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+            let (x0, y0) = (x.floor(), y.floor());
+            let (fx, fy) = (x - x0, y - y0);
+            let column = |x: f32| (x as i64).rem_euclid(i64::from(width)) as usize;
+            let row = |y: f32| (y as usize).min(height as usize - 1);
+            let texel = |x: f32, y: f32| texels[row(y) * width as usize + column(x)];
+
+            let mut result = [0.0; 4];
+            for (channel, value) in result.iter_mut().enumerate() {
+                let top = texel(x0, y0)[channel] * (1.0 - fx) + texel(x0 + 1.0, y0)[channel] * fx;
+                let bottom = texel(x0, y0 + 1.0)[channel] * (1.0 - fx) + texel(x0 + 1.0, y0 + 1.0)[channel] * fx;
+                *value = top * (1.0 - fy) + bottom * fy;
+            }
+            result
+        };
+
+        let faces = [0, 1, 2, 3, 4, 5].map(|face| {
+            let mut face_texels = Vec::with_capacity(face_texel_count);
+            for y in 0..size {
+                for x in 0..size {
+                    let s = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+                    let t = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+                    let [dx, dy, dz] = face_direction(face, s, t);
+                    let length = (dx * dx + dy * dy + dz * dz).sqrt();
+                    let u = 0.5 + dx.atan2(-dz) / (2.0 * core::f32::consts::PI);
+                    let v = (dy / length).acos() / core::f32::consts::PI;
+
+                    let mut texel = sample(u, v);
+                    if format.is_srgb() {
+                        for channel in &mut texel[..3] {
+                            *channel = linear_to_srgb(*channel);
+                        }
+                    }
+                    face_texels.push(texel);
+                }
+            }
+            texel::encode(format, &face_texels).unwrap()
+        });
+
+        Self::cubemap(format, size, faces.each_ref().map(|face| Some(&face[..])))
+    }
+}
+
+/// Direction of the texel at `s` and `t` in `-1.0..=1.0` on a face, with `t` increasing downwards
+fn face_direction(face: usize, s: f32, t: f32) -> [f32; 3] {
+    match face {
+        0 => [1.0, -t, -s],
+        1 => [-1.0, -t, s],
+        2 => [s, 1.0, t],
+        3 => [s, -1.0, -t],
+        4 => [s, -t, 1.0],
+        _ => [-s, -t, -1.0],
+    }
+}
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// Data of the six faces of a cubemap at mip `level` and array `layer`
+    ///
+    /// Faces missing from incomplete cubemaps, as listed by their `KTXcubemapIncomplete` entry, are `None`. Returns
+    /// [`TextureError::UnsupportedLayout`] if the texture isn't a cubemap.
+    pub fn cubemap_faces(&self, level: u32, layer: u32) -> Result<[Option<&[u8]>; 6], TextureError> {
+        let header = self.header();
+        if header.supercompression_scheme.is_some() {
+            return Err(TextureError::UnsupportedSupercompression);
+        }
+        let format = header.format.ok_or(TextureError::UnsupportedFormat)?;
+        let layout = ImageLayout::new(format, header.level_extent(level))?;
+
+        let present = if header.face_count == 6 {
+            0b11_1111
+        } else {
+            match self.key_value_data().find(|&(key, _)| key == CUBEMAP_INCOMPLETE_KEY) {
+                Some((_, &[present, ..])) => present & 0b11_1111,
+                _ => return Err(TextureError::UnsupportedLayout),
+            }
+        };
+
+        let level_data = self
+            .levels()
+            .nth(level as usize)
+            .ok_or(TextureError::InvalidLength)?
+            .data;
+        let faces_per_layer = present.count_ones() as usize;
+        let mut offset = (layer as usize)
+            .checked_mul(faces_per_layer)
+            .and_then(|offset| offset.checked_mul(layout.byte_length))
+            .ok_or(TextureError::InvalidLength)?;
+        let mut faces = [None; 6];
+        for (index, face) in faces.iter_mut().enumerate() {
+            if present & (1 << index) != 0 {
+                *face = Some(
+                    level_data
+                        .get(
+                            offset
+                                ..offset
+                                    .checked_add(layout.byte_length)
+                                    .ok_or(TextureError::InvalidLength)?,
+                        )
+                        .ok_or(TextureError::InvalidLength)?,
+                );
+                offset += layout.byte_length;
+            }
+        }
+        Ok(faces)
+    }
+}
+
+#[test]
+fn test_cubemaps() {
+    let faces = [0u8, 1, 2, 3, 4, 5].map(|face| std::vec![face; 2 * 2 * 4]);
+    let writer = Writer::cubemap(
+        Format::R8G8B8A8_UNORM,
+        2,
+        [Some(&faces[0][..]), None, Some(&faces[2]), None, None, Some(&faces[5])],
+    )
+    .unwrap();
+    assert_eq!((writer.header.face_count, writer.header.layer_count), (1, 3));
+    let file = writer.write();
+    let reader = Reader::new(&file[..]).unwrap();
+    let read_faces = reader.cubemap_faces(0, 0).unwrap();
+    assert_eq!(
+        read_faces.map(|face| face.map(|face| face[0])),
+        [Some(0), None, Some(2), None, None, Some(5)]
+    );
+
+    // 4x3 cross of 1x1 faces, where each texel holds its grid position
+    let cross = (0..12u8).collect::<Vec<_>>();
+    let writer = Writer::cubemap_from_cross(Format::R8_UNORM, 4, 3, &cross).unwrap();
+    assert_eq!(writer.header.face_count, 6);
+    assert_eq!(writer.levels[0].data, [6, 4, 1, 9, 5, 7]);
+
+    let rotated = Writer::cubemap_from_cross(Format::R8G8_UNORM, 6, 8, &[1; 6 * 8 * 2]).unwrap();
+    assert_eq!(rotated.header.pixel_width, 2);
+
+    let equirectangular = [10, 20, 30, 255].repeat(8 * 4);
+    let writer = Writer::cubemap_from_equirectangular(Format::R8G8B8A8_SRGB, 8, 4, &equirectangular, 2).unwrap();
+    assert_eq!(writer.levels[0].data, [10, 20, 30, 255].repeat(6 * 2 * 2));
+
+    let file = include_bytes!("../data/test_tex.ktx2");
+    assert!(matches!(
+        Reader::new(&file[..]).unwrap().cubemap_faces(0, 0),
+        Err(TextureError::UnsupportedLayout)
+    ));
+    assert!(matches!(
+        Writer::cubemap_from_cross(Format::R8_UNORM, 0xc000_0000, 0x9000_0000, &[]),
+        Err(TextureError::InvalidLength)
+    ));
+}
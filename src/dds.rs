@@ -0,0 +1,659 @@
+//! Conversion between [DDS](https://learn.microsoft.com/en-us/windows/win32/direct3ddds/dx-graphics-dds-pguide) files
+//! and KTX2.
+//!
+//! Both legacy headers and the DX10 header extension are read. Files are always written with the DX10 extension.
+
+use crate::{
+    cubemap::CUBEMAP_INCOMPLETE_KEY, dxgi, AlphaMode, DataFormatFlags, Format, ImageLayout, Index, ParseError,
+    TextureError, Writer, WriterLevel,
+};
+use core::convert::TryInto;
+use std::{collections::BTreeMap, string::String, vec::Vec};
+
+/// Magic number at the start of every DDS file
+pub const MAGIC: [u8; 4] = *b"DDS ";
+
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_HEIGHT: u32 = 0x2;
+const DDSD_WIDTH: u32 = 0x4;
+const DDSD_PITCH: u32 = 0x8;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_MIPMAPCOUNT: u32 = 0x2_0000;
+const DDSD_LINEARSIZE: u32 = 0x8_0000;
+const DDSD_DEPTH: u32 = 0x80_0000;
+
+const DDSCAPS_COMPLEX: u32 = 0x8;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const DDSCAPS_MIPMAP: u32 = 0x40_0000;
+
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_CUBEMAP_ALL_FACES: u32 = 0xFC00;
+const DDSCAPS2_VOLUME: u32 = 0x20_0000;
+
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x2_0000;
+
+const RESOURCE_DIMENSION_TEXTURE1D: u32 = 2;
+const RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+const RESOURCE_DIMENSION_TEXTURE3D: u32 = 4;
+const RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+const ALPHA_MODE_PREMULTIPLIED: u32 = 2;
+
+/// Decodes DDS texture data
+pub struct Reader<Data: AsRef<[u8]>> {
+    input: Data,
+    header: Header,
+}
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// Decode DDS data from `input`
+    pub fn new(input: Data) -> Result<Self, ParseError> {
+        let header = Header::parse(input.as_ref())?;
+        Ok(Self { input, header })
+    }
+
+    pub fn header(&self) -> Header {
+        self.header
+    }
+
+    /// Texel data following the header
+    pub fn data(&self) -> &[u8] {
+        &self.input.as_ref()[self.header.length()..]
+    }
+
+    /// The format of the texture, from the DX10 header extension or the legacy pixel format
+    pub fn format(&self) -> Option<Format> {
+        match self.header.dx10 {
+            Some(dx10) => dxgi::to_format(dx10.dxgi_format),
+            None => self.header.pixel_format.format(),
+        }
+    }
+
+    /// Convert the texture into KTX2
+    ///
+    /// Images are reordered from layer, face and level order into level, layer and face order. Legacy luminance
+    /// formats get a `KTXswizzle` entry, and legacy cubemaps missing some faces are stored as incomplete cubemaps with
+    /// a `KTXcubemapIncomplete` entry.
+    pub fn to_ktx2(&self) -> Result<Writer, TextureError> {
+        let format = self.format().ok_or(TextureError::UnsupportedFormat)?;
+        let (mut dfd_header, samples) = format
+            .basic_data_format_descriptor()
+            .ok_or(TextureError::UnsupportedFormat)?;
+        let header = &self.header;
+        let mut key_value_data = BTreeMap::new();
+
+        let (pixel_height, pixel_depth, layer_count, face_count) = match header.dx10 {
+            Some(dx10) => (
+                if dx10.resource_dimension == RESOURCE_DIMENSION_TEXTURE1D {
+                    0
+                } else {
+                    header.height
+                },
+                if dx10.resource_dimension == RESOURCE_DIMENSION_TEXTURE3D {
+                    header.depth
+                } else {
+                    0
+                },
+                if dx10.array_size > 1 { dx10.array_size } else { 0 },
+                if dx10.misc_flag & RESOURCE_MISC_TEXTURECUBE != 0 {
+                    6
+                } else {
+                    1
+                },
+            ),
+            None => (
+                header.height,
+                if header.caps2 & DDSCAPS2_VOLUME != 0 {
+                    header.depth
+                } else {
+                    0
+                },
+                0,
+                if header.caps2 & DDSCAPS2_CUBEMAP != 0 { 6 } else { 1 },
+            ),
+        };
+        // Faces of legacy cubemaps are flagged individually, in the same order as in KTXcubemapIncomplete
+        let (layer_count, face_count) = match (header.caps2 & DDSCAPS2_CUBEMAP_ALL_FACES) >> 10 {
+            present if face_count == 6 && header.dx10.is_none() && present != 0b11_1111 => {
+                if present == 0 {
+                    return Err(TextureError::UnsupportedLayout);
+                }
+                key_value_data.insert(String::from(CUBEMAP_INCOMPLETE_KEY), std::vec![present as u8]);
+                (present.count_ones(), 1)
+            }
+            _ => (layer_count, face_count),
+        };
+        if header.dx10.is_none() && header.pixel_format.flags & DDPF_LUMINANCE != 0 {
+            let swizzle = match format {
+                Format::R8G8_UNORM => b"rrrg\0",
+                _ => b"rrr1\0",
+            };
+            key_value_data.insert(String::from("KTXswizzle"), swizzle.to_vec());
+        }
+        let alpha_premultiplied = match header.dx10 {
+            Some(dx10) => dx10.misc_flags2 & 0x7 == ALPHA_MODE_PREMULTIPLIED,
+            None => header.pixel_format.is_alpha_premultiplied(),
+        };
+        if alpha_premultiplied {
+            dfd_header.flags |= DataFormatFlags::ALPHA_PREMULTIPLIED;
+        }
+
+        let ktx2_header = crate::Header {
+            format: Some(format),
+            type_size: format.type_size(),
+            pixel_width: header.width,
+            pixel_height,
+            pixel_depth,
+            layer_count,
+            face_count,
+            level_count: header.level_count(),
+            supercompression_scheme: None,
+            index: Index::default(),
+        };
+
+        let level_lengths = (0..header.level_count())
+            .map(|level| ImageLayout::new(format, ktx2_header.level_extent(level)).map(|layout| layout.byte_length))
+            .collect::<Result<Vec<_>, _>>()?;
+        let images = layer_count
+            .max(1)
+            .checked_mul(face_count)
+            .ok_or(TextureError::InvalidLength)? as usize;
+        let byte_length = level_lengths
+            .iter()
+            .sum::<usize>()
+            .checked_mul(images)
+            .ok_or(TextureError::InvalidLength)?;
+        if self.data().len() < byte_length {
+            return Err(TextureError::InvalidLength);
+        }
+
+        // Each image stores its whole mip chain before the next one starts
+        let mut levels = level_lengths
+            .iter()
+            .map(|length| Vec::with_capacity(length * images))
+            .collect::<Vec<_>>();
+        let mut data = self.data();
+        for _ in 0..images {
+            for (level, &length) in levels.iter_mut().zip(&level_lengths) {
+                let (image, rest) = data.split_at(length);
+                level.extend_from_slice(image);
+                data = rest;
+            }
+        }
+        if header.dx10.is_none() && header.pixel_format.has_unused_alpha() {
+            // The padding byte of X8R8G8B8 and X8B8G8R8 texels becomes opaque alpha
+            for level in &mut levels {
+                for texel in level.chunks_exact_mut(4) {
+                    texel[3] = 0xff;
+                }
+            }
+        }
+
+        Ok(Writer {
+            header: ktx2_header,
+            data_format_descriptors: crate::basic_data_format_descriptor_block(&dfd_header, &samples),
+            key_value_data,
+            supercompression_global_data: Vec::new(),
+            levels: levels.into_iter().map(WriterLevel::new).collect(),
+        })
+    }
+}
+
+/// Convert a KTX2 texture into a DDS file with the DX10 header extension
+pub fn from_ktx2<Data: AsRef<[u8]>>(reader: &crate::Reader<Data>) -> Result<Vec<u8>, TextureError> {
+    let ktx2_header = reader.header();
+    if ktx2_header.supercompression_scheme.is_some() {
+        return Err(TextureError::UnsupportedSupercompression);
+    }
+    let format = ktx2_header.format.ok_or(TextureError::UnsupportedFormat)?;
+    let dxgi_format = dxgi::from_format(format).ok_or(TextureError::UnsupportedFormat)?;
+    let base_layout = ImageLayout::new(format, ktx2_header.level_extent(0))?;
+
+    let level_count = ktx2_header.level_count.max(1);
+    let cubemap = ktx2_header.face_count == 6;
+    let volume = ktx2_header.pixel_depth > 0;
+    let alpha_premultiplied = reader.alpha_mode() == Some(AlphaMode::Premultiplied);
+
+    let mut flags = DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT | DDSD_MIPMAPCOUNT;
+    let pitch_or_linear_size = if format.is_compressed() {
+        flags |= DDSD_LINEARSIZE;
+        base_layout.byte_length as u32
+    } else {
+        flags |= DDSD_PITCH;
+        base_layout.bytes_per_row
+    };
+    if volume {
+        flags |= DDSD_DEPTH;
+    }
+    let mut caps = DDSCAPS_TEXTURE;
+    if level_count > 1 {
+        caps |= DDSCAPS_COMPLEX | DDSCAPS_MIPMAP;
+    }
+    if cubemap || volume {
+        caps |= DDSCAPS_COMPLEX;
+    }
+
+    let header = Header {
+        flags,
+        height: ktx2_header.pixel_height.max(1),
+        width: ktx2_header.pixel_width,
+        pitch_or_linear_size,
+        depth: ktx2_header.pixel_depth,
+        mip_map_count: level_count,
+        pixel_format: PixelFormat {
+            flags: DDPF_FOURCC,
+            four_cc: u32::from_le_bytes(*b"DX10"),
+            ..PixelFormat::default()
+        },
+        caps,
+        caps2: if cubemap {
+            DDSCAPS2_CUBEMAP | DDSCAPS2_CUBEMAP_ALL_FACES
+        } else if volume {
+            DDSCAPS2_VOLUME
+        } else {
+            0
+        },
+        caps3: 0,
+        caps4: 0,
+        dx10: Some(Dx10Header {
+            dxgi_format,
+            resource_dimension: if volume {
+                RESOURCE_DIMENSION_TEXTURE3D
+            } else if ktx2_header.pixel_height == 0 {
+                RESOURCE_DIMENSION_TEXTURE1D
+            } else {
+                RESOURCE_DIMENSION_TEXTURE2D
+            },
+            misc_flag: if cubemap { RESOURCE_MISC_TEXTURECUBE } else { 0 },
+            array_size: ktx2_header.layer_count.max(1),
+            misc_flags2: if alpha_premultiplied {
+                ALPHA_MODE_PREMULTIPLIED
+            } else {
+                0
+            },
+        }),
+    };
+
+    let images = ktx2_header
+        .layer_count
+        .max(1)
+        .checked_mul(ktx2_header.face_count)
+        .ok_or(TextureError::InvalidLength)? as usize;
+    let mut level_images = Vec::new();
+    for (index, level) in reader.levels().enumerate() {
+        let layout = ImageLayout::new(format, ktx2_header.level_extent(index as u32)).unwrap();
+        if Some(level.data.len()) != layout.byte_length.checked_mul(images) {
+            return Err(TextureError::InvalidLength);
+        }
+        level_images.push(level.data.chunks_exact(layout.byte_length));
+    }
+
+    let mut output = header.as_bytes();
+    for _ in 0..images {
+        for level in &mut level_images {
+            output.extend_from_slice(level.next().unwrap());
+        }
+    }
+    Ok(output)
+}
+
+/// DDS header, including the magic number and the DX10 header extension
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Header {
+    pub flags: u32,
+    pub height: u32,
+    pub width: u32,
+    pub pitch_or_linear_size: u32,
+    pub depth: u32,
+    pub mip_map_count: u32,
+    pub pixel_format: PixelFormat,
+    pub caps: u32,
+    pub caps2: u32,
+    pub caps3: u32,
+    pub caps4: u32,
+    /// Present if the four character code of the pixel format is `DX10`
+    pub dx10: Option<Dx10Header>,
+}
+
+impl Header {
+    /// Length of the magic number and the header, excluding the DX10 header extension
+    pub const LENGTH: usize = 128;
+
+    pub fn parse(data: &[u8]) -> Result<Self, ParseError> {
+        if data.len() < Self::LENGTH {
+            return Err(ParseError::UnexpectedEnd);
+        }
+        if data[0..4] != MAGIC || u32::from_le_bytes(data[4..8].try_into().unwrap()) != 124 {
+            return Err(ParseError::BadMagic);
+        }
+        let field = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+
+        let pixel_format = PixelFormat {
+            flags: field(80),
+            four_cc: field(84),
+            rgb_bit_count: field(88),
+            r_bit_mask: field(92),
+            g_bit_mask: field(96),
+            b_bit_mask: field(100),
+            a_bit_mask: field(104),
+        };
+        let dx10 = if pixel_format.flags & DDPF_FOURCC != 0 && pixel_format.four_cc == u32::from_le_bytes(*b"DX10") {
+            if data.len() < Self::LENGTH + Dx10Header::LENGTH {
+                return Err(ParseError::UnexpectedEnd);
+            }
+            Some(Dx10Header {
+                dxgi_format: field(128),
+                resource_dimension: field(132),
+                misc_flag: field(136),
+                array_size: field(140),
+                misc_flags2: field(144),
+            })
+        } else {
+            None
+        };
+
+        let header = Self {
+            flags: field(8),
+            height: field(12),
+            width: field(16),
+            pitch_or_linear_size: field(20),
+            depth: field(24),
+            mip_map_count: field(28),
+            pixel_format,
+            caps: field(108),
+            caps2: field(112),
+            caps3: field(116),
+            caps4: field(120),
+            dx10,
+        };
+        if header.width == 0 {
+            return Err(ParseError::ZeroWidth);
+        }
+        Ok(header)
+    }
+
+    /// Length of the header including the DX10 header extension, if present
+    pub fn length(&self) -> usize {
+        Self::LENGTH + self.dx10.map_or(0, |_| Dx10Header::LENGTH)
+    }
+
+    /// Number of mip levels, treating an unset mip map count as a single level and clamping it to the length of the
+    /// full mip chain
+    pub fn level_count(&self) -> u32 {
+        if self.flags & DDSD_MIPMAPCOUNT != 0 {
+            let volume = match self.dx10 {
+                Some(dx10) => dx10.resource_dimension == RESOURCE_DIMENSION_TEXTURE3D,
+                None => self.caps2 & DDSCAPS2_VOLUME != 0,
+            };
+            let depth = if volume { self.depth } else { 0 };
+            let full_mip_chain_length = crate::full_mip_chain_length(self.width, self.height, depth);
+            self.mip_map_count.clamp(1, full_mip_chain_length)
+        } else {
+            1
+        }
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        let mut push = |value: u32| bytes.extend_from_slice(&value.to_le_bytes());
+
+        push(124);
+        push(self.flags);
+        push(self.height);
+        push(self.width);
+        push(self.pitch_or_linear_size);
+        push(self.depth);
+        push(self.mip_map_count);
+        for _ in 0..11 {
+            push(0);
+        }
+        push(32);
+        push(self.pixel_format.flags);
+        push(self.pixel_format.four_cc);
+        push(self.pixel_format.rgb_bit_count);
+        push(self.pixel_format.r_bit_mask);
+        push(self.pixel_format.g_bit_mask);
+        push(self.pixel_format.b_bit_mask);
+        push(self.pixel_format.a_bit_mask);
+        push(self.caps);
+        push(self.caps2);
+        push(self.caps3);
+        push(self.caps4);
+        push(0);
+        if let Some(dx10) = self.dx10 {
+            push(dx10.dxgi_format);
+            push(dx10.resource_dimension);
+            push(dx10.misc_flag);
+            push(dx10.array_size);
+            push(dx10.misc_flags2);
+        }
+        bytes
+    }
+}
+
+/// Legacy description of the texel format
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct PixelFormat {
+    pub flags: u32,
+    pub four_cc: u32,
+    pub rgb_bit_count: u32,
+    pub r_bit_mask: u32,
+    pub g_bit_mask: u32,
+    pub b_bit_mask: u32,
+    pub a_bit_mask: u32,
+}
+
+impl PixelFormat {
+    /// The format described by a four character code or by bit masks, if there is one
+    pub fn format(&self) -> Option<Format> {
+        if self.flags & DDPF_FOURCC != 0 {
+            return Some(match &self.four_cc.to_le_bytes() {
+                b"DXT1" => Format::BC1_RGBA_UNORM_BLOCK,
+                b"DXT2" | b"DXT3" => Format::BC2_UNORM_BLOCK,
+                b"DXT4" | b"DXT5" => Format::BC3_UNORM_BLOCK,
+                b"ATI1" | b"BC4U" => Format::BC4_UNORM_BLOCK,
+                b"BC4S" => Format::BC4_SNORM_BLOCK,
+                b"ATI2" | b"BC5U" => Format::BC5_UNORM_BLOCK,
+                b"BC5S" => Format::BC5_SNORM_BLOCK,
+                // D3DFORMAT values stored in place of a four character code
+                _ => match self.four_cc {
+                    36 => Format::R16G16B16A16_UNORM,
+                    110 => Format::R16G16B16A16_SNORM,
+                    111 => Format::R16_SFLOAT,
+                    112 => Format::R16G16_SFLOAT,
+                    113 => Format::R16G16B16A16_SFLOAT,
+                    114 => Format::R32_SFLOAT,
+                    115 => Format::R32G32_SFLOAT,
+                    116 => Format::R32G32B32A32_SFLOAT,
+                    _ => return None,
+                },
+            });
+        }
+
+        let alpha_mask = if self.flags & DDPF_ALPHAPIXELS != 0 {
+            self.a_bit_mask
+        } else {
+            0
+        };
+        let masks = (
+            self.rgb_bit_count,
+            self.r_bit_mask,
+            self.g_bit_mask,
+            self.b_bit_mask,
+            alpha_mask,
+        );
+        if self.flags & DDPF_RGB != 0 {
+            Some(match masks {
+                (32, 0xff, 0xff00, 0xff_0000, 0xff00_0000) => Format::R8G8B8A8_UNORM,
+                (32, 0xff_0000, 0xff00, 0xff, 0xff00_0000) => Format::B8G8R8A8_UNORM,
+                (32, 0xff, 0xff00, 0xff_0000, 0) => Format::R8G8B8A8_UNORM,
+                (32, 0xff_0000, 0xff00, 0xff, 0) => Format::B8G8R8A8_UNORM,
+                (32, 0x3ff, 0xf_fc00, 0x3ff0_0000, 0xc000_0000) => Format::A2B10G10R10_UNORM_PACK32,
+                (32, 0xffff, 0xffff_0000, 0, 0) => Format::R16G16_UNORM,
+                (24, 0xff_0000, 0xff00, 0xff, 0) => Format::B8G8R8_UNORM,
+                (16, 0xf800, 0x7e0, 0x1f, 0) => Format::R5G6B5_UNORM_PACK16,
+                (16, 0x7c00, 0x3e0, 0x1f, 0x8000) => Format::A1R5G5B5_UNORM_PACK16,
+                _ => return None,
+            })
+        } else if self.flags & DDPF_LUMINANCE != 0 {
+            Some(match masks {
+                (8, 0xff, 0, 0, 0) => Format::R8_UNORM,
+                (16, 0xffff, 0, 0, 0) => Format::R16_UNORM,
+                (16, 0xff, 0, 0, 0xff00) => Format::R8G8_UNORM,
+                _ => return None,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Whether the texels are 32-bit RGB with an unused padding byte in place of alpha, as in X8R8G8B8
+    ///
+    /// These layouts map to 8-bit RGBA formats, with alpha set to opaque by [`Reader::to_ktx2`].
+    pub fn has_unused_alpha(&self) -> bool {
+        self.flags & DDPF_FOURCC == 0
+            && self.flags & DDPF_RGB != 0
+            && self.flags & DDPF_ALPHAPIXELS == 0
+            && self.rgb_bit_count == 32
+            && matches!(self.format(), Some(Format::R8G8B8A8_UNORM | Format::B8G8R8A8_UNORM))
+    }
+
+    /// Whether the four character code is `DXT2` or `DXT4`, the premultiplied alpha variants of BC2 and BC3
+    pub fn is_alpha_premultiplied(&self) -> bool {
+        self.flags & DDPF_FOURCC != 0 && matches!(&self.four_cc.to_le_bytes(), b"DXT2" | b"DXT4")
+    }
+}
+
+/// DX10 header extension
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Dx10Header {
+    pub dxgi_format: u32,
+    pub resource_dimension: u32,
+    pub misc_flag: u32,
+    /// Number of array layers, counting cubemaps rather than faces
+    pub array_size: u32,
+    pub misc_flags2: u32,
+}
+
+impl Dx10Header {
+    pub const LENGTH: usize = 20;
+}
+
+#[test]
+fn test_dds_round_trip() {
+    let file = include_bytes!("../data/test_tex.ktx2");
+    let reader = crate::Reader::new(&file[..]).unwrap();
+    let dds = from_ktx2(&reader).unwrap();
+
+    let dds_reader = Reader::new(&dds[..]).unwrap();
+    assert_eq!(dds_reader.format(), Some(Format::R8G8B8A8_UINT));
+    assert_eq!(dds_reader.header().level_count(), reader.header().level_count);
+
+    let ktx2 = dds_reader.to_ktx2().unwrap().write();
+    let round_trip = crate::Reader::new(&ktx2[..]).unwrap();
+    assert!(reader
+        .levels()
+        .map(|level| level.data)
+        .eq(round_trip.levels().map(|level| level.data)));
+
+    // Legacy BGRA8 cubemap without mip maps
+    let mut header = dds_reader.header();
+    header.flags &= !DDSD_MIPMAPCOUNT;
+    header.caps2 = DDSCAPS2_CUBEMAP | DDSCAPS2_CUBEMAP_ALL_FACES;
+    header.pixel_format = PixelFormat {
+        flags: DDPF_RGB | DDPF_ALPHAPIXELS,
+        four_cc: 0,
+        rgb_bit_count: 32,
+        r_bit_mask: 0xff_0000,
+        g_bit_mask: 0xff00,
+        b_bit_mask: 0xff,
+        a_bit_mask: 0xff00_0000,
+    };
+    header.dx10 = None;
+    let mut legacy = header.as_bytes();
+    let face_length = (header.width * header.height * 4) as usize;
+    for face in 0..6 {
+        legacy.resize(legacy.len() + face_length, face);
+    }
+    let writer = Reader::new(&legacy[..]).unwrap().to_ktx2().unwrap();
+    assert_eq!(writer.header.format, Some(Format::B8G8R8A8_UNORM));
+    assert_eq!(writer.header.face_count, 6);
+    assert_eq!(writer.levels.len(), 1);
+    assert_eq!(writer.levels[0].data[face_length * 5], 5);
+
+    // Legacy cubemap with only +X and -Y
+    header.caps2 = DDSCAPS2_CUBEMAP | 0x400 | 0x2000;
+    legacy[..header.length()].copy_from_slice(&header.as_bytes());
+    let writer = Reader::new(&legacy[..]).unwrap().to_ktx2().unwrap();
+    assert_eq!((writer.header.layer_count, writer.header.face_count), (2, 1));
+    assert_eq!(writer.key_value_data[CUBEMAP_INCOMPLETE_KEY], [0b1001]);
+    assert_eq!(writer.levels[0].data.len(), face_length * 2);
+    header.caps2 = DDSCAPS2_CUBEMAP;
+    legacy[..header.length()].copy_from_slice(&header.as_bytes());
+    assert!(matches!(
+        Reader::new(&legacy[..]).unwrap().to_ktx2(),
+        Err(TextureError::UnsupportedLayout)
+    ));
+    header.caps2 = DDSCAPS2_CUBEMAP | DDSCAPS2_CUBEMAP_ALL_FACES;
+
+    // X8R8G8B8 without an alpha mask gets opaque alpha
+    header.pixel_format.flags = DDPF_RGB;
+    header.pixel_format.a_bit_mask = 0;
+    legacy[..header.length()].copy_from_slice(&header.as_bytes());
+    let writer = Reader::new(&legacy[..]).unwrap().to_ktx2().unwrap();
+    assert_eq!(writer.header.format, Some(Format::B8G8R8A8_UNORM));
+    assert_eq!(writer.levels[0].data[..4], [0, 0, 0, 0xff]);
+
+    // Luminance and alpha is swizzled back into gray
+    header.pixel_format = PixelFormat {
+        flags: DDPF_LUMINANCE | DDPF_ALPHAPIXELS,
+        four_cc: 0,
+        rgb_bit_count: 16,
+        r_bit_mask: 0xff,
+        g_bit_mask: 0,
+        b_bit_mask: 0,
+        a_bit_mask: 0xff00,
+    };
+    legacy[..header.length()].copy_from_slice(&header.as_bytes());
+    let writer = Reader::new(&legacy[..]).unwrap().to_ktx2().unwrap();
+    assert_eq!(writer.header.format, Some(Format::R8G8_UNORM));
+    assert_eq!(writer.key_value_data["KTXswizzle"], b"rrrg\0");
+
+    // DXT2 is premultiplied BC2
+    header.caps2 = 0;
+    header.pixel_format = PixelFormat {
+        flags: DDPF_FOURCC,
+        four_cc: u32::from_le_bytes(*b"DXT2"),
+        ..Default::default()
+    };
+    let mut dxt2 = header.as_bytes();
+    dxt2.resize(dxt2.len() + (header.width * header.height) as usize, 0);
+    let file = Reader::new(&dxt2[..]).unwrap().to_ktx2().unwrap().write();
+    let ktx2 = crate::Reader::new(&file[..]).unwrap();
+    assert_eq!(ktx2.header().format, Some(Format::BC2_UNORM_BLOCK));
+    assert_eq!(ktx2.alpha_mode(), Some(crate::AlphaMode::Premultiplied));
+
+    // Premultiplied alpha of DX10 formats is written to the alpha mode of misc_flags2
+    let mut bc7 = crate::Writer::cubemap(Format::BC7_UNORM_BLOCK, 4, [Some(&[0; 16]); 6]).unwrap();
+    bc7.set_alpha_premultiplied(true);
+    let dds = from_ktx2(&crate::Reader::new(bc7.write()).unwrap()).unwrap();
+    let dx10 = Reader::new(&dds[..]).unwrap().header().dx10.unwrap();
+    assert_eq!(dx10.misc_flags2 & 0x7, ALPHA_MODE_PREMULTIPLIED);
+
+    // Mip map counts beyond the full mip chain
+    let mut header = dds_reader.header();
+    header.mip_map_count = u32::MAX;
+    assert_eq!(header.level_count(), reader.header().level_count);
+
+    // Array sizes whose image count overflows
+    let mut header = dds_reader.header();
+    header.dx10.as_mut().unwrap().array_size = u32::MAX;
+    header.dx10.as_mut().unwrap().misc_flag = RESOURCE_MISC_TEXTURECUBE;
+    let overflow = header.as_bytes();
+    assert!(matches!(
+        Reader::new(&overflow[..]).unwrap().to_ktx2(),
+        Err(TextureError::InvalidLength)
+    ));
+}
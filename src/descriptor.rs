@@ -0,0 +1,136 @@
+use crate::{BasicDataFormatDescriptor, DataFormatDescriptor, DataFormatDescriptorHeader, ParseError};
+use core::convert::{Infallible, TryInto};
+
+/// Parser for vendor-specific data format descriptor blocks, used by [`DataFormatDescriptor::block`]
+///
+/// Implementations check the `vendor_id` and `descriptor_type` of the header and return `None` for blocks they don't
+/// know. [`Infallible`] knows no blocks.
+pub trait VendorDescriptorBlock<'data>: Sized {
+    fn parse(header: &DataFormatDescriptorHeader, data: &'data [u8]) -> Option<Result<Self, ParseError>>;
+}
+
+impl<'data> VendorDescriptorBlock<'data> for Infallible {
+    fn parse(_: &DataFormatDescriptorHeader, _: &'data [u8]) -> Option<Result<Self, ParseError>> {
+        None
+    }
+}
+
+/// Data format descriptor block parsed according to its header
+pub enum DescriptorBlock<'data, Vendor = Infallible> {
+    Basic(BasicDataFormatDescriptor<'data>),
+    AdditionalPlanes(AdditionalPlanes<'data>),
+    AdditionalDimensions(AdditionalDimensions<'data>),
+    /// Block recognized by the [`VendorDescriptorBlock`] parser
+    Vendor(Vendor),
+    Unknown(DataFormatDescriptor<'data>),
+}
+
+impl DataFormatDescriptorHeader {
+    pub const ADDITIONAL_PLANES: Self = Self {
+        vendor_id: 0,
+        descriptor_type: 0x6001,
+        version_number: 0,
+    };
+
+    pub const ADDITIONAL_DIMENSIONS: Self = Self {
+        vendor_id: 0,
+        descriptor_type: 0x6002,
+        version_number: 0,
+    };
+}
+
+impl<'data> DataFormatDescriptor<'data> {
+    /// Parse the block as one of the Khronos descriptor blocks or a vendor-specific one
+    ///
+    /// Use [`Infallible`] as `Vendor` to only parse the Khronos blocks.
+    pub fn block<Vendor: VendorDescriptorBlock<'data>>(&self) -> Result<DescriptorBlock<'data, Vendor>, ParseError> {
+        Ok(if self.header == DataFormatDescriptorHeader::BASIC {
+            DescriptorBlock::Basic(BasicDataFormatDescriptor::parse(self.data)?)
+        } else if self.header == DataFormatDescriptorHeader::ADDITIONAL_PLANES {
+            DescriptorBlock::AdditionalPlanes(AdditionalPlanes { data: self.data })
+        } else if self.header == DataFormatDescriptorHeader::ADDITIONAL_DIMENSIONS {
+            DescriptorBlock::AdditionalDimensions(AdditionalDimensions { data: self.data })
+        } else if let Some(vendor) = Vendor::parse(&self.header, self.data) {
+            DescriptorBlock::Vendor(vendor?)
+        } else {
+            DescriptorBlock::Unknown(*self)
+        })
+    }
+}
+
+/// Khronos descriptor block giving the size of more or larger planes than the basic descriptor can describe
+pub struct AdditionalPlanes<'data> {
+    data: &'data [u8],
+}
+
+impl<'data> AdditionalPlanes<'data> {
+    /// Number of bytes in each plane of a texel block, stored as 32-bit values
+    pub fn bytes_planes(&self) -> impl Iterator<Item = u32> + 'data {
+        self.data
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+/// Khronos descriptor block giving more or larger texel block dimensions than the basic descriptor can describe
+pub struct AdditionalDimensions<'data> {
+    data: &'data [u8],
+}
+
+impl<'data> AdditionalDimensions<'data> {
+    /// Size of each texel block dimension, stored as 16-bit values of the size minus one
+    pub fn texel_block_dimensions(&self) -> impl Iterator<Item = u32> + 'data {
+        self.data
+            .chunks_exact(2)
+            .map(|bytes| u32::from(u16::from_le_bytes(bytes.try_into().unwrap())) + 1)
+    }
+}
+
+#[test]
+fn test_descriptor_blocks() {
+    struct Vendor(u32);
+
+    impl<'data> VendorDescriptorBlock<'data> for Vendor {
+        fn parse(header: &DataFormatDescriptorHeader, data: &'data [u8]) -> Option<Result<Self, ParseError>> {
+            if header.vendor_id != 0x1234 {
+                return None;
+            }
+            Some(match data.try_into() {
+                Ok(bytes) => Ok(Self(u32::from_le_bytes(bytes))),
+                Err(_) => Err(ParseError::UnexpectedEnd),
+            })
+        }
+    }
+
+    let descriptor = |vendor_id, descriptor_type, data| DataFormatDescriptor {
+        header: DataFormatDescriptorHeader {
+            vendor_id,
+            descriptor_type,
+            version_number: 0,
+        },
+        data,
+    };
+
+    match descriptor(0, 0x6001, &[16, 0, 0, 0, 8, 0, 0, 0]).block::<Infallible>() {
+        Ok(DescriptorBlock::AdditionalPlanes(planes)) => assert!(planes.bytes_planes().eq([16, 8])),
+        _ => panic!("expected additional planes"),
+    }
+    match descriptor(0, 0x6002, &[3, 0, 0, 1]).block::<Infallible>() {
+        Ok(DescriptorBlock::AdditionalDimensions(dimensions)) => {
+            assert!(dimensions.texel_block_dimensions().eq([4, 257]))
+        }
+        _ => panic!("expected additional dimensions"),
+    }
+    assert!(matches!(
+        descriptor(0x1234, 1, &[7, 0, 0, 0]).block(),
+        Ok(DescriptorBlock::Vendor(Vendor(7)))
+    ));
+    assert!(matches!(
+        descriptor(0x1234, 1, &[7]).block::<Vendor>(),
+        Err(ParseError::UnexpectedEnd)
+    ));
+    assert!(matches!(
+        descriptor(0x5678, 1, &[]).block::<Vendor>(),
+        Ok(DescriptorBlock::Unknown(_))
+    ));
+}
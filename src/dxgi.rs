@@ -0,0 +1,111 @@
+//! Conversion between [`Format`] and `DXGI_FORMAT` values, as used by Direct3D and the `KTXdxgiFormat__` key.
+
+use crate::Format;
+use core::convert::TryInto;
+
+/// Key of the metadata entry containing the `DXGI_FORMAT` of textures whose format is not a Vulkan format
+pub const KEY: &str = "KTXdxgiFormat__";
+
+// BC1 formats without alpha come after their RGBA counterparts, so the reverse mapping prefers RGBA.
+const FORMATS: &[(Format, u32)] = &[
+    (Format::R32G32B32A32_SFLOAT, 2),
+    (Format::R32G32B32A32_UINT, 3),
+    (Format::R32G32B32A32_SINT, 4),
+    (Format::R32G32B32_SFLOAT, 6),
+    (Format::R32G32B32_UINT, 7),
+    (Format::R32G32B32_SINT, 8),
+    (Format::R16G16B16A16_SFLOAT, 10),
+    (Format::R16G16B16A16_UNORM, 11),
+    (Format::R16G16B16A16_UINT, 12),
+    (Format::R16G16B16A16_SNORM, 13),
+    (Format::R16G16B16A16_SINT, 14),
+    (Format::R32G32_SFLOAT, 16),
+    (Format::R32G32_UINT, 17),
+    (Format::R32G32_SINT, 18),
+    (Format::D32_SFLOAT_S8_UINT, 20),
+    (Format::A2B10G10R10_UNORM_PACK32, 24),
+    (Format::A2B10G10R10_UINT_PACK32, 25),
+    (Format::B10G11R11_UFLOAT_PACK32, 26),
+    (Format::R8G8B8A8_UNORM, 28),
+    (Format::R8G8B8A8_SRGB, 29),
+    (Format::R8G8B8A8_UINT, 30),
+    (Format::R8G8B8A8_SNORM, 31),
+    (Format::R8G8B8A8_SINT, 32),
+    (Format::R16G16_SFLOAT, 34),
+    (Format::R16G16_UNORM, 35),
+    (Format::R16G16_UINT, 36),
+    (Format::R16G16_SNORM, 37),
+    (Format::R16G16_SINT, 38),
+    (Format::D32_SFLOAT, 40),
+    (Format::R32_SFLOAT, 41),
+    (Format::R32_UINT, 42),
+    (Format::R32_SINT, 43),
+    (Format::D24_UNORM_S8_UINT, 45),
+    (Format::R8G8_UNORM, 49),
+    (Format::R8G8_UINT, 50),
+    (Format::R8G8_SNORM, 51),
+    (Format::R8G8_SINT, 52),
+    (Format::R16_SFLOAT, 54),
+    (Format::D16_UNORM, 55),
+    (Format::R16_UNORM, 56),
+    (Format::R16_UINT, 57),
+    (Format::R16_SNORM, 58),
+    (Format::R16_SINT, 59),
+    (Format::R8_UNORM, 61),
+    (Format::R8_UINT, 62),
+    (Format::R8_SNORM, 63),
+    (Format::R8_SINT, 64),
+    (Format::E5B9G9R9_UFLOAT_PACK32, 67),
+    (Format::BC1_RGBA_UNORM_BLOCK, 71),
+    (Format::BC1_RGB_UNORM_BLOCK, 71),
+    (Format::BC1_RGBA_SRGB_BLOCK, 72),
+    (Format::BC1_RGB_SRGB_BLOCK, 72),
+    (Format::BC2_UNORM_BLOCK, 74),
+    (Format::BC2_SRGB_BLOCK, 75),
+    (Format::BC3_UNORM_BLOCK, 77),
+    (Format::BC3_SRGB_BLOCK, 78),
+    (Format::BC4_UNORM_BLOCK, 80),
+    (Format::BC4_SNORM_BLOCK, 81),
+    (Format::BC5_UNORM_BLOCK, 83),
+    (Format::BC5_SNORM_BLOCK, 84),
+    (Format::R5G6B5_UNORM_PACK16, 85),
+    (Format::A1R5G5B5_UNORM_PACK16, 86),
+    (Format::B8G8R8A8_UNORM, 87),
+    (Format::B8G8R8A8_SRGB, 91),
+    (Format::BC6H_UFLOAT_BLOCK, 95),
+    (Format::BC6H_SFLOAT_BLOCK, 96),
+    (Format::BC7_UNORM_BLOCK, 98),
+    (Format::BC7_SRGB_BLOCK, 99),
+];
+
+/// The `DXGI_FORMAT` equivalent to `format`, if there is one
+///
+/// BC1 formats without alpha map to the BC1 DXGI formats, which always decode alpha.
+pub fn from_format(format: Format) -> Option<u32> {
+    FORMATS
+        .iter()
+        .find(|&&(candidate, _)| candidate == format)
+        .map(|&(_, dxgi_format)| dxgi_format)
+}
+
+/// The format equivalent to a `DXGI_FORMAT`, if there is one
+pub fn to_format(dxgi_format: u32) -> Option<Format> {
+    FORMATS
+        .iter()
+        .find(|&&(_, candidate)| candidate == dxgi_format)
+        .map(|&(format, _)| format)
+}
+
+/// Parse the value of a [`KEY`] metadata entry
+pub fn parse_value(value: &[u8]) -> Option<u32> {
+    Some(u32::from_le_bytes(value.get(..4)?.try_into().unwrap()))
+}
+
+#[test]
+fn test_dxgi_round_trip() {
+    for &(format, dxgi_format) in FORMATS {
+        assert_eq!(from_format(format), Some(dxgi_format));
+        assert_eq!(from_format(to_format(dxgi_format).unwrap()), Some(dxgi_format));
+    }
+    assert_eq!(to_format(71), Some(Format::BC1_RGBA_UNORM_BLOCK));
+}
@@ -0,0 +1,91 @@
+use crate::{Reader, TextureError, Writer};
+use std::{collections::BTreeMap, string::String, vec::Vec};
+
+/// Edits the key-value data of a texture, leaving its other sections unchanged
+///
+/// Writing the edited file recomputes the [`Index`](crate::Index) and level byte offsets, which shift along with the
+/// size of the key-value data.
+pub struct MetadataEditor<'a, Data: AsRef<[u8]>> {
+    reader: &'a Reader<Data>,
+    key_value_data: BTreeMap<String, Vec<u8>>,
+}
+
+impl<'a, Data: AsRef<[u8]>> MetadataEditor<'a, Data> {
+    pub fn new(reader: &'a Reader<Data>) -> Self {
+        Self {
+            reader,
+            key_value_data: reader
+                .key_value_data()
+                .map(|(key, value)| (key.into(), value.to_vec()))
+                .collect(),
+        }
+    }
+
+    /// Key-value pairs in key order, including any edits
+    pub fn key_value_data(&self) -> &BTreeMap<String, Vec<u8>> {
+        &self.key_value_data
+    }
+
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.key_value_data.get(key).map(Vec::as_slice)
+    }
+
+    /// Add an entry or replace the value of an existing one
+    ///
+    /// Returns [`TextureError::InvalidKey`] if the key is empty or contains a NUL, which terminates keys in the file.
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<Vec<u8>>) -> Result<(), TextureError> {
+        let key = key.into();
+        if key.is_empty() || key.contains('\0') {
+            return Err(TextureError::InvalidKey);
+        }
+        self.key_value_data.insert(key, value.into());
+        Ok(())
+    }
+
+    /// Set an entry to a string value, adding the NUL terminator used by the standard string entries such as
+    /// `KTXwriter` and `KTXswizzle`
+    pub fn set_str(&mut self, key: impl Into<String>, value: &str) -> Result<(), TextureError> {
+        self.set(key, [value.as_bytes(), b"\0"].concat())
+    }
+
+    /// Remove an entry, returning its value if it was present
+    pub fn remove(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.key_value_data.remove(key)
+    }
+
+    /// Serialize the texture with the edited key-value data
+    pub fn write(&self) -> Vec<u8> {
+        let mut writer = Writer::from_reader(self.reader);
+        writer.key_value_data = self.key_value_data.clone();
+        writer.write()
+    }
+}
+
+#[test]
+fn test_metadata_editor() {
+    let file = include_bytes!("../data/test_tex.ktx2");
+    let reader = Reader::new(&file[..]).unwrap();
+
+    let mut editor = MetadataEditor::new(&reader);
+    assert_eq!(editor.get("KTXorientation"), Some(&b"rd\0"[..]));
+    editor.set_str("KTXwriter", "build 42").unwrap();
+    editor.set_str("KTXswizzle", "rgb1").unwrap();
+    assert!(matches!(editor.set("", *b"value"), Err(TextureError::InvalidKey)));
+    assert!(matches!(
+        editor.set_str("KTX\0writer", "value"),
+        Err(TextureError::InvalidKey)
+    ));
+    assert!(editor.remove("KTXorientation").is_some());
+
+    let edited = editor.write();
+    let edited = Reader::new(&edited[..]).unwrap();
+    assert_eq!(
+        edited.key_value_data().collect::<Vec<_>>(),
+        [("KTXswizzle", &b"rgb1\0"[..]), ("KTXwriter", &b"build 42\0"[..])]
+    );
+    assert!(edited.levels().zip(reader.levels()).all(|(a, b)| a.data == b.data));
+    assert_eq!(
+        edited.data_format_descriptors().count(),
+        reader.data_format_descriptors().count()
+    );
+}
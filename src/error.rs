@@ -29,3 +29,43 @@ impl fmt::Display for ParseError {
         }
     }
 }
+
+/// Error, that happened when processing texture data.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TextureError {
+    /// The operation doesn't support the texture format
+    UnsupportedFormat,
+    /// Texture data doesn't match the texture dimensions
+    InvalidLength,
+    /// The operation doesn't support the supercompression scheme of the texture
+    UnsupportedSupercompression,
+    /// Supercompressed level data couldn't be decompressed
+    DecompressionFailed,
+    /// Textures to be combined differ in format, dimensions or layout
+    IncompatibleTextures,
+    /// The compression level is outside of the range supported by the supercompression scheme
+    InvalidCompressionLevel,
+    /// The operation doesn't support the dimensions, layers or faces of the texture
+    UnsupportedLayout,
+    /// A key-value data key is empty or contains a NUL
+    InvalidKey,
+}
+
+#[cfg(feature = "std")]
+impl Error for TextureError {}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            TextureError::UnsupportedFormat => f.pad("unsupported texture format"),
+            TextureError::InvalidLength => f.pad("texture data doesn't match the texture dimensions"),
+            TextureError::UnsupportedSupercompression => f.pad("unsupported supercompression scheme"),
+            TextureError::DecompressionFailed => f.pad("failed to decompress level data"),
+            TextureError::IncompatibleTextures => f.pad("incompatible textures"),
+            TextureError::InvalidCompressionLevel => f.pad("compression level out of range"),
+            TextureError::UnsupportedLayout => f.pad("unsupported texture layout"),
+            TextureError::InvalidKey => f.pad("invalid key-value data key"),
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Decoders for ETC2 and EAC blocks, following the
+//! [Khronos Data Format Specification](https://registry.khronos.org/DataFormat/specs/1.3/dataformat.1.3.html#ETC2).
+//!
+//! Texels are written in row-major order as RGBA values in the format's range, with missing channels set to 0 and
+//! missing alpha to 1.
+
+use core::convert::TryInto;
+
+/// Intensity modifiers of individual and differential mode sub-blocks
+const MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+/// Distances of T and H mode paint colors
+const DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+#[rustfmt::skip]
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14], [-3, -7, -10, -13, 2, 6, 9, 12], [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12], [-3, -6, -8, -12, 2, 5, 7, 11], [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10], [-3, -5, -8, -11, 2, 4, 7, 10], [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9], [-2, -4, -8, -10, 1, 3, 7, 9], [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9], [-1, -2, -3, -10, 0, 1, 2, 9], [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+/// Decode an ETC2 RGB block, whose texels with pixel index 2 are transparent black in blocks without the opaque bit
+/// if `punch_through` is set
+pub(crate) fn decode_etc2(block: &[u8], punch_through: bool, texels: &mut [[f32; 4]]) {
+    let bits = u64::from_be_bytes(block[..8].try_into().unwrap());
+    let field = |start: u32, count: u32| (bits >> start & ((1 << count) - 1)) as i32;
+    // Pixel indices are stored in column-major order, with the most significant bits first
+    let pixel_index = |x: usize, y: usize| {
+        let bit = (x * 4 + y) as u32;
+        (field(bit + 16, 1) << 1 | field(bit, 1)) as usize
+    };
+    let extend4 = |value: i32| value << 4 | value;
+    let extend5 = |value: i32| value << 3 | value >> 2;
+    let differential = field(33, 1) != 0;
+    let opaque = !punch_through || differential;
+
+    let mut colors = [[0, 0, 0, 255]; 16];
+    let mut paint = |paint_colors: [[i32; 3]; 4]| {
+        for (index, color) in colors.iter_mut().enumerate() {
+            let pixel_index = pixel_index(index % 4, index / 4);
+            *color = if !opaque && pixel_index == 2 {
+                [0; 4]
+            } else {
+                let [r, g, b] = paint_colors[pixel_index];
+                [r, g, b, 255]
+            };
+        }
+    };
+
+    let delta = |start: u32| (field(start, 3) << 29) >> 29;
+    let (r, g, b) = (field(59, 5), field(51, 5), field(43, 5));
+    if (punch_through || differential) && !(0..32).contains(&(r + delta(56))) {
+        // T mode
+        let c1 = [field(59, 2) << 2 | field(56, 2), field(52, 4), field(48, 4)].map(extend4);
+        let c2 = [field(44, 4), field(40, 4), field(36, 4)].map(extend4);
+        let distance = DISTANCES[(field(34, 2) << 1 | field(32, 1)) as usize];
+        paint([
+            c1,
+            c2.map(|value| value + distance),
+            c2,
+            c2.map(|value| value - distance),
+        ]);
+    } else if (punch_through || differential) && !(0..32).contains(&(g + delta(48))) {
+        // H mode
+        let c1 = [
+            field(59, 4),
+            field(56, 3) << 1 | field(52, 1),
+            field(51, 1) << 3 | field(47, 3),
+        ];
+        let c2 = [field(43, 4), field(39, 4), field(35, 4)];
+        let key = |[r, g, b]: [i32; 3]| r << 8 | g << 4 | b;
+        let ordering = i32::from(key(c1) >= key(c2));
+        let distance = DISTANCES[(field(34, 1) << 2 | field(32, 1) << 1 | ordering) as usize];
+        let (c1, c2) = (c1.map(extend4), c2.map(extend4));
+        paint([
+            c1.map(|value| value + distance),
+            c1.map(|value| value - distance),
+            c2.map(|value| value + distance),
+            c2.map(|value| value - distance),
+        ]);
+    } else if (punch_through || differential) && !(0..32).contains(&(b + delta(40))) {
+        // Planar mode, which is always opaque
+        let extend6 = |value: i32| value << 2 | value >> 4;
+        let extend7 = |value: i32| value << 1 | value >> 6;
+        let origin = [
+            extend6(field(57, 6)),
+            extend7(field(56, 1) << 6 | field(49, 6)),
+            extend6(field(48, 1) << 5 | field(43, 2) << 3 | field(39, 3)),
+        ];
+        let horizontal = [
+            extend6(field(34, 5) << 1 | field(32, 1)),
+            extend7(field(25, 7)),
+            extend6(field(19, 6)),
+        ];
+        let vertical = [extend6(field(13, 6)), extend7(field(6, 7)), extend6(field(0, 6))];
+        for (index, color) in colors.iter_mut().enumerate() {
+            let (x, y) = ((index % 4) as i32, (index / 4) as i32);
+            for channel in 0..3 {
+                let (o, h, v) = (origin[channel], horizontal[channel], vertical[channel]);
+                color[channel] = (x * (h - o) + y * (v - o) + 4 * o + 2) >> 2;
+            }
+        }
+    } else {
+        // Individual or differential mode, with two sub-blocks of 2x4 texels or 4x2 if the flip bit is set
+        let bases = if punch_through || differential {
+            [
+                [r, g, b].map(extend5),
+                [r + delta(56), g + delta(48), b + delta(40)].map(extend5),
+            ]
+        } else {
+            [
+                [field(60, 4), field(52, 4), field(44, 4)].map(extend4),
+                [field(56, 4), field(48, 4), field(40, 4)].map(extend4),
+            ]
+        };
+        let tables = [field(37, 3), field(34, 3)];
+        let flip = field(32, 1) != 0;
+        for (index, color) in colors.iter_mut().enumerate() {
+            let (x, y) = (index % 4, index / 4);
+            let sub_block = usize::from(if flip { y >= 2 } else { x >= 2 });
+            let [small, large] = MODIFIERS[tables[sub_block] as usize];
+            let pixel_index = pixel_index(x, y);
+            let modifier = match pixel_index {
+                0 if !opaque => 0,
+                0 => small,
+                1 => large,
+                2 => -small,
+                _ => -large,
+            };
+            *color = if !opaque && pixel_index == 2 {
+                [0; 4]
+            } else {
+                let [r, g, b] = bases[sub_block].map(|value| value + modifier);
+                [r, g, b, 255]
+            };
+        }
+    }
+
+    for (texel, color) in texels.iter_mut().zip(colors) {
+        *texel = color.map(|value| value.clamp(0, 255) as f32 / 255.0);
+    }
+}
+
+/// Decode an ETC2 RGBA block, an EAC block of 8-bit alpha followed by an ETC2 RGB block
+pub(crate) fn decode_etc2_alpha(block: &[u8], texels: &mut [[f32; 4]]) {
+    decode_etc2(&block[8..], false, texels);
+    for (texel, alpha) in texels.iter_mut().zip(decode_eac(&block[..8], Eac::Alpha)) {
+        texel[3] = alpha;
+    }
+}
+
+/// Decode an EAC R11 block into red
+pub(crate) fn decode_eac_r11(block: &[u8], signed: bool, texels: &mut [[f32; 4]]) {
+    let kind = if signed { Eac::Signed } else { Eac::Unsigned };
+    for (texel, red) in texels.iter_mut().zip(decode_eac(block, kind)) {
+        *texel = [red, 0.0, 0.0, 1.0];
+    }
+}
+
+/// Decode an EAC RG11 block, an R11 block for red followed by one for green
+pub(crate) fn decode_eac_rg11(block: &[u8], signed: bool, texels: &mut [[f32; 4]]) {
+    let kind = if signed { Eac::Signed } else { Eac::Unsigned };
+    let red = decode_eac(&block[..8], kind);
+    let green = decode_eac(&block[8..], kind);
+    for (index, texel) in texels.iter_mut().enumerate() {
+        *texel = [red[index], green[index], 0.0, 1.0];
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Eac {
+    Alpha,
+    Unsigned,
+    Signed,
+}
+
+/// Decode an 8-byte EAC block of a single channel in row-major order
+fn decode_eac(block: &[u8], kind: Eac) -> [f32; 16] {
+    let bits = u64::from_be_bytes(block[..8].try_into().unwrap());
+    let base = (bits >> 56) as u8;
+    let multiplier = (bits >> 52 & 0xF) as i32;
+    let modifiers = EAC_MODIFIERS[(bits >> 48 & 0xF) as usize];
+
+    let mut texels = [0.0; 16];
+    for (index, texel) in texels.iter_mut().enumerate() {
+        let (x, y) = (index % 4, index / 4);
+        let modifier = modifiers[(bits >> (45 - 3 * (x * 4 + y)) & 0x7) as usize];
+        // 11-bit values use a multiplier of 1/8 when it is 0
+        let offset = if multiplier == 0 {
+            modifier
+        } else {
+            modifier * multiplier * 8
+        };
+        *texel = match kind {
+            Eac::Alpha => (i32::from(base) + modifier * multiplier).clamp(0, 255) as f32 / 255.0,
+            Eac::Unsigned => (i32::from(base) * 8 + 4 + offset).clamp(0, 2047) as f32 / 2047.0,
+            Eac::Signed => {
+                let base = i32::from(base as i8).max(-127);
+                (base * 8 + offset).clamp(-1023, 1023) as f32 / 1023.0
+            }
+        };
+    }
+    texels
+}
+
+#[test]
+fn test_decode_etc2_blocks() {
+    let decode = |bits: u64, punch_through| {
+        let mut texels = [[0.0; 4]; 16];
+        decode_etc2(&bits.to_be_bytes(), punch_through, &mut texels);
+        texels
+    };
+
+    // Individual mode with a red left sub-block, the texel below the first one using the largest negative modifier
+    let texels = decode(0xF0 << 56 | 1 << 17 | 1 << 1, false);
+    assert_eq!(texels[0], [1.0, 2.0 / 255.0, 2.0 / 255.0, 1.0]);
+    assert_eq!(texels[2], [2.0 / 255.0, 2.0 / 255.0, 2.0 / 255.0, 1.0]);
+    assert_eq!(texels[4], [247.0 / 255.0, 0.0, 0.0, 1.0]);
+
+    // Planar mode, selected by overflowing blue, with a blue origin fading to black horizontally
+    let texels = decode(0b11111 << 43 | 1 << 40 | 1 << 33, false);
+    assert_eq!(texels[0], [0.0, 0.0, 105.0 / 255.0, 1.0]);
+    assert_eq!(texels[1], [0.0, 0.0, 79.0 / 255.0, 1.0]);
+
+    // Without the opaque bit, pixel index 2 is transparent and index 0 has no modifier
+    let texels = decode(1 << 16, true);
+    assert_eq!(texels[0], [0.0; 4]);
+    assert_eq!(texels[1], [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(decode(1 << 16, false)[0], [0.0, 0.0, 0.0, 1.0]);
+
+    // A base of 128 with the first modifier of the first table, and full alpha clamped from above
+    let mut texels = [[0.0; 4]; 16];
+    decode_eac_r11(&(0x8010_u64 << 48).to_be_bytes(), false, &mut texels);
+    assert_eq!(texels[0], [1004.0 / 2047.0, 0.0, 0.0, 1.0]);
+    let mut block = [0; 16];
+    block[..8].copy_from_slice(&(0xFF10_u64 << 48 | 0x7 << 45).to_be_bytes());
+    decode_etc2_alpha(&block, &mut texels);
+    assert_eq!(texels[0], [2.0 / 255.0, 2.0 / 255.0, 2.0 / 255.0, 1.0]);
+}
@@ -0,0 +1,1055 @@
+//! Encoder of ETC1S textures with [BasisLZ](https://registry.khronos.org/KTX/specs/2.0/ktxspec.v2.html#basisLZ)
+//! supercompression.
+//!
+//! Each block gets one endpoint, a 5-bit base color and an intensity table shared by both halves of the ETC1 block,
+//! and one selector per texel. The endpoints and selectors of all blocks are clustered into the global codebooks,
+//! and the endpoints are then refitted to the selectors chosen for their blocks. Slices predict endpoint indices from
+//! the left and upper blocks and code selector indices directly, without the selector history buffer.
+
+use crate::{
+    basis::{Block, Source, SourceChannels},
+    etc1s_data_format_descriptors, BasisLzGlobalData, Etc1sChannels, ImageDesc, ImageFlags, Reader,
+    SupercompressionScheme, TextureError, Writer, WriterLevel,
+};
+use core::cmp::Reverse;
+use std::{collections::BinaryHeap, vec, vec::Vec};
+
+/// Options for [`Reader::encode_etc1s`], with the defaults of `toktx`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Etc1sOptions {
+    /// Quality from 1 to 255 setting the codebook sizes, as `toktx --qlevel` does
+    pub quality: u8,
+    /// Maximum number of endpoints, overriding the number given by `quality`
+    pub max_endpoints: Option<u16>,
+    /// Maximum number of selectors, overriding the number given by `quality`
+    pub max_selectors: Option<u16>,
+}
+
+impl Default for Etc1sOptions {
+    fn default() -> Self {
+        Self {
+            quality: 128,
+            max_endpoints: None,
+            max_selectors: None,
+        }
+    }
+}
+
+/// Largest size of each codebook, as in `basisu`, which keeps slice symbols within the 14-bit Huffman tables
+const MAX_CODEBOOK_SIZE: usize = 16128;
+
+/// Size of the selector history buffer written to the tables, which slices never refer to
+const SELECTOR_HISTORY_BUFFER_SIZE: u32 = 64;
+
+/// Intensity modifiers of ETC1 sub-blocks
+const INTENSITIES: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+/// Modifiers of an ETC1 intensity table in selector order, from the most negative to the most positive
+pub(crate) fn modifiers(intensity: usize) -> [i32; 4] {
+    let [small, large] = INTENSITIES[intensity];
+    [-large, -small, small, large]
+}
+
+/// 8-bit value of a 5-bit ETC1 color component
+pub(crate) fn expand5(value: u32) -> i32 {
+    ((value << 3) | (value >> 2)) as i32
+}
+
+fn quantize5(value: f32) -> u8 {
+    (value * 31.0 / 255.0).round().clamp(0.0, 31.0) as u8
+}
+
+/// RGB texels of a block in one slice
+type Texels = [[u8; 3]; 16];
+
+/// Base color and intensity table of a block
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+struct Endpoint {
+    color: [u8; 3],
+    intensity: u8,
+}
+
+impl Endpoint {
+    /// Colors of the four selectors
+    fn colors(self) -> [[i32; 3]; 4] {
+        let base = self.color.map(|value| expand5(value.into()));
+        modifiers(self.intensity.into()).map(|modifier| base.map(|value| (value + modifier).clamp(0, 255)))
+    }
+}
+
+fn texel_error(color: &[i32; 3], texel: &[u8; 3]) -> u32 {
+    color
+        .iter()
+        .zip(texel)
+        .map(|(&color, &value)| (color - i32::from(value)).pow(2) as u32)
+        .sum()
+}
+
+/// Selector with the lowest error for a texel, with its error
+fn select(colors: &[[i32; 3]; 4], texel: &[u8; 3]) -> (u32, u8) {
+    (0..4)
+        .map(|selector| (texel_error(&colors[selector], texel), selector as u8))
+        .min()
+        .unwrap()
+}
+
+/// Endpoint with the lowest error for texels, with either the best selector of each texel or the given selectors
+fn fit_endpoint(texels: &[[u8; 3]], selectors: Option<&[u8]>) -> Endpoint {
+    let selector = |index: usize, colors: &[[i32; 3]; 4]| match selectors {
+        Some(selectors) => (
+            texel_error(&colors[selectors[index] as usize], &texels[index]),
+            selectors[index],
+        ),
+        None => select(colors, &texels[index]),
+    };
+    let error = |endpoint: Endpoint| -> u64 {
+        let colors = endpoint.colors();
+        (0..texels.len())
+            .map(|index| u64::from(selector(index, &colors).0))
+            .sum()
+    };
+    let count = texels.len().max(1) as f32;
+    let mut mean = [0.0; 3];
+    for texel in texels {
+        for (mean, &value) in mean.iter_mut().zip(texel) {
+            *mean += f32::from(value) / count;
+        }
+    }
+
+    let mut best = (u64::MAX, Endpoint::default());
+    for intensity in 0..8 {
+        // The base color is the mean of the texels less the modifiers of their selectors
+        let mut base = mean;
+        for _ in 0..2 {
+            let colors = Endpoint {
+                color: base.map(quantize5),
+                intensity,
+            }
+            .colors();
+            let modifiers = modifiers(intensity.into());
+            let mut sums = [0.0; 3];
+            for (index, texel) in texels.iter().enumerate() {
+                let modifier = modifiers[selector(index, &colors).1 as usize] as f32;
+                for (sum, &value) in sums.iter_mut().zip(texel) {
+                    *sum += f32::from(value) - modifier;
+                }
+            }
+            base = sums.map(|sum| sum / count);
+        }
+        let endpoint = Endpoint {
+            color: base.map(quantize5),
+            intensity,
+        };
+        let error = error(endpoint);
+        if error < best.0 {
+            best = (error, endpoint);
+        }
+    }
+
+    let center = best.1;
+    for offset in 0..27 {
+        let offsets = [offset % 3, offset / 3 % 3, offset / 9].map(|offset: i32| offset - 1);
+        let mut endpoint = center;
+        for (value, offset) in endpoint.color.iter_mut().zip(offsets) {
+            *value = (i32::from(*value) + offset).clamp(0, 31) as u8;
+        }
+        let error = error(endpoint);
+        if error < best.0 {
+            best = (error, endpoint);
+        }
+    }
+    best.1
+}
+
+/// Split points into at most `count` clusters by repeatedly splitting the cluster with the largest error in two, and
+/// return the points of each cluster
+fn cluster<const N: usize>(points: &[[f32; N]], count: usize) -> Vec<Vec<usize>> {
+    let centroid = |indices: &[usize]| {
+        let mut centroid = [0.0; N];
+        for &index in indices {
+            for (centroid, value) in centroid.iter_mut().zip(points[index]) {
+                *centroid += value / indices.len() as f32;
+            }
+        }
+        centroid
+    };
+    let distance = |a: &[f32; N], b: &[f32; N]| a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum::<f32>();
+    let error = |indices: &[usize]| {
+        let centroid = centroid(indices);
+        indices
+            .iter()
+            .map(|&index| distance(&points[index], &centroid))
+            .sum::<f32>()
+    };
+
+    let mut clusters: Vec<Vec<usize>> = vec![(0..points.len()).collect()];
+    let mut largest = BinaryHeap::new();
+    largest.push((error(&clusters[0]).to_bits(), 0));
+    while clusters.len() < count {
+        let index = match largest.pop() {
+            Some((error, index)) if error > 0 => index,
+            _ => break,
+        };
+        let indices = &clusters[index];
+
+        // Split at the centroid along the dimension of largest variance, then refine with 2-means
+        let mean = centroid(indices);
+        let dimension = (0..N)
+            .map(|dimension| {
+                let variance: f32 = indices
+                    .iter()
+                    .map(|&index| (points[index][dimension] - mean[dimension]).powi(2))
+                    .sum();
+                (variance.to_bits(), dimension)
+            })
+            .max()
+            .unwrap()
+            .1;
+        let (mut low, mut high): (Vec<usize>, Vec<usize>) = indices
+            .iter()
+            .partition(|&&index| points[index][dimension] < mean[dimension]);
+        for _ in 0..3 {
+            if low.is_empty() || high.is_empty() {
+                break;
+            }
+            let (low_centroid, high_centroid) = (centroid(&low), centroid(&high));
+            let (next_low, next_high) = indices.iter().partition(|&&index| {
+                distance(&points[index], &low_centroid) <= distance(&points[index], &high_centroid)
+            });
+            low = next_low;
+            high = next_high;
+        }
+        if low.is_empty() || high.is_empty() {
+            continue;
+        }
+
+        largest.push((error(&low).to_bits(), index));
+        largest.push((error(&high).to_bits(), clusters.len()));
+        clusters[index] = low;
+        clusters.push(high);
+    }
+    clusters
+}
+
+/// Writer of bits starting from the least significant bit of each byte
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.buffer |= u64::from(value) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Order in which the lengths of the code length code are written
+const CODE_LENGTH_ORDER: [usize; 21] = [17, 18, 19, 20, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15, 16];
+
+/// Canonical Huffman code, with codes stored bit reversed as they're written from the least significant bit
+struct Huffman {
+    lengths: Vec<u8>,
+    codes: Vec<u32>,
+}
+
+impl Huffman {
+    /// Code for symbol frequencies with lengths of at most `max_length`, giving at least two symbols a code as
+    /// decoders require
+    fn new(frequencies: &[u32], max_length: u8) -> Self {
+        let mut frequencies = frequencies.to_vec();
+        frequencies.resize(frequencies.len().max(2), 0);
+        for symbol in 0..2 {
+            if frequencies.iter().filter(|&&frequency| frequency > 0).count() < 2 && frequencies[symbol] == 0 {
+                frequencies[symbol] = 1;
+            }
+        }
+        let lengths = loop {
+            let lengths = code_lengths(&frequencies);
+            if lengths.iter().all(|&length| length <= max_length) {
+                break lengths;
+            }
+            for frequency in frequencies.iter_mut().filter(|frequency| **frequency > 0) {
+                *frequency = frequency.div_ceil(2);
+            }
+        };
+
+        let mut next_code = [0; 18];
+        for length in 1..17 {
+            let count = lengths.iter().filter(|&&other| other == length).count() as u32;
+            next_code[length as usize + 1] = (next_code[length as usize] + count) << 1;
+        }
+        let codes = lengths
+            .iter()
+            .map(|&length| {
+                if length == 0 {
+                    return 0;
+                }
+                let code = next_code[length as usize];
+                next_code[length as usize] += 1;
+                code.reverse_bits() >> (32 - u32::from(length))
+            })
+            .collect();
+        Self { lengths, codes }
+    }
+
+    fn write(&self, writer: &mut BitWriter, symbol: usize) {
+        writer.write(self.codes[symbol], self.lengths[symbol].into());
+    }
+
+    /// Write the code lengths, run-length coded with a code length code
+    fn write_table(&self, writer: &mut BitWriter) {
+        let used = self.lengths.iter().rposition(|&length| length > 0).unwrap() + 1;
+        // Code length symbols with their extra bits
+        let mut symbols: Vec<(usize, u32, u32)> = Vec::new();
+        let mut index = 0;
+        while index < used {
+            let length = self.lengths[index];
+            let run = self.lengths[index..used]
+                .iter()
+                .take_while(|&&other| other == length)
+                .count();
+            if length == 0 && run >= 11 {
+                let run = run.min(138);
+                symbols.push((18, run as u32 - 11, 7));
+                index += run;
+            } else if length == 0 && run >= 3 {
+                let run = run.min(10);
+                symbols.push((17, run as u32 - 3, 3));
+                index += run;
+            } else {
+                symbols.push((length.into(), 0, 0));
+                index += 1;
+                let mut repeats = run - 1;
+                while length > 0 && repeats >= 3 {
+                    let run = if repeats >= 7 { repeats.min(134) } else { repeats.min(6) };
+                    if run >= 7 {
+                        symbols.push((20, run as u32 - 7, 7));
+                    } else {
+                        symbols.push((19, run as u32 - 3, 2));
+                    }
+                    index += run;
+                    repeats -= run;
+                }
+            }
+        }
+
+        let mut frequencies = [0; 21];
+        for &(symbol, _, _) in &symbols {
+            frequencies[symbol] += 1;
+        }
+        let code = Huffman::new(&frequencies, 7);
+        let count = CODE_LENGTH_ORDER
+            .iter()
+            .rposition(|&symbol| code.lengths[symbol] > 0)
+            .unwrap()
+            + 1;
+        writer.write(used as u32, 14);
+        writer.write(count as u32, 5);
+        for &symbol in &CODE_LENGTH_ORDER[..count] {
+            writer.write(code.lengths[symbol].into(), 3);
+        }
+        for (symbol, extra, extra_bits) in symbols {
+            code.write(writer, symbol);
+            writer.write(extra, extra_bits);
+        }
+    }
+}
+
+/// Huffman code lengths of symbol frequencies, with no length limit
+fn code_lengths(frequencies: &[u32]) -> Vec<u8> {
+    let mut parents = vec![usize::MAX; frequencies.len()];
+    let mut nodes: BinaryHeap<Reverse<(u64, usize)>> = frequencies
+        .iter()
+        .enumerate()
+        .filter(|&(_, &frequency)| frequency > 0)
+        .map(|(symbol, &frequency)| Reverse((u64::from(frequency), symbol)))
+        .collect();
+    while nodes.len() > 1 {
+        let Reverse((first, first_node)) = nodes.pop().unwrap();
+        let Reverse((second, second_node)) = nodes.pop().unwrap();
+        let node = parents.len();
+        parents.push(usize::MAX);
+        parents[first_node] = node;
+        parents[second_node] = node;
+        nodes.push(Reverse((first + second, node)));
+    }
+
+    // Parents are created after their children, so depths are known when going backwards
+    let mut depths = vec![0u8; parents.len()];
+    for node in (0..parents.len()).rev() {
+        if parents[node] != usize::MAX {
+            depths[node] = depths[parents[node]].saturating_add(1);
+        }
+    }
+    depths.truncate(frequencies.len());
+    depths
+}
+
+/// Endpoint and selector indices of the blocks of a slice
+struct Slice {
+    width: usize,
+    height: usize,
+    endpoints: Vec<usize>,
+    selectors: Vec<usize>,
+}
+
+/// Symbol of a slice, to be coded with one of the slice Huffman tables
+#[derive(Copy, Clone)]
+enum Symbol {
+    Prediction(usize),
+    EndpointDelta(usize),
+    Selector(usize),
+}
+
+impl Slice {
+    /// Prediction of the endpoint of a block: 0 for the left block, 1 for the upper block and 3 for a delta from the
+    /// previous endpoint index
+    fn prediction(&self, x: usize, y: usize) -> usize {
+        if x >= self.width || y >= self.height {
+            return 0;
+        }
+        let endpoint = self.endpoints[y * self.width + x];
+        if x > 0 && self.endpoints[y * self.width + x - 1] == endpoint {
+            0
+        } else if y > 0 && self.endpoints[(y - 1) * self.width + x] == endpoint {
+            1
+        } else {
+            3
+        }
+    }
+
+    fn symbols(&self, endpoint_count: usize) -> Vec<Symbol> {
+        let mut symbols = Vec::new();
+        let mut previous_endpoint = 0;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                // Predictions are coded together for each 2x2 group of blocks
+                if x % 2 == 0 && y % 2 == 0 {
+                    let prediction = self.prediction(x, y)
+                        | (self.prediction(x + 1, y) << 2)
+                        | (self.prediction(x, y + 1) << 4)
+                        | (self.prediction(x + 1, y + 1) << 6);
+                    symbols.push(Symbol::Prediction(prediction));
+                }
+                let endpoint = self.endpoints[y * self.width + x];
+                if self.prediction(x, y) == 3 {
+                    symbols.push(Symbol::EndpointDelta(
+                        (endpoint + endpoint_count - previous_endpoint) % endpoint_count,
+                    ));
+                }
+                previous_endpoint = endpoint;
+                symbols.push(Symbol::Selector(self.selectors[y * self.width + x]));
+            }
+        }
+        symbols
+    }
+}
+
+fn codebook_size(quality: u8, max: Option<u16>) -> usize {
+    let size = match max {
+        Some(max) => max.into(),
+        None => {
+            let quality = f32::from(quality.max(1) - 1) / 254.0;
+            64 + ((MAX_CODEBOOK_SIZE - 64) as f32 * quality * quality) as usize
+        }
+    };
+    size.clamp(1, MAX_CODEBOOK_SIZE)
+}
+
+/// Texel of a slice for an RGBA texel
+type SliceTexel = fn(&[u8; 4]) -> [u8; 3];
+
+/// Codebooks and slices of an ETC1S texture
+struct Encoded {
+    endpoints: Vec<Endpoint>,
+    selectors: Vec<[u8; 16]>,
+    slices: Vec<Slice>,
+}
+
+/// Encode slices given as their width in blocks and their blocks, clustering into codebooks of the given sizes
+fn encode(slices: &[(usize, Vec<Texels>)], max_endpoints: usize, max_selectors: usize) -> Encoded {
+    let blocks: Vec<&Texels> = slices.iter().flat_map(|(_, blocks)| blocks).collect();
+
+    let block_endpoints: Vec<Endpoint> = blocks.iter().map(|texels| fit_endpoint(&texels[..], None)).collect();
+    let points: Vec<[f32; 4]> = block_endpoints
+        .iter()
+        .map(|endpoint| {
+            let [r, g, b] = endpoint.color.map(|value| expand5(value.into()) as f32);
+            [r, g, b, INTENSITIES[endpoint.intensity as usize][1] as f32]
+        })
+        .collect();
+    let endpoint_clusters = cluster(&points, max_endpoints);
+    let mut block_endpoint_indices = vec![0; blocks.len()];
+    let mut endpoints: Vec<Endpoint> = endpoint_clusters
+        .iter()
+        .enumerate()
+        .map(|(cluster, members)| {
+            let texels: Vec<[u8; 3]> = members.iter().flat_map(|&block| *blocks[block]).collect();
+            for &block in members {
+                block_endpoint_indices[block] = cluster;
+            }
+            fit_endpoint(&texels, None)
+        })
+        .collect();
+
+    let block_selectors: Vec<[u8; 16]> = blocks
+        .iter()
+        .zip(&block_endpoint_indices)
+        .map(|(texels, &endpoint)| {
+            let colors = endpoints[endpoint].colors();
+            texels.map(|texel| select(&colors, &texel).1)
+        })
+        .collect();
+    let points: Vec<[f32; 16]> = block_selectors
+        .iter()
+        .map(|selectors| selectors.map(f32::from))
+        .collect();
+    let selector_clusters = cluster(&points, max_selectors);
+    let mut block_selector_indices = vec![0; blocks.len()];
+    let selectors: Vec<[u8; 16]> = selector_clusters
+        .iter()
+        .enumerate()
+        .map(|(cluster, members)| {
+            let mut errors = [[0u64; 4]; 16];
+            for &block in members {
+                block_selector_indices[block] = cluster;
+                let colors = endpoints[block_endpoint_indices[block]].colors();
+                for (errors, texel) in errors.iter_mut().zip(blocks[block]) {
+                    for (error, color) in errors.iter_mut().zip(&colors) {
+                        *error += u64::from(texel_error(color, texel));
+                    }
+                }
+            }
+            errors.map(|errors| (0..4).min_by_key(|&selector| errors[selector]).unwrap() as u8)
+        })
+        .collect();
+
+    // Refit each endpoint to the texels of its blocks with their selectors from the codebook
+    for (endpoint, members) in endpoints.iter_mut().zip(&endpoint_clusters) {
+        let texels: Vec<[u8; 3]> = members.iter().flat_map(|&block| *blocks[block]).collect();
+        let texel_selectors: Vec<u8> = members
+            .iter()
+            .flat_map(|&block| selectors[block_selector_indices[block]])
+            .collect();
+        *endpoint = fit_endpoint(&texels, Some(&texel_selectors));
+    }
+
+    // Sort endpoints by brightness so neighboring entries of the codebook have small deltas
+    let mut order: Vec<usize> = (0..endpoints.len()).collect();
+    order.sort_by_key(|&index| {
+        let endpoint = endpoints[index];
+        (
+            endpoint.color.iter().map(|&value| u32::from(value)).sum::<u32>(),
+            endpoint.intensity,
+        )
+    });
+    let mut remap = vec![0; endpoints.len()];
+    for (new, &old) in order.iter().enumerate() {
+        remap[old] = new;
+    }
+    let endpoints = order.iter().map(|&index| endpoints[index]).collect();
+
+    let mut start = 0;
+    let slices = slices
+        .iter()
+        .map(|(width, blocks)| {
+            let range = start..start + blocks.len();
+            start += blocks.len();
+            Slice {
+                width: *width,
+                height: blocks.len() / width,
+                endpoints: block_endpoint_indices[range.clone()]
+                    .iter()
+                    .map(|&index| remap[index])
+                    .collect(),
+                selectors: block_selector_indices[range].to_vec(),
+            }
+        })
+        .collect();
+    Encoded {
+        endpoints,
+        selectors,
+        slices,
+    }
+}
+
+impl Encoded {
+    fn endpoints_data(&self) -> Vec<u8> {
+        let grayscale = self
+            .endpoints
+            .iter()
+            .all(|endpoint| endpoint.color[0] == endpoint.color[1] && endpoint.color[0] == endpoint.color[2]);
+        let channels = if grayscale { 1 } else { 3 };
+
+        let mut deltas = Vec::new();
+        let mut previous = Endpoint {
+            color: [16; 3],
+            intensity: 0,
+        };
+        for endpoint in &self.endpoints {
+            let intensity = usize::from(endpoint.intensity.wrapping_sub(previous.intensity) & 7);
+            let mut colors = [0; 3];
+            for ((color, &value), &previous) in colors
+                .iter_mut()
+                .zip(&endpoint.color)
+                .zip(&previous.color)
+                .take(channels)
+            {
+                *color = usize::from(value.wrapping_sub(previous) & 31);
+            }
+            deltas.push((intensity, colors));
+            previous = *endpoint;
+        }
+
+        let (mut color_frequencies, mut intensity_frequencies) = ([0; 32], [0; 8]);
+        for (intensity, colors) in &deltas {
+            intensity_frequencies[*intensity] += 1;
+            for &color in &colors[..channels] {
+                color_frequencies[color] += 1;
+            }
+        }
+        // The color delta table is picked by the previous value, and the same table is used for all of them
+        let color_code = Huffman::new(&color_frequencies, 16);
+        let intensity_code = Huffman::new(&intensity_frequencies, 16);
+
+        let mut writer = BitWriter::default();
+        for _ in 0..3 {
+            color_code.write_table(&mut writer);
+        }
+        intensity_code.write_table(&mut writer);
+        writer.write(grayscale.into(), 1);
+        for (intensity, colors) in deltas {
+            intensity_code.write(&mut writer, intensity);
+            for &color in &colors[..channels] {
+                color_code.write(&mut writer, color);
+            }
+        }
+        writer.finish()
+    }
+
+    fn selectors_data(&self) -> Vec<u8> {
+        let mut writer = BitWriter::default();
+        // Neither the global nor hybrid codebooks are used, and selectors are stored raw
+        writer.write(0, 1);
+        writer.write(0, 1);
+        writer.write(1, 1);
+        for selectors in &self.selectors {
+            for row in selectors.chunks_exact(4) {
+                let byte = row
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (x, &selector)| byte | (u32::from(selector) << (x * 2)));
+                writer.write(byte, 8);
+            }
+        }
+        writer.finish()
+    }
+
+    /// Tables data and the data of each slice
+    fn tables_and_slices(&self) -> (Vec<u8>, Vec<Vec<u8>>) {
+        let endpoint_count = self.endpoints.len();
+        let symbols: Vec<Vec<Symbol>> = self.slices.iter().map(|slice| slice.symbols(endpoint_count)).collect();
+
+        let mut prediction_frequencies = vec![0; 257];
+        let mut delta_frequencies = vec![0; endpoint_count];
+        let mut selector_frequencies = vec![0; self.selectors.len() + SELECTOR_HISTORY_BUFFER_SIZE as usize + 1];
+        for &symbol in symbols.iter().flatten() {
+            match symbol {
+                Symbol::Prediction(value) => prediction_frequencies[value] += 1,
+                Symbol::EndpointDelta(value) => delta_frequencies[value] += 1,
+                Symbol::Selector(value) => selector_frequencies[value] += 1,
+            }
+        }
+        let prediction_code = Huffman::new(&prediction_frequencies, 16);
+        let delta_code = Huffman::new(&delta_frequencies, 16);
+        let selector_code = Huffman::new(&selector_frequencies, 16);
+        let history_code = Huffman::new(&[0; 2], 16);
+
+        let mut writer = BitWriter::default();
+        prediction_code.write_table(&mut writer);
+        delta_code.write_table(&mut writer);
+        selector_code.write_table(&mut writer);
+        history_code.write_table(&mut writer);
+        writer.write(SELECTOR_HISTORY_BUFFER_SIZE, 13);
+        let tables = writer.finish();
+
+        let slices = symbols
+            .iter()
+            .map(|symbols| {
+                let mut writer = BitWriter::default();
+                for &symbol in symbols {
+                    match symbol {
+                        Symbol::Prediction(value) => prediction_code.write(&mut writer, value),
+                        Symbol::EndpointDelta(value) => delta_code.write(&mut writer, value),
+                        Symbol::Selector(value) => selector_code.write(&mut writer, value),
+                    }
+                }
+                writer.finish()
+            })
+            .collect();
+        (tables, slices)
+    }
+}
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// Encode a texture of an 8-bit normalized or sRGB format as ETC1S with BasisLZ supercompression, as
+    /// `toktx --encode etc1s` does
+    ///
+    /// The key/value data is kept, with `KTXswizzle` updated for one and two channel textures, which are stored as
+    /// [`Etc1sChannels::Rrr`] and [`Etc1sChannels::RrrGgg`]. The source texture can't be supercompressed.
+    pub fn encode_etc1s(&self, options: &Etc1sOptions) -> Result<Writer, TextureError> {
+        let source = Source::new(self)?;
+        let (channels, rgb, alpha): (_, SliceTexel, Option<SliceTexel>) = match source.channels {
+            SourceChannels::R => (Etc1sChannels::Rrr, |&[r, ..]| [r; 3], None),
+            SourceChannels::Rg => (Etc1sChannels::RrrGgg, |&[r, ..]| [r; 3], Some(|&[_, g, ..]| [g; 3])),
+            SourceChannels::Rgb => (Etc1sChannels::Rgb, |&[r, g, b, _]| [r, g, b], None),
+            SourceChannels::Rgba => (
+                Etc1sChannels::RgbAaa,
+                |&[r, g, b, _]| [r, g, b],
+                Some(|&[.., a]| [a; 3]),
+            ),
+        };
+
+        // Slices of each image in storage order, each level starting with the base level
+        let header = self.header();
+        let mut slices = Vec::new();
+        for (level, images) in source.levels.iter().enumerate() {
+            let width = (header.level_extent(level as u32)[0] as usize).div_ceil(4);
+            for image in images {
+                let slice = |texel: SliceTexel| {
+                    let blocks = image.iter().map(|block: &Block| block.map(|value| texel(&value)));
+                    (width, blocks.collect::<Vec<_>>())
+                };
+                slices.push(slice(rgb));
+                slices.extend(alpha.map(slice));
+            }
+        }
+        let encoded = encode(
+            &slices,
+            codebook_size(options.quality, options.max_endpoints),
+            codebook_size(options.quality, options.max_selectors),
+        );
+        let (tables_data, slice_data) = encoded.tables_and_slices();
+
+        let mut image_descs = Vec::new();
+        let mut levels = Vec::new();
+        let mut slice_data = slice_data.into_iter();
+        for images in &source.levels {
+            let mut data = Vec::new();
+            for _ in images {
+                let mut image_desc = ImageDesc {
+                    image_flags: ImageFlags::empty(),
+                    rgb_slice_byte_offset: 0,
+                    rgb_slice_byte_length: 0,
+                    alpha_slice_byte_offset: 0,
+                    alpha_slice_byte_length: 0,
+                };
+                let rgb = slice_data.next().unwrap();
+                image_desc.rgb_slice_byte_offset = data.len() as u32;
+                image_desc.rgb_slice_byte_length = rgb.len() as u32;
+                data.extend(rgb);
+                if alpha.is_some() {
+                    let alpha = slice_data.next().unwrap();
+                    image_desc.alpha_slice_byte_offset = data.len() as u32;
+                    image_desc.alpha_slice_byte_length = alpha.len() as u32;
+                    data.extend(alpha);
+                }
+                image_descs.push(image_desc);
+            }
+            levels.push(WriterLevel {
+                data,
+                uncompressed_byte_length: 0,
+            });
+        }
+
+        let global_data = BasisLzGlobalData {
+            endpoint_count: encoded.endpoints.len() as u16,
+            selector_count: encoded.selectors.len() as u16,
+            image_descs,
+            endpoints_data: encoded.endpoints_data(),
+            selectors_data: encoded.selectors_data(),
+            tables_data,
+            extended_data: Vec::new(),
+        };
+        Ok(source.writer(
+            self,
+            etc1s_data_format_descriptors(channels, source.srgb),
+            Some(SupercompressionScheme::BasisLZ),
+            global_data.as_bytes(),
+            levels,
+        ))
+    }
+}
+
+#[test]
+fn test_encode_etc1s() {
+    use crate::{Format, MipmapOptions};
+    use std::collections::BTreeMap;
+
+    /// Reader of bits from the least significant bit of each byte
+    struct BitReader<'a> {
+        data: &'a [u8],
+        position: usize,
+    }
+
+    impl BitReader<'_> {
+        fn read(&mut self, count: u32) -> u32 {
+            let mut value = 0;
+            for bit in 0..count {
+                value |= u32::from((self.data[self.position / 8] >> (self.position % 8)) & 1) << bit;
+                self.position += 1;
+            }
+            value
+        }
+
+        /// Decode a symbol of a canonical Huffman code, whose codes start from their most significant bit
+        fn decode(&mut self, code: &BTreeMap<(u32, u32), usize>) -> usize {
+            let mut value = 0;
+            for length in 1..=16 {
+                value = (value << 1) | self.read(1);
+                if let Some(&symbol) = code.get(&(length, value)) {
+                    return symbol;
+                }
+            }
+            panic!("invalid Huffman code");
+        }
+
+        fn table(&mut self) -> BTreeMap<(u32, u32), usize> {
+            let used = self.read(14) as usize;
+            let mut code_length_lengths = [0; 21];
+            for &symbol in &CODE_LENGTH_ORDER[..self.read(5) as usize] {
+                code_length_lengths[symbol] = self.read(3);
+            }
+            let code_length_code = canonical(&code_length_lengths);
+            let mut lengths = Vec::new();
+            while lengths.len() < used {
+                let symbol = self.decode(&code_length_code);
+                let (value, count) = match symbol {
+                    17 => (0, self.read(3) + 3),
+                    18 => (0, self.read(7) + 11),
+                    19 => (*lengths.last().unwrap(), self.read(2) + 3),
+                    20 => (*lengths.last().unwrap(), self.read(7) + 7),
+                    length => (length as u32, 1),
+                };
+                lengths.extend((0..count).map(|_| value));
+            }
+            assert_eq!(lengths.len(), used);
+            canonical(&lengths)
+        }
+    }
+
+    fn canonical(lengths: &[u32]) -> BTreeMap<(u32, u32), usize> {
+        let mut code = BTreeMap::new();
+        let mut next = 0;
+        for length in 1..=16 {
+            for (symbol, _) in lengths.iter().enumerate().filter(|&(_, &other)| other == length) {
+                code.insert((length, next), symbol);
+                next += 1;
+            }
+            next <<= 1;
+        }
+        code
+    }
+
+    /// Decode the RGB texels of each block of the slices of the base level
+    fn decode(file: &[u8]) -> Vec<Vec<Texels>> {
+        let reader = Reader::new(file).unwrap();
+        let global_data = BasisLzGlobalData::parse(reader.supercompression_global_data()).unwrap();
+
+        let mut bits = BitReader {
+            data: &global_data.endpoints_data,
+            position: 0,
+        };
+        let color_code = [(); 3].map(|()| bits.table());
+        let intensity_code = bits.table();
+        let grayscale = bits.read(1) == 1;
+        let mut endpoints = Vec::new();
+        let mut previous = Endpoint {
+            color: [16; 3],
+            intensity: 0,
+        };
+        for _ in 0..global_data.endpoint_count {
+            let mut endpoint = previous;
+            endpoint.intensity = (previous.intensity + bits.decode(&intensity_code) as u8) & 7;
+            for channel in 0..if grayscale { 1 } else { 3 } {
+                let code = match previous.color[channel] {
+                    0..=9 => &color_code[0],
+                    10..=21 => &color_code[1],
+                    _ => &color_code[2],
+                };
+                endpoint.color[channel] = (previous.color[channel] + bits.decode(code) as u8) & 31;
+            }
+            if grayscale {
+                endpoint.color = [endpoint.color[0]; 3];
+            }
+            endpoints.push(endpoint);
+            previous = endpoint;
+        }
+
+        let mut bits = BitReader {
+            data: &global_data.selectors_data,
+            position: 0,
+        };
+        assert_eq!([bits.read(1), bits.read(1), bits.read(1)], [0, 0, 1]);
+        let selectors: Vec<[u8; 16]> = (0..global_data.selector_count)
+            .map(|_| {
+                let mut selectors = [0; 16];
+                for row in selectors.chunks_exact_mut(4) {
+                    let byte = bits.read(8);
+                    for (x, selector) in row.iter_mut().enumerate() {
+                        *selector = ((byte >> (x * 2)) & 3) as u8;
+                    }
+                }
+                selectors
+            })
+            .collect();
+
+        let mut bits = BitReader {
+            data: &global_data.tables_data,
+            position: 0,
+        };
+        let prediction_code = bits.table();
+        let delta_code = bits.table();
+        let selector_code = bits.table();
+        bits.table();
+        assert!(bits.read(13) > 0);
+
+        let header = reader.header();
+        let (width, height) = (
+            header.pixel_width.div_ceil(4) as usize,
+            header.pixel_height.div_ceil(4) as usize,
+        );
+        let level = reader.levels().next().unwrap().data;
+        let image_desc = &global_data.image_descs[0];
+        let slices = [
+            (image_desc.rgb_slice_byte_offset, image_desc.rgb_slice_byte_length),
+            (image_desc.alpha_slice_byte_offset, image_desc.alpha_slice_byte_length),
+        ];
+        slices
+            .iter()
+            .filter(|&&(_, length)| length > 0)
+            .map(|&(offset, length)| {
+                let mut bits = BitReader {
+                    data: &level[offset as usize..(offset + length) as usize],
+                    position: 0,
+                };
+                let mut indices = vec![0; width * height];
+                let mut predictions = vec![0; width * height];
+                let mut previous = 0;
+                let mut blocks = Vec::new();
+                for y in 0..height {
+                    for x in 0..width {
+                        if x % 2 == 0 && y % 2 == 0 {
+                            let symbol = bits.decode(&prediction_code);
+                            for (index, (dx, dy)) in [(0, 0), (1, 0), (0, 1), (1, 1)].iter().enumerate() {
+                                if x + dx < width && y + dy < height {
+                                    predictions[(y + dy) * width + x + dx] = (symbol >> (index * 2)) & 3;
+                                }
+                            }
+                        }
+                        let index = match predictions[y * width + x] {
+                            0 => {
+                                assert!(x > 0);
+                                previous
+                            }
+                            1 => indices[(y - 1) * width + x],
+                            2 => indices[(y - 1) * width + x - 1],
+                            _ => (previous + bits.decode(&delta_code)) % endpoints.len(),
+                        };
+                        indices[y * width + x] = index;
+                        previous = index;
+                        let selector = bits.decode(&selector_code);
+                        assert!(selector < selectors.len());
+                        let colors = endpoints[index].colors();
+                        let mut texels = [[0; 3]; 16];
+                        for (texel, &selector) in texels.iter_mut().zip(&selectors[selector]) {
+                            *texel = colors[selector as usize].map(|value| value as u8);
+                        }
+                        blocks.push(texels);
+                    }
+                }
+                blocks
+            })
+            .collect()
+    }
+
+    let (width, height) = (16, 12);
+    let base: Vec<u8> = (0..width * height)
+        .flat_map(|index| {
+            let (x, y) = (index % width, index / width);
+            // Blocks vary mostly in luminance, which ETC1S represents best
+            let value = x * 8 + y * 6;
+            [
+                (value + 60) as u8,
+                (value + 30) as u8,
+                (value + x / 4 * 10) as u8,
+                (255 - y * 10) as u8,
+            ]
+        })
+        .collect();
+    let source = |x: usize, y: usize| &base[(y * width + x) * 4..][..4];
+    let writer = Writer::with_mipmaps(Format::R8G8B8A8_SRGB, [16, 12, 0], &base, &MipmapOptions::default()).unwrap();
+    let file = writer.write();
+    let reader = Reader::new(&file[..]).unwrap();
+    let psnr = |slices: &[Vec<Texels>]| {
+        let mut error = 0;
+        for (block, (rgb, alpha)) in slices[0].iter().zip(&slices[1]).enumerate() {
+            for index in 0..16 {
+                let (x, y) = (block % 4 * 4 + index % 4, block / 4 * 4 + index / 4);
+                let decoded = [rgb[index][0], rgb[index][1], rgb[index][2], alpha[index][0]];
+                error += decoded
+                    .iter()
+                    .zip(source(x, y))
+                    .map(|(&a, &b)| u32::from(a.abs_diff(b)).pow(2))
+                    .sum::<u32>();
+            }
+        }
+        10.0 * (255.0f64 * 255.0 / (f64::from(error) / (width * height * 4) as f64)).log10()
+    };
+
+    let encoded = reader.encode_etc1s(&Etc1sOptions::default()).unwrap().write();
+    let encoded_reader = Reader::new(&encoded[..]).unwrap();
+    assert_eq!(
+        encoded_reader.header().supercompression_scheme,
+        Some(SupercompressionScheme::BasisLZ)
+    );
+    assert_eq!(encoded_reader.levels().next().unwrap().uncompressed_byte_length, 0);
+    let global_data = BasisLzGlobalData::parse(encoded_reader.supercompression_global_data()).unwrap();
+    assert_eq!(global_data.image_descs.len(), 5);
+    assert!(psnr(&decode(&encoded)) > 30.0);
+
+    let options = Etc1sOptions {
+        max_endpoints: Some(2),
+        max_selectors: Some(3),
+        ..Default::default()
+    };
+    let encoded = reader.encode_etc1s(&options).unwrap().write();
+    let global_data = BasisLzGlobalData::parse(Reader::new(&encoded[..]).unwrap().supercompression_global_data());
+    let global_data = global_data.unwrap();
+    assert_eq!((global_data.endpoint_count, global_data.selector_count), (2, 3));
+    assert!(psnr(&decode(&encoded)) > 15.0);
+}
@@ -0,0 +1,646 @@
+// Texel component layouts are only consumed by the std-only modules
+#![cfg_attr(not(feature = "std"), allow(dead_code))]
+
+#[cfg(feature = "std")]
+use crate::{
+    BasicDataFormatDescriptor, BasicDataFormatDescriptorHeader, ChannelTypeQualifiers, ColorModel, ColorPrimaries,
+    DataFormatFlags, SampleInformation, TransferFunction,
+};
+use crate::{Format, TextureError};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+/// Numeric interpretation of a single texel component
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Numeric {
+    UNorm,
+    SNorm,
+    UInt,
+    SInt,
+    UFloat,
+    SFloat,
+    /// sRGB encoded for color channels, unsigned normalized for alpha
+    SRgb,
+}
+
+/// A single component of an uncompressed texel
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Component {
+    /// Channel in the RGBSDA color model
+    pub channel: u32,
+    /// Offset in bits from the start of the texel, as a little-endian number
+    pub offset: u32,
+    pub bits: u32,
+    pub numeric: Numeric,
+}
+
+pub(crate) const RED: u32 = 0;
+pub(crate) const GREEN: u32 = 1;
+pub(crate) const BLUE: u32 = 2;
+pub(crate) const STENCIL: u32 = 13;
+pub(crate) const DEPTH: u32 = 14;
+pub(crate) const ALPHA: u32 = 15;
+
+/// Byte layout of a single layer and face of a mip level
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ImageLayout {
+    /// Size in texels, rounded up to whole texel blocks
+    pub physical_extent: [u32; 3],
+    pub bytes_per_row: u32,
+    pub rows_per_image: u32,
+    pub byte_length: usize,
+}
+
+impl ImageLayout {
+    /// Layout of an image of `format` with the given width, height and depth in pixels, such as
+    /// [`Header::level_extent`](crate::Header::level_extent)
+    ///
+    /// Returns [`TextureError::UnsupportedFormat`] for unknown formats and [`TextureError::InvalidLength`] if the
+    /// byte length or row size overflows.
+    pub fn new(format: Format, extent: [u32; 3]) -> Result<Self, TextureError> {
+        let block_size = format.block_size().ok_or(TextureError::UnsupportedFormat)?;
+        let block_dimensions = format.block_dimensions();
+        let blocks = [0, 1, 2].map(|axis| extent[axis].div_ceil(block_dimensions[axis]));
+        let physical_extent = [0, 1, 2].map(|axis| blocks[axis].checked_mul(block_dimensions[axis]));
+        let byte_length = blocks.iter().try_fold(block_size as usize, |length, &blocks| {
+            length.checked_mul(blocks as usize)
+        });
+
+        Ok(Self {
+            physical_extent: match physical_extent {
+                [Some(width), Some(height), Some(depth)] => [width, height, depth],
+                _ => return Err(TextureError::InvalidLength),
+            },
+            bytes_per_row: blocks[0].checked_mul(block_size).ok_or(TextureError::InvalidLength)?,
+            rows_per_image: blocks[1],
+            byte_length: byte_length.ok_or(TextureError::InvalidLength)?,
+        })
+    }
+}
+
+macro_rules! components {
+    ($numeric:ident; $($channel:ident $offset:literal $bits:literal),*) => {
+        &[$(Component { channel: $channel, offset: $offset, bits: $bits, numeric: Numeric::$numeric }),*]
+    };
+}
+
+macro_rules! formats_with_size {
+    ($format:expr; $(($size:literal, $dimensions:expr) => [$($case:ident),*],)*) => {
+        match $format {
+            $($(Format::$case)|* => Some(($size, $dimensions)),)*
+            _ => None,
+        }
+    };
+}
+
+impl Format {
+    /// Size in bytes of a texel block, or `None` for unknown formats
+    pub fn block_size(self) -> Option<u32> {
+        self.block_info().map(|(size, _)| size)
+    }
+
+    /// Width, height and depth in texels of a texel block
+    ///
+    /// This is `[1, 1, 1]` for uncompressed and unknown formats.
+    pub fn block_dimensions(self) -> [u32; 3] {
+        self.block_info().map_or([1, 1, 1], |(_, dimensions)| dimensions)
+    }
+
+    /// Whether the color channels of this format are sRGB encoded
+    pub fn is_srgb(self) -> bool {
+        matches!(
+            self,
+            Format::R8_SRGB
+                | Format::R8G8_SRGB
+                | Format::R8G8B8_SRGB
+                | Format::B8G8R8_SRGB
+                | Format::R8G8B8A8_SRGB
+                | Format::B8G8R8A8_SRGB
+                | Format::BC1_RGB_SRGB_BLOCK
+                | Format::BC1_RGBA_SRGB_BLOCK
+                | Format::BC2_SRGB_BLOCK
+                | Format::BC3_SRGB_BLOCK
+                | Format::BC7_SRGB_BLOCK
+                | Format::ETC2_R8G8B8_SRGB_BLOCK
+                | Format::ETC2_R8G8B8A1_SRGB_BLOCK
+                | Format::ETC2_R8G8B8A8_SRGB_BLOCK
+                | Format::ASTC_4x4_SRGB_BLOCK
+                | Format::ASTC_5x4_SRGB_BLOCK
+                | Format::ASTC_5x5_SRGB_BLOCK
+                | Format::ASTC_6x5_SRGB_BLOCK
+                | Format::ASTC_6x6_SRGB_BLOCK
+                | Format::ASTC_8x5_SRGB_BLOCK
+                | Format::ASTC_8x6_SRGB_BLOCK
+                | Format::ASTC_8x8_SRGB_BLOCK
+                | Format::ASTC_10x5_SRGB_BLOCK
+                | Format::ASTC_10x6_SRGB_BLOCK
+                | Format::ASTC_10x8_SRGB_BLOCK
+                | Format::ASTC_10x10_SRGB_BLOCK
+                | Format::ASTC_12x10_SRGB_BLOCK
+                | Format::ASTC_12x12_SRGB_BLOCK
+        )
+    }
+
+    /// Whether this is a block-compressed format
+    pub fn is_compressed(self) -> bool {
+        self.block_dimensions() != [1, 1, 1]
+    }
+
+    fn block_info(self) -> Option<(u32, [u32; 3])> {
+        formats_with_size! { self;
+            (1, [1, 1, 1]) => [R4G4_UNORM_PACK8, R8_UNORM, R8_SNORM, R8_UINT, R8_SINT, R8_SRGB, S8_UINT],
+            (2, [1, 1, 1]) => [
+                R4G4B4A4_UNORM_PACK16, B4G4R4A4_UNORM_PACK16, R5G6B5_UNORM_PACK16, B5G6R5_UNORM_PACK16,
+                R5G5B5A1_UNORM_PACK16, B5G5R5A1_UNORM_PACK16, A1R5G5B5_UNORM_PACK16, R8G8_UNORM, R8G8_SNORM,
+                R8G8_UINT, R8G8_SINT, R8G8_SRGB, R16_UNORM, R16_SNORM, R16_UINT, R16_SINT, R16_SFLOAT, D16_UNORM
+            ],
+            (3, [1, 1, 1]) => [
+                R8G8B8_UNORM, R8G8B8_SNORM, R8G8B8_UINT, R8G8B8_SINT, R8G8B8_SRGB, B8G8R8_UNORM, B8G8R8_SNORM,
+                B8G8R8_UINT, B8G8R8_SINT, B8G8R8_SRGB
+            ],
+            (4, [1, 1, 1]) => [
+                R8G8B8A8_UNORM, R8G8B8A8_SNORM, R8G8B8A8_UINT, R8G8B8A8_SINT, R8G8B8A8_SRGB, B8G8R8A8_UNORM,
+                B8G8R8A8_SNORM, B8G8R8A8_UINT, B8G8R8A8_SINT, B8G8R8A8_SRGB, A2R10G10B10_UNORM_PACK32,
+                A2R10G10B10_SNORM_PACK32, A2R10G10B10_UINT_PACK32, A2R10G10B10_SINT_PACK32,
+                A2B10G10R10_UNORM_PACK32, A2B10G10R10_SNORM_PACK32, A2B10G10R10_UINT_PACK32,
+                A2B10G10R10_SINT_PACK32, R16G16_UNORM, R16G16_SNORM, R16G16_UINT, R16G16_SINT, R16G16_SFLOAT,
+                R32_UINT, R32_SINT, R32_SFLOAT, B10G11R11_UFLOAT_PACK32, E5B9G9R9_UFLOAT_PACK32,
+                X8_D24_UNORM_PACK32, D32_SFLOAT, D16_UNORM_S8_UINT, D24_UNORM_S8_UINT
+            ],
+            (6, [1, 1, 1]) => [R16G16B16_UNORM, R16G16B16_SNORM, R16G16B16_UINT, R16G16B16_SINT, R16G16B16_SFLOAT],
+            (8, [1, 1, 1]) => [
+                R16G16B16A16_UNORM, R16G16B16A16_SNORM, R16G16B16A16_UINT, R16G16B16A16_SINT,
+                R16G16B16A16_SFLOAT, R32G32_UINT, R32G32_SINT, R32G32_SFLOAT, R64_UINT, R64_SINT, R64_SFLOAT,
+                D32_SFLOAT_S8_UINT
+            ],
+            (12, [1, 1, 1]) => [R32G32B32_UINT, R32G32B32_SINT, R32G32B32_SFLOAT],
+            (16, [1, 1, 1]) => [
+                R32G32B32A32_UINT, R32G32B32A32_SINT, R32G32B32A32_SFLOAT, R64G64_UINT, R64G64_SINT, R64G64_SFLOAT
+            ],
+            (24, [1, 1, 1]) => [R64G64B64_UINT, R64G64B64_SINT, R64G64B64_SFLOAT],
+            (32, [1, 1, 1]) => [R64G64B64A64_UINT, R64G64B64A64_SINT, R64G64B64A64_SFLOAT],
+            (8, [4, 4, 1]) => [
+                BC1_RGB_UNORM_BLOCK, BC1_RGB_SRGB_BLOCK, BC1_RGBA_UNORM_BLOCK, BC1_RGBA_SRGB_BLOCK, BC4_UNORM_BLOCK,
+                BC4_SNORM_BLOCK, ETC2_R8G8B8_UNORM_BLOCK, ETC2_R8G8B8_SRGB_BLOCK, ETC2_R8G8B8A1_UNORM_BLOCK,
+                ETC2_R8G8B8A1_SRGB_BLOCK, EAC_R11_UNORM_BLOCK, EAC_R11_SNORM_BLOCK
+            ],
+            (16, [4, 4, 1]) => [
+                BC2_UNORM_BLOCK, BC2_SRGB_BLOCK, BC3_UNORM_BLOCK, BC3_SRGB_BLOCK, BC5_UNORM_BLOCK, BC5_SNORM_BLOCK,
+                BC6H_UFLOAT_BLOCK, BC6H_SFLOAT_BLOCK, BC7_UNORM_BLOCK, BC7_SRGB_BLOCK, ETC2_R8G8B8A8_UNORM_BLOCK,
+                ETC2_R8G8B8A8_SRGB_BLOCK, EAC_R11G11_UNORM_BLOCK, EAC_R11G11_SNORM_BLOCK, ASTC_4x4_UNORM_BLOCK,
+                ASTC_4x4_SRGB_BLOCK
+            ],
+            (16, [5, 4, 1]) => [ASTC_5x4_UNORM_BLOCK, ASTC_5x4_SRGB_BLOCK],
+            (16, [5, 5, 1]) => [ASTC_5x5_UNORM_BLOCK, ASTC_5x5_SRGB_BLOCK],
+            (16, [6, 5, 1]) => [ASTC_6x5_UNORM_BLOCK, ASTC_6x5_SRGB_BLOCK],
+            (16, [6, 6, 1]) => [ASTC_6x6_UNORM_BLOCK, ASTC_6x6_SRGB_BLOCK],
+            (16, [8, 5, 1]) => [ASTC_8x5_UNORM_BLOCK, ASTC_8x5_SRGB_BLOCK],
+            (16, [8, 6, 1]) => [ASTC_8x6_UNORM_BLOCK, ASTC_8x6_SRGB_BLOCK],
+            (16, [8, 8, 1]) => [ASTC_8x8_UNORM_BLOCK, ASTC_8x8_SRGB_BLOCK],
+            (16, [10, 5, 1]) => [ASTC_10x5_UNORM_BLOCK, ASTC_10x5_SRGB_BLOCK],
+            (16, [10, 6, 1]) => [ASTC_10x6_UNORM_BLOCK, ASTC_10x6_SRGB_BLOCK],
+            (16, [10, 8, 1]) => [ASTC_10x8_UNORM_BLOCK, ASTC_10x8_SRGB_BLOCK],
+            (16, [10, 10, 1]) => [ASTC_10x10_UNORM_BLOCK, ASTC_10x10_SRGB_BLOCK],
+            (16, [12, 10, 1]) => [ASTC_12x10_UNORM_BLOCK, ASTC_12x10_SRGB_BLOCK],
+            (16, [12, 12, 1]) => [ASTC_12x12_UNORM_BLOCK, ASTC_12x12_SRGB_BLOCK],
+        }
+    }
+
+    /// Components of uncompressed formats, in order of increasing bit offset
+    ///
+    /// Shared exponent formats aren't described by their components and return `None`.
+    pub(crate) fn components(self) -> Option<&'static [Component]> {
+        Some(match self {
+            Format::R4G4_UNORM_PACK8 => components!(UNorm; GREEN 0 4, RED 4 4),
+            Format::R4G4B4A4_UNORM_PACK16 => components!(UNorm; ALPHA 0 4, BLUE 4 4, GREEN 8 4, RED 12 4),
+            Format::B4G4R4A4_UNORM_PACK16 => components!(UNorm; ALPHA 0 4, RED 4 4, GREEN 8 4, BLUE 12 4),
+            Format::R5G6B5_UNORM_PACK16 => components!(UNorm; BLUE 0 5, GREEN 5 6, RED 11 5),
+            Format::B5G6R5_UNORM_PACK16 => components!(UNorm; RED 0 5, GREEN 5 6, BLUE 11 5),
+            Format::R5G5B5A1_UNORM_PACK16 => components!(UNorm; ALPHA 0 1, BLUE 1 5, GREEN 6 5, RED 11 5),
+            Format::B5G5R5A1_UNORM_PACK16 => components!(UNorm; ALPHA 0 1, RED 1 5, GREEN 6 5, BLUE 11 5),
+            Format::A1R5G5B5_UNORM_PACK16 => components!(UNorm; BLUE 0 5, GREEN 5 5, RED 10 5, ALPHA 15 1),
+            Format::R8_UNORM => components!(UNorm; RED 0 8),
+            Format::R8_SNORM => components!(SNorm; RED 0 8),
+            Format::R8_UINT => components!(UInt; RED 0 8),
+            Format::R8_SINT => components!(SInt; RED 0 8),
+            Format::R8_SRGB => components!(SRgb; RED 0 8),
+            Format::R8G8_UNORM => components!(UNorm; RED 0 8, GREEN 8 8),
+            Format::R8G8_SNORM => components!(SNorm; RED 0 8, GREEN 8 8),
+            Format::R8G8_UINT => components!(UInt; RED 0 8, GREEN 8 8),
+            Format::R8G8_SINT => components!(SInt; RED 0 8, GREEN 8 8),
+            Format::R8G8_SRGB => components!(SRgb; RED 0 8, GREEN 8 8),
+            Format::R8G8B8_UNORM => components!(UNorm; RED 0 8, GREEN 8 8, BLUE 16 8),
+            Format::R8G8B8_SNORM => components!(SNorm; RED 0 8, GREEN 8 8, BLUE 16 8),
+            Format::R8G8B8_UINT => components!(UInt; RED 0 8, GREEN 8 8, BLUE 16 8),
+            Format::R8G8B8_SINT => components!(SInt; RED 0 8, GREEN 8 8, BLUE 16 8),
+            Format::R8G8B8_SRGB => components!(SRgb; RED 0 8, GREEN 8 8, BLUE 16 8),
+            Format::B8G8R8_UNORM => components!(UNorm; BLUE 0 8, GREEN 8 8, RED 16 8),
+            Format::B8G8R8_SNORM => components!(SNorm; BLUE 0 8, GREEN 8 8, RED 16 8),
+            Format::B8G8R8_UINT => components!(UInt; BLUE 0 8, GREEN 8 8, RED 16 8),
+            Format::B8G8R8_SINT => components!(SInt; BLUE 0 8, GREEN 8 8, RED 16 8),
+            Format::B8G8R8_SRGB => components!(SRgb; BLUE 0 8, GREEN 8 8, RED 16 8),
+            Format::R8G8B8A8_UNORM => components!(UNorm; RED 0 8, GREEN 8 8, BLUE 16 8, ALPHA 24 8),
+            Format::R8G8B8A8_SNORM => components!(SNorm; RED 0 8, GREEN 8 8, BLUE 16 8, ALPHA 24 8),
+            Format::R8G8B8A8_UINT => components!(UInt; RED 0 8, GREEN 8 8, BLUE 16 8, ALPHA 24 8),
+            Format::R8G8B8A8_SINT => components!(SInt; RED 0 8, GREEN 8 8, BLUE 16 8, ALPHA 24 8),
+            Format::R8G8B8A8_SRGB => components!(SRgb; RED 0 8, GREEN 8 8, BLUE 16 8, ALPHA 24 8),
+            Format::B8G8R8A8_UNORM => components!(UNorm; BLUE 0 8, GREEN 8 8, RED 16 8, ALPHA 24 8),
+            Format::B8G8R8A8_SNORM => components!(SNorm; BLUE 0 8, GREEN 8 8, RED 16 8, ALPHA 24 8),
+            Format::B8G8R8A8_UINT => components!(UInt; BLUE 0 8, GREEN 8 8, RED 16 8, ALPHA 24 8),
+            Format::B8G8R8A8_SINT => components!(SInt; BLUE 0 8, GREEN 8 8, RED 16 8, ALPHA 24 8),
+            Format::B8G8R8A8_SRGB => components!(SRgb; BLUE 0 8, GREEN 8 8, RED 16 8, ALPHA 24 8),
+            Format::A2R10G10B10_UNORM_PACK32 => components!(UNorm; BLUE 0 10, GREEN 10 10, RED 20 10, ALPHA 30 2),
+            Format::A2R10G10B10_SNORM_PACK32 => components!(SNorm; BLUE 0 10, GREEN 10 10, RED 20 10, ALPHA 30 2),
+            Format::A2R10G10B10_UINT_PACK32 => components!(UInt; BLUE 0 10, GREEN 10 10, RED 20 10, ALPHA 30 2),
+            Format::A2R10G10B10_SINT_PACK32 => components!(SInt; BLUE 0 10, GREEN 10 10, RED 20 10, ALPHA 30 2),
+            Format::A2B10G10R10_UNORM_PACK32 => components!(UNorm; RED 0 10, GREEN 10 10, BLUE 20 10, ALPHA 30 2),
+            Format::A2B10G10R10_SNORM_PACK32 => components!(SNorm; RED 0 10, GREEN 10 10, BLUE 20 10, ALPHA 30 2),
+            Format::A2B10G10R10_UINT_PACK32 => components!(UInt; RED 0 10, GREEN 10 10, BLUE 20 10, ALPHA 30 2),
+            Format::A2B10G10R10_SINT_PACK32 => components!(SInt; RED 0 10, GREEN 10 10, BLUE 20 10, ALPHA 30 2),
+            Format::R16_UNORM => components!(UNorm; RED 0 16),
+            Format::R16_SNORM => components!(SNorm; RED 0 16),
+            Format::R16_UINT => components!(UInt; RED 0 16),
+            Format::R16_SINT => components!(SInt; RED 0 16),
+            Format::R16_SFLOAT => components!(SFloat; RED 0 16),
+            Format::R16G16_UNORM => components!(UNorm; RED 0 16, GREEN 16 16),
+            Format::R16G16_SNORM => components!(SNorm; RED 0 16, GREEN 16 16),
+            Format::R16G16_UINT => components!(UInt; RED 0 16, GREEN 16 16),
+            Format::R16G16_SINT => components!(SInt; RED 0 16, GREEN 16 16),
+            Format::R16G16_SFLOAT => components!(SFloat; RED 0 16, GREEN 16 16),
+            Format::R16G16B16_UNORM => components!(UNorm; RED 0 16, GREEN 16 16, BLUE 32 16),
+            Format::R16G16B16_SNORM => components!(SNorm; RED 0 16, GREEN 16 16, BLUE 32 16),
+            Format::R16G16B16_UINT => components!(UInt; RED 0 16, GREEN 16 16, BLUE 32 16),
+            Format::R16G16B16_SINT => components!(SInt; RED 0 16, GREEN 16 16, BLUE 32 16),
+            Format::R16G16B16_SFLOAT => components!(SFloat; RED 0 16, GREEN 16 16, BLUE 32 16),
+            Format::R16G16B16A16_UNORM => components!(UNorm; RED 0 16, GREEN 16 16, BLUE 32 16, ALPHA 48 16),
+            Format::R16G16B16A16_SNORM => components!(SNorm; RED 0 16, GREEN 16 16, BLUE 32 16, ALPHA 48 16),
+            Format::R16G16B16A16_UINT => components!(UInt; RED 0 16, GREEN 16 16, BLUE 32 16, ALPHA 48 16),
+            Format::R16G16B16A16_SINT => components!(SInt; RED 0 16, GREEN 16 16, BLUE 32 16, ALPHA 48 16),
+            Format::R16G16B16A16_SFLOAT => components!(SFloat; RED 0 16, GREEN 16 16, BLUE 32 16, ALPHA 48 16),
+            Format::R32_UINT => components!(UInt; RED 0 32),
+            Format::R32_SINT => components!(SInt; RED 0 32),
+            Format::R32_SFLOAT => components!(SFloat; RED 0 32),
+            Format::R32G32_UINT => components!(UInt; RED 0 32, GREEN 32 32),
+            Format::R32G32_SINT => components!(SInt; RED 0 32, GREEN 32 32),
+            Format::R32G32_SFLOAT => components!(SFloat; RED 0 32, GREEN 32 32),
+            Format::R32G32B32_UINT => components!(UInt; RED 0 32, GREEN 32 32, BLUE 64 32),
+            Format::R32G32B32_SINT => components!(SInt; RED 0 32, GREEN 32 32, BLUE 64 32),
+            Format::R32G32B32_SFLOAT => components!(SFloat; RED 0 32, GREEN 32 32, BLUE 64 32),
+            Format::R32G32B32A32_UINT => components!(UInt; RED 0 32, GREEN 32 32, BLUE 64 32, ALPHA 96 32),
+            Format::R32G32B32A32_SINT => components!(SInt; RED 0 32, GREEN 32 32, BLUE 64 32, ALPHA 96 32),
+            Format::R32G32B32A32_SFLOAT => components!(SFloat; RED 0 32, GREEN 32 32, BLUE 64 32, ALPHA 96 32),
+            Format::R64_UINT => components!(UInt; RED 0 64),
+            Format::R64_SINT => components!(SInt; RED 0 64),
+            Format::R64_SFLOAT => components!(SFloat; RED 0 64),
+            Format::R64G64_UINT => components!(UInt; RED 0 64, GREEN 64 64),
+            Format::R64G64_SINT => components!(SInt; RED 0 64, GREEN 64 64),
+            Format::R64G64_SFLOAT => components!(SFloat; RED 0 64, GREEN 64 64),
+            Format::R64G64B64_UINT => components!(UInt; RED 0 64, GREEN 64 64, BLUE 128 64),
+            Format::R64G64B64_SINT => components!(SInt; RED 0 64, GREEN 64 64, BLUE 128 64),
+            Format::R64G64B64_SFLOAT => components!(SFloat; RED 0 64, GREEN 64 64, BLUE 128 64),
+            Format::R64G64B64A64_UINT => components!(UInt; RED 0 64, GREEN 64 64, BLUE 128 64, ALPHA 192 64),
+            Format::R64G64B64A64_SINT => components!(SInt; RED 0 64, GREEN 64 64, BLUE 128 64, ALPHA 192 64),
+            Format::R64G64B64A64_SFLOAT => components!(SFloat; RED 0 64, GREEN 64 64, BLUE 128 64, ALPHA 192 64),
+            Format::B10G11R11_UFLOAT_PACK32 => components!(UFloat; RED 0 11, GREEN 11 11, BLUE 22 10),
+            Format::D16_UNORM => components!(UNorm; DEPTH 0 16),
+            Format::X8_D24_UNORM_PACK32 => components!(UNorm; DEPTH 0 24),
+            Format::D32_SFLOAT => components!(SFloat; DEPTH 0 32),
+            Format::S8_UINT => components!(UInt; STENCIL 0 8),
+            Format::D16_UNORM_S8_UINT => &[
+                Component {
+                    channel: DEPTH,
+                    offset: 0,
+                    bits: 16,
+                    numeric: Numeric::UNorm,
+                },
+                Component {
+                    channel: STENCIL,
+                    offset: 16,
+                    bits: 8,
+                    numeric: Numeric::UInt,
+                },
+            ],
+            Format::D24_UNORM_S8_UINT => &[
+                Component {
+                    channel: DEPTH,
+                    offset: 0,
+                    bits: 24,
+                    numeric: Numeric::UNorm,
+                },
+                Component {
+                    channel: STENCIL,
+                    offset: 24,
+                    bits: 8,
+                    numeric: Numeric::UInt,
+                },
+            ],
+            Format::D32_SFLOAT_S8_UINT => &[
+                Component {
+                    channel: DEPTH,
+                    offset: 0,
+                    bits: 32,
+                    numeric: Numeric::SFloat,
+                },
+                Component {
+                    channel: STENCIL,
+                    offset: 32,
+                    bits: 8,
+                    numeric: Numeric::UInt,
+                },
+            ],
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl Format {
+    /// Data format descriptor blocks describing this format, for use in
+    /// [`Writer::data_format_descriptors`](crate::Writer::data_format_descriptors)
+    ///
+    /// Returns `None` for formats which can't be described.
+    pub fn data_format_descriptors(self) -> Option<Vec<u8>> {
+        let (header, samples) = self.basic_data_format_descriptor()?;
+        Some(crate::basic_data_format_descriptor_block(&header, &samples))
+    }
+
+    /// Format described by a basic data format descriptor, for textures whose header format is
+    /// `VK_FORMAT_UNDEFINED` or to check that the descriptor agrees with the header
+    ///
+    /// The color model, transfer function, texel block size and samples must match the descriptor of the format as
+    /// generated by [`Format::data_format_descriptors`], except for color primaries, flags and sample positions.
+    /// Returns `None` if no known format matches.
+    pub fn from_data_format_descriptor(descriptor: &BasicDataFormatDescriptor) -> Option<Self> {
+        let is_srgb =
+            |header: &BasicDataFormatDescriptorHeader| header.transfer_function == Some(TransferFunction::SRGB);
+        let sample_key = |sample: SampleInformation| {
+            (
+                sample.bit_offset,
+                sample.bit_length,
+                sample.channel_type,
+                sample.channel_type_qualifiers,
+                sample.lower,
+                sample.upper,
+            )
+        };
+        let samples = descriptor.sample_information().map(sample_key).collect::<Vec<_>>();
+
+        (1..=184).filter_map(Format::new).find(|format| {
+            let (header, format_samples) = match format.basic_data_format_descriptor() {
+                Some(descriptor) => descriptor,
+                None => return false,
+            };
+            header.color_model == descriptor.header.color_model
+                && is_srgb(&header) == is_srgb(&descriptor.header)
+                && header.texel_block_dimensions == descriptor.header.texel_block_dimensions
+                && header.bytes_planes == descriptor.header.bytes_planes
+                && format_samples.into_iter().map(sample_key).eq(samples.iter().copied())
+        })
+    }
+
+    /// Value of [`Header::type_size`](crate::Header::type_size) for this format
+    pub(crate) fn type_size(self) -> u32 {
+        match self {
+            Format::X8_D24_UNORM_PACK32
+            | Format::D24_UNORM_S8_UINT
+            | Format::D32_SFLOAT_S8_UINT
+            | Format::B10G11R11_UFLOAT_PACK32
+            | Format::E5B9G9R9_UFLOAT_PACK32 => 4,
+            Format::D16_UNORM_S8_UINT => 2,
+            _ => match self.components() {
+                Some(components)
+                    if components
+                        .iter()
+                        .all(|c| c.bits % 8 == 0 && c.bits == components[0].bits) =>
+                {
+                    components[0].bits / 8
+                }
+                Some(_) => self.block_size().unwrap(),
+                None => 1,
+            },
+        }
+    }
+
+    pub(crate) fn basic_data_format_descriptor(
+        self,
+    ) -> Option<(BasicDataFormatDescriptorHeader, Vec<SampleInformation>)> {
+        let samples = if self == Format::E5B9G9R9_UFLOAT_PACK32 {
+            let mut samples = Vec::new();
+            for (channel, offset) in [(RED, 0), (GREEN, 9), (BLUE, 18)] {
+                samples.push(SampleInformation {
+                    bit_offset: offset,
+                    bit_length: 9,
+                    channel_type: channel,
+                    channel_type_qualifiers: ChannelTypeQualifiers::empty(),
+                    sample_positions: [0; 4],
+                    lower: 0,
+                    upper: 256,
+                });
+                samples.push(SampleInformation {
+                    bit_offset: 27,
+                    bit_length: 5,
+                    channel_type: channel,
+                    channel_type_qualifiers: ChannelTypeQualifiers::EXPONENT,
+                    sample_positions: [0; 4],
+                    lower: 15,
+                    upper: 31,
+                });
+            }
+            samples
+        } else if let Some(components) = self.components() {
+            components.iter().map(Component::sample_information).collect()
+        } else {
+            return self.compressed_data_format_descriptor();
+        };
+
+        let mut bytes_planes = [0; 8];
+        bytes_planes[0] = self.block_size()?;
+        let header = BasicDataFormatDescriptorHeader {
+            color_model: Some(ColorModel::RGBSDA),
+            color_primaries: Some(ColorPrimaries::BT709),
+            transfer_function: Some(if self.is_srgb() {
+                TransferFunction::SRGB
+            } else {
+                TransferFunction::Linear
+            }),
+            flags: DataFormatFlags::STRAIGHT_ALPHA,
+            texel_block_dimensions: [1, 1, 1, 1],
+            bytes_planes,
+        };
+        Some((header, samples))
+    }
+
+    /// Descriptor of a block-compressed format, following the sample layouts used by libktx
+    fn compressed_data_format_descriptor(self) -> Option<(BasicDataFormatDescriptorHeader, Vec<SampleInformation>)> {
+        use Format as F;
+
+        // Channel IDs of the compressed color models
+        const DATA: u32 = 0;
+        const BC1A_ALPHA_PRESENT: u32 = 1;
+        const ETC2_COLOR: u32 = 2;
+
+        let (color_model, channels): (_, &[(u32, u32)]) = match self {
+            F::BC1_RGB_UNORM_BLOCK | F::BC1_RGB_SRGB_BLOCK => (ColorModel::BC1A, &[(DATA, 0)]),
+            F::BC1_RGBA_UNORM_BLOCK | F::BC1_RGBA_SRGB_BLOCK => (ColorModel::BC1A, &[(BC1A_ALPHA_PRESENT, 0)]),
+            F::BC2_UNORM_BLOCK | F::BC2_SRGB_BLOCK => (ColorModel::BC2, &[(ALPHA, 0), (DATA, 64)]),
+            F::BC3_UNORM_BLOCK | F::BC3_SRGB_BLOCK => (ColorModel::BC3, &[(ALPHA, 0), (DATA, 64)]),
+            F::BC4_UNORM_BLOCK | F::BC4_SNORM_BLOCK => (ColorModel::BC4, &[(DATA, 0)]),
+            F::BC5_UNORM_BLOCK | F::BC5_SNORM_BLOCK => (ColorModel::BC5, &[(RED, 0), (GREEN, 64)]),
+            F::BC6H_UFLOAT_BLOCK | F::BC6H_SFLOAT_BLOCK => (ColorModel::BC6H, &[(DATA, 0)]),
+            F::BC7_UNORM_BLOCK | F::BC7_SRGB_BLOCK => (ColorModel::BC7, &[(DATA, 0)]),
+            F::ETC2_R8G8B8_UNORM_BLOCK | F::ETC2_R8G8B8_SRGB_BLOCK => (ColorModel::ETC2, &[(ETC2_COLOR, 0)]),
+            F::ETC2_R8G8B8A1_UNORM_BLOCK | F::ETC2_R8G8B8A1_SRGB_BLOCK => {
+                (ColorModel::ETC2, &[(ETC2_COLOR, 0), (ALPHA, 0)])
+            }
+            F::ETC2_R8G8B8A8_UNORM_BLOCK | F::ETC2_R8G8B8A8_SRGB_BLOCK => {
+                (ColorModel::ETC2, &[(ALPHA, 0), (ETC2_COLOR, 64)])
+            }
+            F::EAC_R11_UNORM_BLOCK | F::EAC_R11_SNORM_BLOCK => (ColorModel::ETC2, &[(RED, 0)]),
+            F::EAC_R11G11_UNORM_BLOCK | F::EAC_R11G11_SNORM_BLOCK => (ColorModel::ETC2, &[(RED, 0), (GREEN, 64)]),
+            // The remaining block-compressed formats are ASTC
+            _ if self.is_compressed() => (ColorModel::ASTC, &[(DATA, 0)]),
+            _ => return None,
+        };
+
+        let block_size = self.block_size()?;
+        let signed = matches!(
+            self,
+            F::BC4_SNORM_BLOCK | F::BC5_SNORM_BLOCK | F::EAC_R11_SNORM_BLOCK | F::EAC_R11G11_SNORM_BLOCK
+        );
+        let (channel_type_qualifiers, lower, upper) = match self {
+            F::BC6H_UFLOAT_BLOCK => (ChannelTypeQualifiers::FLOAT, 0, 1.0f32.to_bits()),
+            F::BC6H_SFLOAT_BLOCK => (
+                ChannelTypeQualifiers::FLOAT | ChannelTypeQualifiers::SIGNED,
+                (-1.0f32).to_bits(),
+                1.0f32.to_bits(),
+            ),
+            _ if signed => (ChannelTypeQualifiers::SIGNED, i32::MIN as u32, i32::MAX as u32),
+            _ => (ChannelTypeQualifiers::empty(), 0, u32::MAX),
+        };
+        let sample_bits = block_size * 8
+            / if channels.len() == 2 && channels[1].1 != 0 {
+                2
+            } else {
+                1
+            };
+        let samples = channels
+            .iter()
+            .map(|&(channel_type, bit_offset)| SampleInformation {
+                bit_offset,
+                bit_length: sample_bits,
+                channel_type,
+                channel_type_qualifiers: if channel_type == ALPHA && self.is_srgb() {
+                    channel_type_qualifiers | ChannelTypeQualifiers::LINEAR
+                } else {
+                    channel_type_qualifiers
+                },
+                sample_positions: [0; 4],
+                lower,
+                upper,
+            })
+            .collect();
+
+        let [width, height, depth] = self.block_dimensions();
+        let mut bytes_planes = [0; 8];
+        bytes_planes[0] = block_size;
+        let header = BasicDataFormatDescriptorHeader {
+            color_model: Some(color_model),
+            color_primaries: Some(ColorPrimaries::BT709),
+            transfer_function: Some(if self.is_srgb() {
+                TransferFunction::SRGB
+            } else {
+                TransferFunction::Linear
+            }),
+            flags: DataFormatFlags::STRAIGHT_ALPHA,
+            texel_block_dimensions: [width, height, depth, 1],
+            bytes_planes,
+        };
+        Some((header, samples))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Component {
+    fn sample_information(&self) -> SampleInformation {
+        let signed_max = |bits: u32| (1u64 << (bits - 1)) as u32 - 1;
+        let (channel_type_qualifiers, lower, upper) = match self.numeric {
+            Numeric::UNorm => (ChannelTypeQualifiers::empty(), 0, (u64::MAX >> (64 - self.bits)) as u32),
+            Numeric::SRgb if self.channel == ALPHA => (ChannelTypeQualifiers::LINEAR, 0, (1 << self.bits) - 1),
+            Numeric::SRgb => (ChannelTypeQualifiers::empty(), 0, (1 << self.bits) - 1),
+            Numeric::SNorm => (
+                ChannelTypeQualifiers::SIGNED,
+                (signed_max(self.bits) as i32).wrapping_neg() as u32,
+                signed_max(self.bits),
+            ),
+            Numeric::UInt => (ChannelTypeQualifiers::empty(), 0, 1),
+            Numeric::SInt => (ChannelTypeQualifiers::SIGNED, (-1i32) as u32, 1),
+            Numeric::UFloat => (ChannelTypeQualifiers::FLOAT, 0, 1.0f32.to_bits()),
+            Numeric::SFloat => (
+                ChannelTypeQualifiers::FLOAT | ChannelTypeQualifiers::SIGNED,
+                (-1.0f32).to_bits(),
+                1.0f32.to_bits(),
+            ),
+        };
+
+        SampleInformation {
+            bit_offset: self.offset,
+            bit_length: self.bits,
+            channel_type: self.channel,
+            channel_type_qualifiers,
+            sample_positions: [0; 4],
+            lower,
+            upper,
+        }
+    }
+}
+
+#[test]
+fn test_data_format_descriptors_match_libktx() {
+    let file = include_bytes!("../data/test_tex.ktx2");
+    let reader = crate::Reader::new(&file[..]).unwrap();
+    let index = reader.header().index;
+    let start = index.dfd_byte_offset as usize + 4;
+    let end = (index.dfd_byte_offset + index.dfd_byte_length) as usize;
+
+    assert_eq!(
+        Format::R8G8B8A8_UINT.data_format_descriptors().unwrap(),
+        &reader.data()[start..end]
+    );
+}
+
+#[test]
+fn test_format_from_data_format_descriptor() {
+    for format in (1..=184).filter_map(Format::new) {
+        if let Some(bytes) = format.data_format_descriptors() {
+            // Skip the descriptor block header
+            let descriptor = BasicDataFormatDescriptor::parse(&bytes[8..]).unwrap();
+            assert_eq!(Format::from_data_format_descriptor(&descriptor), Some(format));
+        }
+    }
+
+    let file = include_bytes!("../data/test_tex.ktx2");
+    let reader = crate::Reader::new(&file[..]).unwrap();
+    let descriptor = BasicDataFormatDescriptor::parse(reader.data_format_descriptors().next().unwrap().data).unwrap();
+    assert_eq!(Format::from_data_format_descriptor(&descriptor), reader.header().format);
+}
+
+#[test]
+fn test_image_layout() {
+    let layout = ImageLayout::new(Format::BC1_RGBA_UNORM_BLOCK, [5, 3, 1]).unwrap();
+    assert_eq!(layout.physical_extent, [8, 4, 1]);
+    assert_eq!(layout.bytes_per_row, 16);
+    assert_eq!(layout.byte_length, 16);
+
+    assert!(matches!(
+        ImageLayout::new(Format::R32G32B32A32_SFLOAT, [u32::MAX, 1, 1]),
+        Err(TextureError::InvalidLength)
+    ));
+    assert!(matches!(
+        ImageLayout::new(Format::BC1_RGBA_UNORM_BLOCK, [u32::MAX, 1, 1]),
+        Err(TextureError::InvalidLength)
+    ));
+    assert!(matches!(
+        ImageLayout::new(Format::R8_UNORM, [u32::MAX, u32::MAX, u32::MAX]),
+        Err(TextureError::InvalidLength)
+    ));
+}
@@ -0,0 +1,322 @@
+//! Conversion between [`Format`] and OpenGL internal format, format and type triples, as used by KTX1 files and the
+//! `KTXglFormat` key.
+
+use crate::Format;
+use core::convert::TryInto;
+
+/// Key of the metadata entry containing the [`GlFormat`] of textures whose format is not a Vulkan format
+pub const KEY: &str = "KTXglFormat";
+
+/// OpenGL description of a texture format
+///
+/// `format` and `type_` are 0 for compressed formats.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct GlFormat {
+    pub internal_format: u32,
+    pub format: u32,
+    pub type_: u32,
+}
+
+impl GlFormat {
+    pub const LENGTH: usize = 12;
+
+    pub fn from_bytes(data: &[u8; Self::LENGTH]) -> Self {
+        Self {
+            internal_format: u32::from_le_bytes(data[0..4].try_into().unwrap()),
+            format: u32::from_le_bytes(data[4..8].try_into().unwrap()),
+            type_: u32::from_le_bytes(data[8..12].try_into().unwrap()),
+        }
+    }
+
+    pub fn as_bytes(&self) -> [u8; Self::LENGTH] {
+        let mut bytes = [0; Self::LENGTH];
+
+        bytes[0..4].copy_from_slice(&self.internal_format.to_le_bytes()[..]);
+        bytes[4..8].copy_from_slice(&self.format.to_le_bytes()[..]);
+        bytes[8..12].copy_from_slice(&self.type_.to_le_bytes()[..]);
+
+        bytes
+    }
+}
+
+// Pixel types
+const BYTE: u32 = 0x1400;
+const UNSIGNED_BYTE: u32 = 0x1401;
+const SHORT: u32 = 0x1402;
+const UNSIGNED_SHORT: u32 = 0x1403;
+const INT: u32 = 0x1404;
+const UNSIGNED_INT: u32 = 0x1405;
+const FLOAT: u32 = 0x1406;
+const HALF_FLOAT: u32 = 0x140B;
+const UNSIGNED_SHORT_4_4_4_4: u32 = 0x8033;
+const UNSIGNED_SHORT_5_5_5_1: u32 = 0x8034;
+const UNSIGNED_SHORT_5_6_5: u32 = 0x8363;
+const UNSIGNED_SHORT_5_6_5_REV: u32 = 0x8364;
+const UNSIGNED_SHORT_1_5_5_5_REV: u32 = 0x8366;
+const UNSIGNED_INT_2_10_10_10_REV: u32 = 0x8368;
+const UNSIGNED_INT_24_8: u32 = 0x84FA;
+const UNSIGNED_INT_10F_11F_11F_REV: u32 = 0x8C3B;
+const UNSIGNED_INT_5_9_9_9_REV: u32 = 0x8C3E;
+const FLOAT_32_UNSIGNED_INT_24_8_REV: u32 = 0x8DAD;
+
+// Pixel formats
+const STENCIL_INDEX: u32 = 0x1901;
+const DEPTH_COMPONENT: u32 = 0x1902;
+const RED: u32 = 0x1903;
+const RGB: u32 = 0x1907;
+const RGBA: u32 = 0x1908;
+const BGR: u32 = 0x80E0;
+const BGRA: u32 = 0x80E1;
+const RG: u32 = 0x8227;
+const RG_INTEGER: u32 = 0x8228;
+const DEPTH_STENCIL: u32 = 0x84F9;
+const RED_INTEGER: u32 = 0x8D94;
+const RGB_INTEGER: u32 = 0x8D98;
+const RGBA_INTEGER: u32 = 0x8D99;
+const BGR_INTEGER: u32 = 0x8D9A;
+const BGRA_INTEGER: u32 = 0x8D9B;
+
+// Internal format of ETC1, which has no format of its own
+const ETC1_RGB8_OES: u32 = 0x8D64;
+
+const fn gl(internal_format: u32, format: u32, type_: u32) -> GlFormat {
+    GlFormat {
+        internal_format,
+        format,
+        type_,
+    }
+}
+
+const fn compressed(internal_format: u32) -> GlFormat {
+    gl(internal_format, 0, 0)
+}
+
+// BGR(A) formats share internal formats with RGB(A) ones and come after them, so a lookup by internal format alone
+// prefers RGB(A).
+const FORMATS: &[(Format, GlFormat)] = &[
+    (Format::R4G4B4A4_UNORM_PACK16, gl(0x8056, RGBA, UNSIGNED_SHORT_4_4_4_4)),
+    (Format::B4G4R4A4_UNORM_PACK16, gl(0x8056, BGRA, UNSIGNED_SHORT_4_4_4_4)),
+    (Format::R5G6B5_UNORM_PACK16, gl(0x8D62, RGB, UNSIGNED_SHORT_5_6_5)),
+    (Format::B5G6R5_UNORM_PACK16, gl(0x8D62, RGB, UNSIGNED_SHORT_5_6_5_REV)),
+    (Format::R5G5B5A1_UNORM_PACK16, gl(0x8057, RGBA, UNSIGNED_SHORT_5_5_5_1)),
+    (
+        Format::A1R5G5B5_UNORM_PACK16,
+        gl(0x8057, BGRA, UNSIGNED_SHORT_1_5_5_5_REV),
+    ),
+    (Format::R8_UNORM, gl(0x8229, RED, UNSIGNED_BYTE)),
+    (Format::R8_SNORM, gl(0x8F94, RED, BYTE)),
+    (Format::R8_UINT, gl(0x8232, RED_INTEGER, UNSIGNED_BYTE)),
+    (Format::R8_SINT, gl(0x8231, RED_INTEGER, BYTE)),
+    (Format::R8_SRGB, gl(0x8FBD, RED, UNSIGNED_BYTE)),
+    (Format::R8G8_UNORM, gl(0x822B, RG, UNSIGNED_BYTE)),
+    (Format::R8G8_SNORM, gl(0x8F95, RG, BYTE)),
+    (Format::R8G8_UINT, gl(0x8238, RG_INTEGER, UNSIGNED_BYTE)),
+    (Format::R8G8_SINT, gl(0x8237, RG_INTEGER, BYTE)),
+    (Format::R8G8_SRGB, gl(0x8FBE, RG, UNSIGNED_BYTE)),
+    (Format::R8G8B8_UNORM, gl(0x8051, RGB, UNSIGNED_BYTE)),
+    (Format::R8G8B8_SNORM, gl(0x8F96, RGB, BYTE)),
+    (Format::R8G8B8_UINT, gl(0x8D7D, RGB_INTEGER, UNSIGNED_BYTE)),
+    (Format::R8G8B8_SINT, gl(0x8D8F, RGB_INTEGER, BYTE)),
+    (Format::R8G8B8_SRGB, gl(0x8C41, RGB, UNSIGNED_BYTE)),
+    (Format::B8G8R8_UNORM, gl(0x8051, BGR, UNSIGNED_BYTE)),
+    (Format::B8G8R8_SNORM, gl(0x8F96, BGR, BYTE)),
+    (Format::B8G8R8_UINT, gl(0x8D7D, BGR_INTEGER, UNSIGNED_BYTE)),
+    (Format::B8G8R8_SINT, gl(0x8D8F, BGR_INTEGER, BYTE)),
+    (Format::B8G8R8_SRGB, gl(0x8C41, BGR, UNSIGNED_BYTE)),
+    (Format::R8G8B8A8_UNORM, gl(0x8058, RGBA, UNSIGNED_BYTE)),
+    (Format::R8G8B8A8_SNORM, gl(0x8F97, RGBA, BYTE)),
+    (Format::R8G8B8A8_UINT, gl(0x8D7C, RGBA_INTEGER, UNSIGNED_BYTE)),
+    (Format::R8G8B8A8_SINT, gl(0x8D8E, RGBA_INTEGER, BYTE)),
+    (Format::R8G8B8A8_SRGB, gl(0x8C43, RGBA, UNSIGNED_BYTE)),
+    (Format::B8G8R8A8_UNORM, gl(0x8058, BGRA, UNSIGNED_BYTE)),
+    (Format::B8G8R8A8_SNORM, gl(0x8F97, BGRA, BYTE)),
+    (Format::B8G8R8A8_UINT, gl(0x8D7C, BGRA_INTEGER, UNSIGNED_BYTE)),
+    (Format::B8G8R8A8_SINT, gl(0x8D8E, BGRA_INTEGER, BYTE)),
+    (Format::B8G8R8A8_SRGB, gl(0x8C43, BGRA, UNSIGNED_BYTE)),
+    (
+        Format::A2B10G10R10_UNORM_PACK32,
+        gl(0x8059, RGBA, UNSIGNED_INT_2_10_10_10_REV),
+    ),
+    (
+        Format::A2B10G10R10_UINT_PACK32,
+        gl(0x906F, RGBA_INTEGER, UNSIGNED_INT_2_10_10_10_REV),
+    ),
+    (
+        Format::A2R10G10B10_UNORM_PACK32,
+        gl(0x8059, BGRA, UNSIGNED_INT_2_10_10_10_REV),
+    ),
+    (
+        Format::A2R10G10B10_UINT_PACK32,
+        gl(0x906F, BGRA_INTEGER, UNSIGNED_INT_2_10_10_10_REV),
+    ),
+    (Format::R16_UNORM, gl(0x822A, RED, UNSIGNED_SHORT)),
+    (Format::R16_SNORM, gl(0x8F98, RED, SHORT)),
+    (Format::R16_UINT, gl(0x8234, RED_INTEGER, UNSIGNED_SHORT)),
+    (Format::R16_SINT, gl(0x8233, RED_INTEGER, SHORT)),
+    (Format::R16_SFLOAT, gl(0x822D, RED, HALF_FLOAT)),
+    (Format::R16G16_UNORM, gl(0x822C, RG, UNSIGNED_SHORT)),
+    (Format::R16G16_SNORM, gl(0x8F99, RG, SHORT)),
+    (Format::R16G16_UINT, gl(0x823A, RG_INTEGER, UNSIGNED_SHORT)),
+    (Format::R16G16_SINT, gl(0x8239, RG_INTEGER, SHORT)),
+    (Format::R16G16_SFLOAT, gl(0x822F, RG, HALF_FLOAT)),
+    (Format::R16G16B16_UNORM, gl(0x8054, RGB, UNSIGNED_SHORT)),
+    (Format::R16G16B16_SNORM, gl(0x8F9A, RGB, SHORT)),
+    (Format::R16G16B16_UINT, gl(0x8D77, RGB_INTEGER, UNSIGNED_SHORT)),
+    (Format::R16G16B16_SINT, gl(0x8D89, RGB_INTEGER, SHORT)),
+    (Format::R16G16B16_SFLOAT, gl(0x881B, RGB, HALF_FLOAT)),
+    (Format::R16G16B16A16_UNORM, gl(0x805B, RGBA, UNSIGNED_SHORT)),
+    (Format::R16G16B16A16_SNORM, gl(0x8F9B, RGBA, SHORT)),
+    (Format::R16G16B16A16_UINT, gl(0x8D76, RGBA_INTEGER, UNSIGNED_SHORT)),
+    (Format::R16G16B16A16_SINT, gl(0x8D88, RGBA_INTEGER, SHORT)),
+    (Format::R16G16B16A16_SFLOAT, gl(0x881A, RGBA, HALF_FLOAT)),
+    (Format::R32_UINT, gl(0x8236, RED_INTEGER, UNSIGNED_INT)),
+    (Format::R32_SINT, gl(0x8235, RED_INTEGER, INT)),
+    (Format::R32_SFLOAT, gl(0x822E, RED, FLOAT)),
+    (Format::R32G32_UINT, gl(0x823C, RG_INTEGER, UNSIGNED_INT)),
+    (Format::R32G32_SINT, gl(0x823B, RG_INTEGER, INT)),
+    (Format::R32G32_SFLOAT, gl(0x8230, RG, FLOAT)),
+    (Format::R32G32B32_UINT, gl(0x8D71, RGB_INTEGER, UNSIGNED_INT)),
+    (Format::R32G32B32_SINT, gl(0x8D83, RGB_INTEGER, INT)),
+    (Format::R32G32B32_SFLOAT, gl(0x8815, RGB, FLOAT)),
+    (Format::R32G32B32A32_UINT, gl(0x8D70, RGBA_INTEGER, UNSIGNED_INT)),
+    (Format::R32G32B32A32_SINT, gl(0x8D82, RGBA_INTEGER, INT)),
+    (Format::R32G32B32A32_SFLOAT, gl(0x8814, RGBA, FLOAT)),
+    (
+        Format::B10G11R11_UFLOAT_PACK32,
+        gl(0x8C3A, RGB, UNSIGNED_INT_10F_11F_11F_REV),
+    ),
+    (
+        Format::E5B9G9R9_UFLOAT_PACK32,
+        gl(0x8C3D, RGB, UNSIGNED_INT_5_9_9_9_REV),
+    ),
+    (Format::D16_UNORM, gl(0x81A5, DEPTH_COMPONENT, UNSIGNED_SHORT)),
+    (Format::X8_D24_UNORM_PACK32, gl(0x81A6, DEPTH_COMPONENT, UNSIGNED_INT)),
+    (Format::D32_SFLOAT, gl(0x8CAC, DEPTH_COMPONENT, FLOAT)),
+    (Format::S8_UINT, gl(0x8D48, STENCIL_INDEX, UNSIGNED_BYTE)),
+    (Format::D24_UNORM_S8_UINT, gl(0x88F0, DEPTH_STENCIL, UNSIGNED_INT_24_8)),
+    (
+        Format::D32_SFLOAT_S8_UINT,
+        gl(0x8CAD, DEPTH_STENCIL, FLOAT_32_UNSIGNED_INT_24_8_REV),
+    ),
+    (Format::BC1_RGB_UNORM_BLOCK, compressed(0x83F0)),
+    (Format::BC1_RGBA_UNORM_BLOCK, compressed(0x83F1)),
+    (Format::BC2_UNORM_BLOCK, compressed(0x83F2)),
+    (Format::BC3_UNORM_BLOCK, compressed(0x83F3)),
+    (Format::BC1_RGB_SRGB_BLOCK, compressed(0x8C4C)),
+    (Format::BC1_RGBA_SRGB_BLOCK, compressed(0x8C4D)),
+    (Format::BC2_SRGB_BLOCK, compressed(0x8C4E)),
+    (Format::BC3_SRGB_BLOCK, compressed(0x8C4F)),
+    (Format::BC4_UNORM_BLOCK, compressed(0x8DBB)),
+    (Format::BC4_SNORM_BLOCK, compressed(0x8DBC)),
+    (Format::BC5_UNORM_BLOCK, compressed(0x8DBD)),
+    (Format::BC5_SNORM_BLOCK, compressed(0x8DBE)),
+    (Format::BC7_UNORM_BLOCK, compressed(0x8E8C)),
+    (Format::BC7_SRGB_BLOCK, compressed(0x8E8D)),
+    (Format::BC6H_SFLOAT_BLOCK, compressed(0x8E8E)),
+    (Format::BC6H_UFLOAT_BLOCK, compressed(0x8E8F)),
+    (Format::EAC_R11_UNORM_BLOCK, compressed(0x9270)),
+    (Format::EAC_R11_SNORM_BLOCK, compressed(0x9271)),
+    (Format::EAC_R11G11_UNORM_BLOCK, compressed(0x9272)),
+    (Format::EAC_R11G11_SNORM_BLOCK, compressed(0x9273)),
+    (Format::ETC2_R8G8B8_UNORM_BLOCK, compressed(0x9274)),
+    (Format::ETC2_R8G8B8_SRGB_BLOCK, compressed(0x9275)),
+    (Format::ETC2_R8G8B8A1_UNORM_BLOCK, compressed(0x9276)),
+    (Format::ETC2_R8G8B8A1_SRGB_BLOCK, compressed(0x9277)),
+    (Format::ETC2_R8G8B8A8_UNORM_BLOCK, compressed(0x9278)),
+    (Format::ETC2_R8G8B8A8_SRGB_BLOCK, compressed(0x9279)),
+    (Format::ASTC_4x4_UNORM_BLOCK, compressed(0x93B0)),
+    (Format::ASTC_5x4_UNORM_BLOCK, compressed(0x93B1)),
+    (Format::ASTC_5x5_UNORM_BLOCK, compressed(0x93B2)),
+    (Format::ASTC_6x5_UNORM_BLOCK, compressed(0x93B3)),
+    (Format::ASTC_6x6_UNORM_BLOCK, compressed(0x93B4)),
+    (Format::ASTC_8x5_UNORM_BLOCK, compressed(0x93B5)),
+    (Format::ASTC_8x6_UNORM_BLOCK, compressed(0x93B6)),
+    (Format::ASTC_8x8_UNORM_BLOCK, compressed(0x93B7)),
+    (Format::ASTC_10x5_UNORM_BLOCK, compressed(0x93B8)),
+    (Format::ASTC_10x6_UNORM_BLOCK, compressed(0x93B9)),
+    (Format::ASTC_10x8_UNORM_BLOCK, compressed(0x93BA)),
+    (Format::ASTC_10x10_UNORM_BLOCK, compressed(0x93BB)),
+    (Format::ASTC_12x10_UNORM_BLOCK, compressed(0x93BC)),
+    (Format::ASTC_12x12_UNORM_BLOCK, compressed(0x93BD)),
+    (Format::ASTC_4x4_SRGB_BLOCK, compressed(0x93D0)),
+    (Format::ASTC_5x4_SRGB_BLOCK, compressed(0x93D1)),
+    (Format::ASTC_5x5_SRGB_BLOCK, compressed(0x93D2)),
+    (Format::ASTC_6x5_SRGB_BLOCK, compressed(0x93D3)),
+    (Format::ASTC_6x6_SRGB_BLOCK, compressed(0x93D4)),
+    (Format::ASTC_8x5_SRGB_BLOCK, compressed(0x93D5)),
+    (Format::ASTC_8x6_SRGB_BLOCK, compressed(0x93D6)),
+    (Format::ASTC_8x8_SRGB_BLOCK, compressed(0x93D7)),
+    (Format::ASTC_10x5_SRGB_BLOCK, compressed(0x93D8)),
+    (Format::ASTC_10x6_SRGB_BLOCK, compressed(0x93D9)),
+    (Format::ASTC_10x8_SRGB_BLOCK, compressed(0x93DA)),
+    (Format::ASTC_10x10_SRGB_BLOCK, compressed(0x93DB)),
+    (Format::ASTC_12x10_SRGB_BLOCK, compressed(0x93DC)),
+    (Format::ASTC_12x12_SRGB_BLOCK, compressed(0x93DD)),
+];
+
+/// The OpenGL description of `format`, if there is one
+pub fn from_format(format: Format) -> Option<GlFormat> {
+    FORMATS
+        .iter()
+        .find(|&&(candidate, _)| candidate == format)
+        .map(|&(_, gl_format)| gl_format)
+}
+
+/// The format described by `gl_format`, if there is one
+///
+/// If no format matches all three values, the format is looked up by its sized internal format alone. Unsized
+/// internal formats such as `GL_RGBA` are looked up by format and type instead, preferring normalized formats.
+/// `GL_ETC1_RGB8_OES` maps to [`Format::ETC2_R8G8B8_UNORM_BLOCK`], a superset of ETC1, as libktx does.
+pub fn to_format(gl_format: GlFormat) -> Option<Format> {
+    if gl_format.internal_format == ETC1_RGB8_OES {
+        return Some(Format::ETC2_R8G8B8_UNORM_BLOCK);
+    }
+    let unsized_internal_format = matches!(
+        gl_format.internal_format,
+        STENCIL_INDEX | DEPTH_COMPONENT | RED | RGB | RGBA | BGR | BGRA | RG | DEPTH_STENCIL
+    );
+    FORMATS
+        .iter()
+        .find(|&&(_, candidate)| candidate == gl_format)
+        .or_else(|| {
+            FORMATS
+                .iter()
+                .find(|&&(_, candidate)| candidate.internal_format == gl_format.internal_format)
+        })
+        .or_else(|| {
+            FORMATS.iter().find(|&&(_, candidate)| {
+                unsized_internal_format && candidate.format == gl_format.format && candidate.type_ == gl_format.type_
+            })
+        })
+        .map(|&(format, _)| format)
+}
+
+/// Parse the value of a [`KEY`] metadata entry
+pub fn parse_value(value: &[u8]) -> Option<GlFormat> {
+    Some(GlFormat::from_bytes(value.get(..GlFormat::LENGTH)?.try_into().unwrap()))
+}
+
+#[test]
+fn test_gl_round_trip() {
+    for &(format, gl_format) in FORMATS {
+        assert_eq!(from_format(format), Some(gl_format));
+        assert_eq!(to_format(gl_format), Some(format));
+        assert_eq!(GlFormat::from_bytes(&gl_format.as_bytes()), gl_format);
+    }
+    assert_eq!(to_format(gl(0x8058, 0, 0)), Some(Format::R8G8B8A8_UNORM));
+
+    // Unsized internal formats
+    assert_eq!(to_format(gl(RGBA, RGBA, UNSIGNED_BYTE)), Some(Format::R8G8B8A8_UNORM));
+    assert_eq!(
+        to_format(gl(RGB, RGB, UNSIGNED_SHORT_5_6_5)),
+        Some(Format::R5G6B5_UNORM_PACK16)
+    );
+    assert_eq!(to_format(gl(RGBA, RGBA, HALF_FLOAT)), Some(Format::R16G16B16A16_SFLOAT));
+    assert_eq!(to_format(gl(RED, RED, FLOAT)), Some(Format::R32_SFLOAT));
+    assert_eq!(to_format(gl(0x1234, RGBA, UNSIGNED_BYTE)), None);
+
+    assert_eq!(
+        to_format(compressed(ETC1_RGB8_OES)),
+        Some(Format::ETC2_R8G8B8_UNORM_BLOCK)
+    );
+}
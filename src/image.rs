@@ -0,0 +1,380 @@
+//! Conversion between [`image`] crate images and KTX2 textures.
+
+use crate::{
+    astc, bc, bc6h, bc7, etc, format::Numeric, half::f16_to_f32, texel, Format, ImageLayout, MipmapOptions, Reader,
+    TextureError, Writer,
+};
+use ::image::{DynamicImage, ImageBuffer, ImageFormat};
+use core::convert::TryInto;
+use std::{string::String, vec::Vec};
+
+/// File format of [`debug_image`] exports of floating point textures
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HdrFormat {
+    /// OpenEXR, which keeps alpha and negative values
+    OpenExr,
+    /// Radiance HDR, which drops alpha and clamps negative values to 0
+    Radiance,
+}
+
+/// Create a texture from an image, such as a [`DynamicImage`] or an [`ImageBuffer`] of `u8`, `u16` or `f32`
+/// subpixels
+///
+/// `srgb` selects sRGB formats for 8-bit images. If `mipmaps` is `None`, the texture has a single level, otherwise its
+/// mip chain is generated. Grayscale images store luma in red and alpha in green, with a `KTXswizzle` entry that maps
+/// them back to gray.
+pub fn from_image(
+    image: impl Into<DynamicImage>,
+    srgb: bool,
+    mipmaps: Option<&MipmapOptions>,
+) -> Result<Writer, TextureError> {
+    let image = image.into();
+    let unorm8 = |unorm, srgb_format| if srgb { srgb_format } else { unorm };
+    let (format, data, swizzle) = match &image {
+        DynamicImage::ImageLuma8(image) => (unorm8(Format::R8_UNORM, Format::R8_SRGB), image.to_vec(), Some("rrr1")),
+        DynamicImage::ImageLumaA8(image) => (
+            unorm8(Format::R8G8_UNORM, Format::R8G8_SRGB),
+            image.to_vec(),
+            Some("rrrg"),
+        ),
+        DynamicImage::ImageRgb8(image) => (unorm8(Format::R8G8B8_UNORM, Format::R8G8B8_SRGB), image.to_vec(), None),
+        DynamicImage::ImageRgba8(image) => (
+            unorm8(Format::R8G8B8A8_UNORM, Format::R8G8B8A8_SRGB),
+            image.to_vec(),
+            None,
+        ),
+        DynamicImage::ImageLuma16(image) => (Format::R16_UNORM, le_bytes(image, u16::to_le_bytes), Some("rrr1")),
+        DynamicImage::ImageLumaA16(image) => (Format::R16G16_UNORM, le_bytes(image, u16::to_le_bytes), Some("rrrg")),
+        DynamicImage::ImageRgb16(image) => (Format::R16G16B16_UNORM, le_bytes(image, u16::to_le_bytes), None),
+        DynamicImage::ImageRgba16(image) => (Format::R16G16B16A16_UNORM, le_bytes(image, u16::to_le_bytes), None),
+        DynamicImage::ImageRgb32F(image) => (Format::R32G32B32_SFLOAT, le_bytes(image, f32::to_le_bytes), None),
+        DynamicImage::ImageRgba32F(image) => (Format::R32G32B32A32_SFLOAT, le_bytes(image, f32::to_le_bytes), None),
+        _ => return Err(TextureError::UnsupportedFormat),
+    };
+
+    let options = match mipmaps {
+        Some(options) => *options,
+        None => MipmapOptions {
+            level_count: Some(1),
+            ..MipmapOptions::default()
+        },
+    };
+    let mut writer = Writer::with_mipmaps(format, [image.width(), image.height(), 0], &data, &options)?;
+    if let Some(swizzle) = swizzle {
+        writer
+            .key_value_data
+            .insert(String::from("KTXswizzle"), [swizzle.as_bytes(), b"\0"].concat());
+    }
+    Ok(writer)
+}
+
+fn le_bytes<P: ::image::Pixel, const N: usize>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    to_le_bytes: fn(P::Subpixel) -> [u8; N],
+) -> Vec<u8> {
+    image
+        .as_raw()
+        .iter()
+        .flat_map(|&subpixel| to_le_bytes(subpixel))
+        .collect()
+}
+
+/// Decode a single 2D image of an uncompressed texture
+///
+/// `image` indexes the 2D images of the level in storage order: z slices within faces within layers. Formats that
+/// match an image type are copied without conversion, two-channel formats becoming luma-alpha images. Other formats
+/// are decoded into RGBA `f32` images with [`texel::decode`].
+pub fn to_image<Data: AsRef<[u8]>>(
+    reader: &Reader<Data>,
+    level: u32,
+    image: u32,
+) -> Result<DynamicImage, TextureError> {
+    let (format, [width, height], data) = image_data(reader, level, image)?;
+    if format.is_compressed() {
+        return Err(TextureError::UnsupportedFormat);
+    }
+
+    let u16s = || {
+        data.chunks_exact(2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .collect::<Vec<_>>()
+    };
+    let f32s = || {
+        data.chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect::<Vec<_>>()
+    };
+    let image = match format {
+        Format::R8_UNORM | Format::R8_SRGB => {
+            ImageBuffer::from_raw(width, height, data.to_vec()).map(DynamicImage::ImageLuma8)
+        }
+        Format::R8G8_UNORM | Format::R8G8_SRGB => {
+            ImageBuffer::from_raw(width, height, data.to_vec()).map(DynamicImage::ImageLumaA8)
+        }
+        Format::R8G8B8_UNORM | Format::R8G8B8_SRGB => {
+            ImageBuffer::from_raw(width, height, data.to_vec()).map(DynamicImage::ImageRgb8)
+        }
+        Format::R8G8B8A8_UNORM | Format::R8G8B8A8_SRGB => {
+            ImageBuffer::from_raw(width, height, data.to_vec()).map(DynamicImage::ImageRgba8)
+        }
+        Format::R16_UNORM => ImageBuffer::from_raw(width, height, u16s()).map(DynamicImage::ImageLuma16),
+        Format::R16G16_UNORM => ImageBuffer::from_raw(width, height, u16s()).map(DynamicImage::ImageLumaA16),
+        Format::R16G16B16_UNORM => ImageBuffer::from_raw(width, height, u16s()).map(DynamicImage::ImageRgb16),
+        Format::R16G16B16A16_UNORM => ImageBuffer::from_raw(width, height, u16s()).map(DynamicImage::ImageRgba16),
+        Format::R32G32B32_SFLOAT => ImageBuffer::from_raw(width, height, f32s()).map(DynamicImage::ImageRgb32F),
+        Format::R32G32B32A32_SFLOAT => ImageBuffer::from_raw(width, height, f32s()).map(DynamicImage::ImageRgba32F),
+        _ => {
+            let texels = texel::decode(format, data).ok_or(TextureError::UnsupportedFormat)?;
+            ImageBuffer::from_raw(width, height, texels.concat()).map(DynamicImage::ImageRgba32F)
+        }
+    };
+    image.ok_or(TextureError::InvalidLength)
+}
+
+/// Decode a single 2D image for visual inspection, along with the file format to save it in
+///
+/// Floating point formats, including BC6H, decode to `f32` images to be saved as OpenEXR or Radiance HDR, as selected
+/// by `hdr_format`. Other formats decode to images to be saved as PNG: uncompressed formats as in [`to_image`],
+/// converted to 8-bit RGBA if PNG can't store them, and the BC1 to BC5, BC7, ETC2, EAC and ASTC block-compressed
+/// formats to 8-bit RGBA. Signed values are clamped to 0 and sRGB values aren't linearized. The image is flipped
+/// according to the texture's `KTXorientation`, so its first row is at the top and its first column on the left.
+///
+/// Any level and image can be exported. ASTC blocks using HDR color endpoint modes decode to magenta, as do invalid
+/// blocks. Other formats, such as PVRTC, return [`TextureError::UnsupportedFormat`], and supercompressed textures
+/// [`TextureError::UnsupportedSupercompression`].
+///
+/// ```no_run
+/// # let file = include_bytes!("../data/test_tex.ktx2");
+/// let reader = ktx2::Reader::new(&file[..]).unwrap();
+/// let (image, format) = ktx2::image::debug_image(&reader, 0, 0, ktx2::image::HdrFormat::OpenExr).unwrap();
+/// image.save_with_format("level0.png", format).unwrap();
+/// ```
+pub fn debug_image<Data: AsRef<[u8]>>(
+    reader: &Reader<Data>,
+    level: u32,
+    image: u32,
+    hdr_format: HdrFormat,
+) -> Result<(DynamicImage, ImageFormat), TextureError> {
+    let (format, [width, height], data) = image_data(reader, level, image)?;
+    let float = matches!(
+        format,
+        Format::BC6H_UFLOAT_BLOCK | Format::BC6H_SFLOAT_BLOCK | Format::E5B9G9R9_UFLOAT_PACK32
+    ) || format.components().is_some_and(|components| {
+        components
+            .iter()
+            .any(|component| matches!(component.numeric, Numeric::SFloat | Numeric::UFloat))
+    });
+
+    let image = if format.is_compressed() {
+        let texels = decode_blocks(format, data, width, height)?;
+        DynamicImage::ImageRgba32F(
+            ImageBuffer::from_raw(width, height, texels.concat()).ok_or(TextureError::InvalidLength)?,
+        )
+    } else {
+        to_image(reader, level, image)?
+    };
+    let (mut image, image_format) = match image {
+        image if float && hdr_format == HdrFormat::OpenExr => {
+            (DynamicImage::ImageRgba32F(image.into_rgba32f()), ImageFormat::OpenExr)
+        }
+        image if float => (DynamicImage::ImageRgb32F(image.into_rgb32f()), ImageFormat::Hdr),
+        image @ DynamicImage::ImageRgba32F(_) => (DynamicImage::ImageRgba8(image.into_rgba8()), ImageFormat::Png),
+        image => (image, ImageFormat::Png),
+    };
+
+    if let Some(orientation) = reader.orientation() {
+        if orientation.left {
+            image = image.fliph();
+        }
+        if orientation.up {
+            image = image.flipv();
+        }
+    }
+    Ok((image, image_format))
+}
+
+/// Decoder of a single block into its texels in row-major order
+type BlockDecoder<'a> = dyn Fn(&[u8], &mut [[f32; 4]]) + 'a;
+
+/// Decode the blocks of a block-compressed image into RGBA values in row-major order
+fn decode_blocks(format: Format, data: &[u8], width: u32, height: u32) -> Result<Vec<[f32; 4]>, TextureError> {
+    let signed = matches!(
+        format,
+        Format::BC4_SNORM_BLOCK
+            | Format::BC5_SNORM_BLOCK
+            | Format::BC6H_SFLOAT_BLOCK
+            | Format::EAC_R11_SNORM_BLOCK
+            | Format::EAC_R11G11_SNORM_BLOCK
+    );
+    let [block_width, block_height, _] = format.block_dimensions().map(|dimension| dimension as usize);
+    let decode_block: &BlockDecoder = match format {
+        Format::BC1_RGB_UNORM_BLOCK | Format::BC1_RGB_SRGB_BLOCK => {
+            &|block, texels| bc::decode_bc1(block, false, texels)
+        }
+        Format::BC1_RGBA_UNORM_BLOCK | Format::BC1_RGBA_SRGB_BLOCK => {
+            &|block, texels| bc::decode_bc1(block, true, texels)
+        }
+        Format::BC2_UNORM_BLOCK | Format::BC2_SRGB_BLOCK => &bc::decode_bc2,
+        Format::BC3_UNORM_BLOCK | Format::BC3_SRGB_BLOCK => &bc::decode_bc3,
+        Format::BC4_UNORM_BLOCK | Format::BC4_SNORM_BLOCK => &|block, texels| bc::decode_bc4(block, signed, texels),
+        Format::BC5_UNORM_BLOCK | Format::BC5_SNORM_BLOCK => &|block, texels| bc::decode_bc5(block, signed, texels),
+        Format::BC6H_UFLOAT_BLOCK | Format::BC6H_SFLOAT_BLOCK => &|block, texels| {
+            let decoded = bc6h::decode_block(block.try_into().unwrap(), signed);
+            for (texel, rgb) in texels.iter_mut().zip(decoded) {
+                *texel = [f16_to_f32(rgb[0]), f16_to_f32(rgb[1]), f16_to_f32(rgb[2]), 1.0];
+            }
+        },
+        Format::BC7_UNORM_BLOCK | Format::BC7_SRGB_BLOCK => &bc7::decode_block,
+        Format::ETC2_R8G8B8_UNORM_BLOCK | Format::ETC2_R8G8B8_SRGB_BLOCK => {
+            &|block, texels| etc::decode_etc2(block, false, texels)
+        }
+        Format::ETC2_R8G8B8A1_UNORM_BLOCK | Format::ETC2_R8G8B8A1_SRGB_BLOCK => {
+            &|block, texels| etc::decode_etc2(block, true, texels)
+        }
+        Format::ETC2_R8G8B8A8_UNORM_BLOCK | Format::ETC2_R8G8B8A8_SRGB_BLOCK => &etc::decode_etc2_alpha,
+        Format::EAC_R11_UNORM_BLOCK | Format::EAC_R11_SNORM_BLOCK => {
+            &|block, texels| etc::decode_eac_r11(block, signed, texels)
+        }
+        Format::EAC_R11G11_UNORM_BLOCK | Format::EAC_R11G11_SNORM_BLOCK => {
+            &|block, texels| etc::decode_eac_rg11(block, signed, texels)
+        }
+        _ if (Format::ASTC_4x4_UNORM_BLOCK..=Format::ASTC_12x12_SRGB_BLOCK).contains(&format) => {
+            &|block, texels| astc::decode_block(block, [block_width, block_height], format.is_srgb(), texels)
+        }
+        _ => return Err(TextureError::UnsupportedFormat),
+    };
+
+    let block_size = format.block_size().ok_or(TextureError::UnsupportedFormat)? as usize;
+    let (width, height) = (width as usize, height as usize);
+    let blocks_per_row = width.div_ceil(block_width);
+    let mut texels = std::vec![[0.0; 4]; width * height];
+    let mut block_texels = std::vec![[0.0; 4]; block_width * block_height];
+    for (block_index, block) in data.chunks_exact(block_size).enumerate() {
+        decode_block(block, &mut block_texels);
+        let (block_x, block_y) = (
+            block_index % blocks_per_row * block_width,
+            block_index / blocks_per_row * block_height,
+        );
+        for (index, texel) in block_texels.iter().enumerate() {
+            let (x, y) = (block_x + index % block_width, block_y + index / block_width);
+            if x < width && y < height {
+                texels[y * width + x] = *texel;
+            }
+        }
+    }
+    Ok(texels)
+}
+
+/// Format, size and data of a single 2D image
+fn image_data<Data: AsRef<[u8]>>(
+    reader: &Reader<Data>,
+    level: u32,
+    image: u32,
+) -> Result<(Format, [u32; 2], &[u8]), TextureError> {
+    let header = reader.header();
+    if header.supercompression_scheme.is_some() {
+        return Err(TextureError::UnsupportedSupercompression);
+    }
+    let format = header.format.ok_or(TextureError::UnsupportedFormat)?;
+    let [width, height, _] = header.level_extent(level);
+    let layout = ImageLayout::new(format, [width, height, 1])?;
+
+    let level_data = reader
+        .levels()
+        .nth(level as usize)
+        .ok_or(TextureError::InvalidLength)?
+        .data;
+    let start = layout.byte_length * image as usize;
+    let data = level_data
+        .get(start..start + layout.byte_length)
+        .ok_or(TextureError::InvalidLength)?;
+    Ok((format, [width, height], data))
+}
+
+#[test]
+fn test_image_round_trip() {
+    let rgba = ImageBuffer::from_fn(4, 2, |x, y| ::image::Rgba([x as u8 * 60, y as u8 * 200, 7, 255]));
+    let writer = from_image(rgba.clone(), true, Some(&MipmapOptions::default())).unwrap();
+    assert_eq!(writer.header.format, Some(Format::R8G8B8A8_SRGB));
+    let file = writer.write();
+    let reader = Reader::new(&file[..]).unwrap();
+    assert_eq!(reader.header().level_count, 3);
+    assert_eq!(to_image(&reader, 0, 0).unwrap(), DynamicImage::ImageRgba8(rgba));
+    assert_eq!(to_image(&reader, 2, 0).unwrap().width(), 1);
+
+    let luma = ImageBuffer::from_fn(3, 3, |x, y| ::image::Luma([(x * 1000 + y) as u16]));
+    let writer = from_image(luma.clone(), false, None).unwrap();
+    assert_eq!(writer.key_value_data["KTXswizzle"], b"rrr1\0");
+    let file = writer.write();
+    let reader = Reader::new(&file[..]).unwrap();
+    assert_eq!(to_image(&reader, 0, 0).unwrap(), DynamicImage::ImageLuma16(luma));
+
+    let file = include_bytes!("../data/test_tex.ktx2");
+    let reader = Reader::new(&file[..]).unwrap();
+    let image = to_image(&reader, 0, 0).unwrap().into_rgba32f();
+    assert_eq!(
+        image.dimensions(),
+        (reader.header().pixel_width, reader.header().pixel_height)
+    );
+
+    // Debug images are flipped to be upright
+    let mut writer = from_image(ImageBuffer::from_fn(1, 2, |_, y| ::image::Luma([y as u8])), false, None).unwrap();
+    writer
+        .key_value_data
+        .insert(String::from("KTXorientation"), b"ru\0".to_vec());
+    let file = writer.write();
+    let reader = Reader::new(&file[..]).unwrap();
+    let (image, format) = debug_image(&reader, 0, 0, HdrFormat::OpenExr).unwrap();
+    assert_eq!((image.as_bytes(), format), (&[1, 0][..], ImageFormat::Png));
+    image.write_to(&mut std::io::Cursor::new(Vec::new()), format).unwrap();
+
+    let texture = |format: Format, [width, height]: [u32; 2], data: Vec<u8>| {
+        let writer = Writer {
+            header: crate::Header {
+                format: Some(format),
+                type_size: 1,
+                pixel_width: width,
+                pixel_height: height,
+                pixel_depth: 0,
+                layer_count: 0,
+                face_count: 1,
+                level_count: 1,
+                supercompression_scheme: None,
+                index: Default::default(),
+            },
+            data_format_descriptors: format.data_format_descriptors().unwrap(),
+            key_value_data: Default::default(),
+            supercompression_global_data: Vec::new(),
+            levels: std::vec![crate::WriterLevel::new(data)],
+        };
+        writer.write()
+    };
+    let texels = [[0.5, 2.0, 100.0, 1.0]];
+    let data = texel::encode(Format::R16G16B16A16_SFLOAT, &texels).unwrap();
+    let file = texture(Format::R16G16B16A16_SFLOAT, [1, 1], data);
+    let reader = Reader::new(&file[..]).unwrap();
+    let (image, format) = debug_image(&reader, 0, 0, HdrFormat::OpenExr).unwrap();
+    assert_eq!(format, ImageFormat::OpenExr);
+    assert_eq!(image.into_rgba32f().as_raw(), &texels.concat());
+    let (image, format) = debug_image(&reader, 0, 0, HdrFormat::Radiance).unwrap();
+    assert_eq!(format, ImageFormat::Hdr);
+    assert_eq!(image.as_rgb32f().unwrap().as_raw(), &[0.5, 2.0, 100.0]);
+    image.write_to(&mut std::io::Cursor::new(Vec::new()), format).unwrap();
+
+    // Block-compressed images are cropped to their size, here a red BC1 block and a partial blue one
+    let file = texture(
+        Format::BC1_RGB_UNORM_BLOCK,
+        [5, 4],
+        [[0x00, 0xF8], [0x1F, 0x00]]
+            .iter()
+            .flat_map(|color| [color[0], color[1], 0, 0, 0, 0, 0, 0])
+            .collect(),
+    );
+    let reader = Reader::new(&file[..]).unwrap();
+    let (image, format) = debug_image(&reader, 0, 0, HdrFormat::OpenExr).unwrap();
+    assert_eq!(format, ImageFormat::Png);
+    let image = image.as_rgba8().unwrap();
+    assert_eq!(image.dimensions(), (5, 4));
+    assert_eq!(image.get_pixel(3, 3).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(4, 0).0, [0, 0, 255, 255]);
+}
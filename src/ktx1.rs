@@ -0,0 +1,425 @@
+//! Reading [KTX 1.1](https://registry.khronos.org/KTX/specs/1.0/ktxspec.v1.html) files and converting them to KTX2.
+
+use crate::{
+    gl::{self, GlFormat},
+    Format, ImageLayout, Index, ParseError, TextureError, Writer, WriterLevel,
+};
+use core::convert::TryInto;
+use std::{collections::BTreeMap, string::String, vec::Vec};
+
+/// Identifier at the start of every KTX 1.1 file
+pub const IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+
+const ENDIANNESS: u32 = 0x0403_0201;
+
+/// Decodes KTX 1.1 texture data
+pub struct Reader<Data: AsRef<[u8]>> {
+    input: Data,
+    header: Header,
+}
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// Decode KTX 1.1 data from `input`
+    pub fn new(input: Data) -> Result<Self, ParseError> {
+        let data = input.as_ref();
+        if data.len() < Header::LENGTH {
+            return Err(ParseError::UnexpectedEnd);
+        }
+        let header = Header::from_bytes(data[..Header::LENGTH].try_into().unwrap())?;
+
+        let reader = Self { input, header };
+        // Check the bounds of the key-value data and every level
+        let kvd_end = Header::LENGTH
+            .checked_add(header.bytes_of_key_value_data as usize)
+            .ok_or(ParseError::UnexpectedEnd)?;
+        if kvd_end > reader.input.as_ref().len() {
+            return Err(ParseError::UnexpectedEnd);
+        }
+        let mut offset = kvd_end;
+        for _ in 0..header.number_of_mipmap_levels.max(1) {
+            offset = reader.level_at(offset)?.1;
+        }
+
+        Ok(reader)
+    }
+
+    pub fn header(&self) -> Header {
+        self.header
+    }
+
+    /// Iterator over the texture's key-value pairs, in the order they are stored
+    pub fn key_value_data(&self) -> KeyValueDataIterator<'_> {
+        let start = Header::LENGTH;
+        KeyValueDataIterator {
+            data: &self.input.as_ref()[start..start + self.header.bytes_of_key_value_data as usize],
+            big_endian: self.header.big_endian,
+        }
+    }
+
+    /// Iterator over the texture's mip levels, starting with the base level
+    pub fn levels(&self) -> impl ExactSizeIterator<Item = Level<'_>> + '_ {
+        let mut offset = Header::LENGTH + self.header.bytes_of_key_value_data as usize;
+        (0..self.header.number_of_mipmap_levels.max(1)).map(move |_| {
+            // Bounds were checked when creating the reader
+            let (level, next_offset) = self.level_at(offset).unwrap();
+            offset = next_offset;
+            level
+        })
+    }
+
+    /// The format of the texture, looked up from its OpenGL format and type
+    pub fn format(&self) -> Option<Format> {
+        gl::to_format(GlFormat {
+            internal_format: self.header.gl_internal_format,
+            format: self.header.gl_format,
+            type_: self.header.gl_type,
+        })
+    }
+
+    /// Convert the texture into KTX2
+    ///
+    /// Mip levels are reordered, row padding is removed, texel data is converted to little-endian and a data format
+    /// descriptor is created for the format. Key-value pairs are copied, with `KTXorientation` converted to its KTX2
+    /// form.
+    pub fn to_ktx2(&self) -> Result<Writer, TextureError> {
+        let format = self.format().ok_or(TextureError::UnsupportedFormat)?;
+        let data_format_descriptors = format
+            .data_format_descriptors()
+            .ok_or(TextureError::UnsupportedFormat)?;
+        let header = self.header;
+
+        let non_array_cubemap = header.number_of_faces == 6 && header.number_of_array_elements == 0;
+        let images = header
+            .number_of_array_elements
+            .max(1)
+            .checked_mul(header.number_of_faces)
+            .ok_or(TextureError::InvalidLength)? as usize;
+        let levels = self
+            .levels()
+            .enumerate()
+            .map(|(index, level)| {
+                let extent = [header.pixel_width, header.pixel_height, header.pixel_depth]
+                    .map(|dimension| dimension.checked_shr(index as u32).unwrap_or(0).max(1));
+                let layout = ImageLayout::new(format, extent)?;
+                let row_length = layout.bytes_per_row as usize;
+                let rows = (layout.rows_per_image as usize)
+                    .checked_mul(layout.physical_extent[2] as usize)
+                    .ok_or(TextureError::InvalidLength)?;
+
+                // Rows of uncompressed formats are 4-byte aligned, but some writers omit the padding
+                let padded_row_length = if format.is_compressed() {
+                    row_length
+                } else {
+                    (row_length + 3) & !3
+                };
+                let image_size = level.image_size as usize;
+                let images_per_image_size = if non_array_cubemap { 1 } else { images };
+                let images_length = |row_length: usize| {
+                    row_length
+                        .checked_mul(rows)
+                        .and_then(|length| length.checked_mul(images_per_image_size))
+                };
+                let stride = if Some(image_size) == images_length(padded_row_length) {
+                    padded_row_length
+                } else if Some(image_size) == images_length(row_length) {
+                    row_length
+                } else {
+                    return Err(TextureError::InvalidLength);
+                };
+
+                let image_stride = if non_array_cubemap {
+                    (image_size + 3) & !3
+                } else {
+                    stride * rows
+                };
+                let data_length = row_length
+                    .checked_mul(rows)
+                    .and_then(|length| length.checked_mul(images))
+                    .ok_or(TextureError::InvalidLength)?;
+                let mut data = Vec::with_capacity(data_length);
+                for image in 0..images {
+                    let image = &level.data[image * image_stride..];
+                    for row in 0..rows {
+                        data.extend_from_slice(&image[row * stride..row * stride + row_length]);
+                    }
+                }
+                if header.big_endian {
+                    swap_bytes(&mut data, header.gl_type_size as usize);
+                }
+                Ok(WriterLevel::new(data))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let key_value_data = self
+            .key_value_data()
+            .map(|(key, value)| {
+                let value = match key {
+                    "KTXorientation" => convert_orientation(value),
+                    _ => value.to_vec(),
+                };
+                (String::from(key), value)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        Ok(Writer {
+            header: crate::Header {
+                format: Some(format),
+                type_size: format.type_size(),
+                pixel_width: header.pixel_width,
+                pixel_height: header.pixel_height,
+                pixel_depth: header.pixel_depth,
+                layer_count: header.number_of_array_elements,
+                face_count: header.number_of_faces,
+                level_count: header.number_of_mipmap_levels,
+                supercompression_scheme: None,
+                index: Index::default(),
+            },
+            data_format_descriptors,
+            key_value_data,
+            supercompression_global_data: Vec::new(),
+            levels,
+        })
+    }
+
+    /// Read the level starting at `offset`, returning it and the offset of the next level
+    fn level_at(&self, offset: usize) -> Result<(Level<'_>, usize), ParseError> {
+        let data = self.input.as_ref();
+        let image_size = read_u32(data, offset, self.header.big_endian)?;
+        let start = offset + 4;
+
+        // Faces of non-array cubemaps are stored separately, each padded to 4 bytes
+        let length = if self.header.number_of_faces == 6 && self.header.number_of_array_elements == 0 {
+            ((image_size as usize + 3) & !3) * 6
+        } else {
+            (image_size as usize + 3) & !3
+        };
+        let end = start.checked_add(length).ok_or(ParseError::UnexpectedEnd)?;
+        let level_data = data.get(start..end).ok_or(ParseError::UnexpectedEnd)?;
+
+        Ok((
+            Level {
+                image_size,
+                data: level_data,
+            },
+            end,
+        ))
+    }
+}
+
+impl<Data: AsRef<[u8]>> AsRef<[u8]> for Reader<Data> {
+    fn as_ref(&self) -> &[u8] {
+        self.input.as_ref()
+    }
+}
+
+/// KTX 1.1 header, with all values converted to native endianness
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Header {
+    /// Whether the file was written with big-endian byte order
+    pub big_endian: bool,
+    pub gl_type: u32,
+    pub gl_type_size: u32,
+    pub gl_format: u32,
+    pub gl_internal_format: u32,
+    pub gl_base_internal_format: u32,
+    pub pixel_width: u32,
+    pub pixel_height: u32,
+    pub pixel_depth: u32,
+    pub number_of_array_elements: u32,
+    pub number_of_faces: u32,
+    pub number_of_mipmap_levels: u32,
+    pub bytes_of_key_value_data: u32,
+}
+
+impl Header {
+    pub const LENGTH: usize = 64;
+
+    pub fn from_bytes(data: &[u8; Self::LENGTH]) -> Result<Self, ParseError> {
+        if !data.starts_with(&IDENTIFIER) {
+            return Err(ParseError::BadMagic);
+        }
+
+        let big_endian = match u32::from_le_bytes(data[12..16].try_into().unwrap()) {
+            ENDIANNESS => false,
+            endianness if endianness.swap_bytes() == ENDIANNESS => true,
+            _ => return Err(ParseError::BadMagic),
+        };
+        let field = |index: usize| read_u32(data, 16 + index * 4, big_endian).unwrap();
+
+        let header = Self {
+            big_endian,
+            gl_type: field(0),
+            gl_type_size: field(1),
+            gl_format: field(2),
+            gl_internal_format: field(3),
+            gl_base_internal_format: field(4),
+            pixel_width: field(5),
+            pixel_height: field(6),
+            pixel_depth: field(7),
+            number_of_array_elements: field(8),
+            number_of_faces: field(9),
+            number_of_mipmap_levels: field(10),
+            bytes_of_key_value_data: field(11),
+        };
+
+        if header.pixel_width == 0 {
+            return Err(ParseError::ZeroWidth);
+        }
+        if header.number_of_faces == 0 {
+            return Err(ParseError::ZeroFaceCount);
+        }
+
+        Ok(header)
+    }
+}
+
+/// Data of a single mip level, as stored in the file
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Level<'a> {
+    /// Byte length of the level, or of a single face for cubemaps which aren't arrays
+    pub image_size: u32,
+    /// Images of the level, including row and cubemap padding, in the byte order of the file
+    pub data: &'a [u8],
+}
+
+/// An iterator that parses the key-value pairs of a KTX 1.1 file
+pub struct KeyValueDataIterator<'data> {
+    data: &'data [u8],
+    big_endian: bool,
+}
+
+impl<'data> Iterator for KeyValueDataIterator<'data> {
+    type Item = (&'data str, &'data [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let length = read_u32(self.data, 0, self.big_endian).ok()? as usize;
+            let end = length.checked_add(4)?;
+            let key_and_value = self.data.get(4..end);
+            self.data = self.data.get((end.checked_add(3)? & !3).min(self.data.len())..)?;
+
+            let key_and_value = match key_and_value {
+                Some(key_and_value) => key_and_value,
+                None => continue,
+            };
+            // The key is terminated with a NUL character.
+            let key_end_index = match key_and_value.iter().position(|&c| c == b'\0') {
+                Some(index) => index,
+                None => continue,
+            };
+            let key = match core::str::from_utf8(&key_and_value[..key_end_index]) {
+                Ok(key) => key,
+                Err(_) => continue,
+            };
+            return Some((key, &key_and_value[key_end_index + 1..]));
+        }
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize, big_endian: bool) -> Result<u32, ParseError> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or(ParseError::UnexpectedEnd)?
+        .try_into()
+        .unwrap();
+    Ok(if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    })
+}
+
+fn swap_bytes(data: &mut [u8], type_size: usize) {
+    if type_size > 1 {
+        for value in data.chunks_exact_mut(type_size) {
+            value.reverse();
+        }
+    }
+}
+
+/// Convert a KTX 1.1 orientation such as `S=r,T=d` into the KTX2 form `rd`
+fn convert_orientation(value: &[u8]) -> Vec<u8> {
+    let mut orientation = value
+        .split(|&byte| byte == b',')
+        .filter_map(|axis| axis.iter().position(|&byte| byte == b'=').map(|index| axis[index + 1]))
+        .filter(|&byte| byte != 0)
+        .collect::<Vec<_>>();
+    orientation.push(0);
+    orientation
+}
+
+#[test]
+fn test_ktx1_to_ktx2() {
+    // 3x2 RGB8 texture with 2 levels, written big-endian to exercise byte swapping and row padding
+    let mut file = IDENTIFIER.to_vec();
+    let orientation = b"KTXorientation\0S=r,T=u\0";
+    for value in [ENDIANNESS, 0x1401, 1, 0x1907, 0x8051, 0x1907, 3, 2, 0, 0, 1, 2, 4 + 24] {
+        file.extend_from_slice(&value.to_be_bytes());
+    }
+    file.extend_from_slice(&(orientation.len() as u32).to_be_bytes());
+    file.extend_from_slice(orientation);
+    file.push(0);
+    file.extend_from_slice(&24u32.to_be_bytes());
+    file.extend_from_slice(&[
+        1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 10, 11, 12, 13, 14, 15, 16, 17, 18, 0, 0, 0,
+    ]);
+    file.extend_from_slice(&4u32.to_be_bytes());
+    file.extend_from_slice(&[19, 20, 21, 0]);
+
+    let reader = Reader::new(&file[..]).unwrap();
+    assert!(reader.header().big_endian);
+    assert_eq!(reader.format(), Some(Format::R8G8B8_UNORM));
+    assert_eq!(
+        reader.key_value_data().next(),
+        Some(("KTXorientation", &b"S=r,T=u\0"[..]))
+    );
+
+    let ktx2 = reader.to_ktx2().unwrap().write();
+    let reader = crate::Reader::new(&ktx2[..]).unwrap();
+    let header = reader.header();
+    assert_eq!((header.pixel_width, header.pixel_height, header.level_count), (3, 2, 2));
+    let levels = reader.levels().map(|level| level.data).collect::<Vec<_>>();
+    assert_eq!(
+        levels,
+        [
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18][..],
+            &[19, 20, 21]
+        ]
+    );
+    assert_eq!(reader.key_value_data().next(), Some(("KTXorientation", &b"ru\0"[..])));
+
+    // Unsized GL_RGB internal format
+    file[28..32].copy_from_slice(&0x1907u32.to_be_bytes());
+    assert_eq!(Reader::new(&file[..]).unwrap().format(), Some(Format::R8G8B8_UNORM));
+
+    // Row counts whose image size overflows
+    let mut tall = file.clone();
+    tall[40..48].copy_from_slice(&[0xff; 8]);
+    assert!(matches!(
+        Reader::new(&tall[..]).unwrap().to_ktx2(),
+        Err(TextureError::InvalidLength)
+    ));
+
+    // Array element and face counts whose product overflows
+    file[48..52].copy_from_slice(&u32::MAX.to_be_bytes());
+    file[52..56].copy_from_slice(&6u32.to_be_bytes());
+    assert!(Reader::new(&file[..]).unwrap().to_ktx2().is_err());
+}
+
+#[test]
+fn test_ktx1_etc1() {
+    // Single 4x4 ETC1 block, which ETC2 decoders read as is
+    let block = [0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0];
+    let mut file = IDENTIFIER.to_vec();
+    for value in [ENDIANNESS, 0, 1, 0, 0x8D64, 0x1907, 4, 4, 0, 0, 1, 1, 0, 8] {
+        file.extend_from_slice(&value.to_le_bytes());
+    }
+    file.extend_from_slice(&block);
+
+    let reader = Reader::new(&file[..]).unwrap();
+    assert_eq!(reader.format(), Some(Format::ETC2_R8G8B8_UNORM_BLOCK));
+    let ktx2 = reader.to_ktx2().unwrap().write();
+    let reader = crate::Reader::new(&ktx2[..]).unwrap();
+    assert_eq!(reader.header().format, Some(Format::ETC2_R8G8B8_UNORM_BLOCK));
+    assert_eq!(reader.levels().next().unwrap().data, block);
+}
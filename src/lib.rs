@@ -4,6 +4,7 @@
 //! - [x] Async reading
 //! - [x] Parsing
 //! - [x] Validating
+//! - [x] Writing
 //! - [x] [Data format description](https://github.khronos.org/KTX-Specification/#_data_format_descriptor)
 //! - [ ] [Key/value data](https://github.khronos.org/KTX-Specification/#_keyvalue_data)
 //
@@ -26,12 +27,84 @@
 #[cfg(feature = "std")]
 extern crate std;
 
+mod alpha;
+#[cfg(feature = "std")]
+mod array;
+#[cfg(feature = "ash")]
+pub mod ash;
+#[cfg(feature = "image")]
+mod astc;
+#[cfg(feature = "std")]
+mod basis;
+#[cfg(feature = "image")]
+mod bc;
+#[cfg(feature = "image")]
+mod bc6h;
+#[cfg(feature = "image")]
+mod bc7;
+mod channel;
+#[cfg(feature = "std")]
+pub mod color;
+#[cfg(feature = "std")]
+pub mod cubemap;
+#[cfg(feature = "std")]
+pub mod dds;
+mod descriptor;
+pub mod dxgi;
+#[cfg(feature = "std")]
+mod editor;
 mod enums;
 mod error;
+#[cfg(feature = "image")]
+mod etc;
+#[cfg(feature = "std")]
+mod etc1s;
+mod format;
+pub mod gl;
+mod half;
+#[cfg(feature = "image")]
+pub mod image;
+#[cfg(feature = "std")]
+pub mod ktx1;
+pub mod metal;
+#[cfg(feature = "std")]
+mod mipmap;
+mod sample;
+#[cfg(any(feature = "zstd", feature = "zlib"))]
+mod supercompression;
+#[cfg(feature = "std")]
+pub mod texel;
+#[cfg(feature = "std")]
+mod truncate;
+#[cfg(feature = "std")]
+mod uastc;
+#[cfg(feature = "wgpu")]
+pub mod wgpu;
+#[cfg(feature = "std")]
+mod writer;
+#[cfg(feature = "std")]
+pub mod yuv;
 
 pub use crate::{
+    alpha::{premultiply_alpha, unpremultiply_alpha, AlphaMode},
+    channel::Channel,
+    descriptor::{AdditionalDimensions, AdditionalPlanes, DescriptorBlock, VendorDescriptorBlock},
     enums::{ColorModel, ColorPrimaries, Format, SupercompressionScheme, TransferFunction},
-    error::ParseError,
+    error::{ParseError, TextureError},
+    format::ImageLayout,
+    sample::SampleBounds,
+};
+#[cfg(feature = "std")]
+pub use crate::{
+    basis::{
+        etc1s_data_format_descriptors, uastc_data_format_descriptors, BasisLzGlobalData, Etc1sChannels, ImageDesc,
+        ImageFlags, UastcChannels,
+    },
+    editor::MetadataEditor,
+    etc1s::Etc1sOptions,
+    mipmap::{full_mip_chain_length, generate_mipmaps, MipmapFilter, MipmapOptions},
+    uastc::{UastcOptions, UastcRdoOptions},
+    writer::{Writer, WriterLevel},
 };
 
 use core::convert::TryInto;
@@ -138,7 +211,7 @@ impl<Data: AsRef<[u8]>> Reader<Data> {
     }
 
     /// Iterator over the texture's mip levels
-    pub fn levels(&self) -> impl ExactSizeIterator<Item = Level> + '_ {
+    pub fn levels(&self) -> impl ExactSizeIterator<Item = Level<'_>> + '_ {
         self.level_index().unwrap().map(move |level| Level {
             // Bounds-checking previously performed in `new`
             data: &self.input.as_ref()[level.byte_offset as usize..(level.byte_offset + level.byte_length) as usize],
@@ -154,7 +227,7 @@ impl<Data: AsRef<[u8]>> Reader<Data> {
         &self.input.as_ref()[start..end]
     }
 
-    pub fn data_format_descriptors(&self) -> impl Iterator<Item = DataFormatDescriptor> {
+    pub fn data_format_descriptors(&self) -> impl Iterator<Item = DataFormatDescriptor<'_>> {
         let header = self.header();
         let start = header.index.dfd_byte_offset as usize;
         // Bounds-checking previously performed in `new`
@@ -166,7 +239,7 @@ impl<Data: AsRef<[u8]>> Reader<Data> {
     }
 
     /// Iterator over the key-value pairs
-    pub fn key_value_data(&self) -> KeyValueDataIterator {
+    pub fn key_value_data(&self) -> KeyValueDataIterator<'_> {
         let header = self.header();
 
         let start = header.index.kvd_byte_offset as usize;
@@ -251,7 +324,7 @@ impl<'data> Iterator for KeyValueDataIterator<'data> {
             let key = &key_and_value[..key_end_index];
             let value = &key_and_value[key_end_index + 1..];
 
-            let key = match std::str::from_utf8(key) {
+            let key = match core::str::from_utf8(key) {
                 Ok(key) => key,
                 Err(_) => continue,
             };
@@ -291,7 +364,7 @@ pub struct Header {
 }
 
 /// An index giving the byte offsets from the start of the file and byte sizes of the various sections of the KTX2 file.
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
 pub struct Index {
     pub dfd_byte_offset: u32,
     pub dfd_byte_length: u32,
@@ -339,6 +412,12 @@ impl Header {
         Ok(header)
     }
 
+    /// Width, height and depth in pixels of mip `level`, with unused dimensions set to 1
+    pub fn level_extent(&self, level: u32) -> [u32; 3] {
+        [self.pixel_width, self.pixel_height, self.pixel_depth]
+            .map(|dimension| dimension.checked_shr(level).unwrap_or(0).max(1))
+    }
+
     pub fn as_bytes(&self) -> [u8; Self::LENGTH] {
         let mut bytes = [0; Self::LENGTH];
 
@@ -419,7 +498,7 @@ bitflags::bitflags! {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct DataFormatDescriptorHeader {
     pub vendor_id: u32,       //: 17;
     pub descriptor_type: u32, //: 15;
@@ -427,7 +506,7 @@ pub struct DataFormatDescriptorHeader {
 }
 
 impl DataFormatDescriptorHeader {
-    const LENGTH: usize = 8;
+    pub const LENGTH: usize = 8;
 
     pub const BASIC: Self = Self {
         vendor_id: 0,
@@ -455,14 +534,43 @@ impl DataFormatDescriptorHeader {
             descriptor_block_size as usize,
         ))
     }
+
+    /// Serialize the header of a descriptor block of `descriptor_block_size` bytes, including the header
+    ///
+    /// # Panics
+    ///
+    /// Panics if a field doesn't fit its bitfield: 17 bits for `vendor_id`, 15 for `descriptor_type` and 16 for
+    /// `version_number` and `descriptor_block_size`.
+    pub fn as_bytes(&self, descriptor_block_size: u32) -> [u8; Self::LENGTH] {
+        assert!(
+            self.vendor_id < 1 << 17
+                && self.descriptor_type < 1 << 15
+                && self.version_number <= 0xffff
+                && descriptor_block_size <= 0xffff,
+            "data format descriptor header field out of range"
+        );
+        let mut bytes = [0; Self::LENGTH];
+        let v = self.vendor_id | (self.descriptor_type << 17);
+        bytes[0..4].copy_from_slice(&v.to_le_bytes());
+        let v = self.version_number | (descriptor_block_size << 16);
+        bytes[4..8].copy_from_slice(&v.to_le_bytes());
+        bytes
+    }
+
+    /// Write the serialized header, as with [`as_bytes`](Self::as_bytes)
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, descriptor_block_size: u32, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.as_bytes(descriptor_block_size))
+    }
 }
 
+#[derive(Copy, Clone)]
 pub struct DataFormatDescriptor<'data> {
     pub header: DataFormatDescriptorHeader,
     pub data: &'data [u8],
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct BasicDataFormatDescriptorHeader {
     /// None means Unspecified
     pub color_model: Option<ColorModel>, //: 8;
@@ -517,6 +625,43 @@ impl BasicDataFormatDescriptorHeader {
             bytes_planes,
         })
     }
+
+    /// Serialize the header, storing the texel block dimensions minus one
+    ///
+    /// # Panics
+    ///
+    /// Panics if a texel block dimension isn't in `1..=256` or a plane has more than 255 bytes.
+    pub fn as_bytes(&self) -> [u8; Self::LENGTH] {
+        assert!(
+            self.texel_block_dimensions
+                .iter()
+                .all(|dimension| (1..=256).contains(dimension))
+                && self.bytes_planes.iter().all(|&bytes| bytes <= 0xff),
+            "basic data format descriptor header field out of range"
+        );
+        let mut bytes = [0; Self::LENGTH];
+        let v = self.color_model.map_or(0, |model| model.0.get())
+            | (self.color_primaries.map_or(0, |primaries| primaries.0.get()) << 8)
+            | (self.transfer_function.map_or(0, |transfer| transfer.0.get()) << 16)
+            | (self.flags.bits() << 24);
+        bytes[0..4].copy_from_slice(&v.to_le_bytes());
+
+        let [x, y, z, w] = self.texel_block_dimensions;
+        let v = (x - 1) | ((y - 1) << 8) | ((z - 1) << 16) | ((w - 1) << 24);
+        bytes[4..8].copy_from_slice(&v.to_le_bytes());
+
+        for (index, planes) in self.bytes_planes.chunks_exact(4).enumerate() {
+            let v = planes[0] | (planes[1] << 8) | (planes[2] << 16) | (planes[3] << 24);
+            bytes[8 + index * 4..12 + index * 4].copy_from_slice(&v.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Write the serialized header, as with [`as_bytes`](Self::as_bytes)
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.as_bytes())
+    }
 }
 
 pub struct BasicDataFormatDescriptor<'data> {
@@ -539,6 +684,13 @@ impl<'data> BasicDataFormatDescriptor<'data> {
             data: self.sample_information,
         }
     }
+
+    /// Channel of each sample, interpreted according to the color model
+    pub fn channels(&self) -> impl Iterator<Item = Channel> + 'data {
+        let color_model = self.header.color_model;
+        self.sample_information()
+            .map(move |sample| Channel::new(color_model, sample.channel_type))
+    }
 }
 
 struct SampleInformationIterator<'data> {
@@ -559,7 +711,7 @@ impl<'data> Iterator for SampleInformationIterator<'data> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct SampleInformation {
     pub bit_offset: u32,                                //: 16;
     pub bit_length: u32,                                //: 8;
@@ -571,9 +723,14 @@ pub struct SampleInformation {
 }
 
 impl SampleInformation {
-    const LENGTH: usize = 16;
+    pub const LENGTH: usize = 16;
+
+    /// Interpret `channel_type` according to the color model of the sample's descriptor
+    pub fn channel(&self, color_model: Option<ColorModel>) -> Channel {
+        Channel::new(color_model, self.channel_type)
+    }
 
-    fn parse(bytes: &[u8]) -> Result<Self, ParseError> {
+    pub fn parse(bytes: &[u8]) -> Result<Self, ParseError> {
         let mut offset = 0;
 
         let v = bytes_to_u32(bytes, &mut offset)?;
@@ -602,6 +759,105 @@ impl SampleInformation {
             upper,
         })
     }
+
+    /// Serialize the sample, storing the bit length minus one
+    ///
+    /// # Panics
+    ///
+    /// Panics if a field doesn't fit its bitfield: `bit_offset` must be at most 0xffff, `bit_length` in `1..=256`,
+    /// `channel_type` at most 15 and each sample position at most 255.
+    pub fn as_bytes(&self) -> [u8; Self::LENGTH] {
+        assert!(
+            self.bit_offset <= 0xffff
+                && (1..=256).contains(&self.bit_length)
+                && self.channel_type <= 0xf
+                && self.sample_positions.iter().all(|&position| position <= 0xff),
+            "sample information field out of range"
+        );
+        let mut bytes = [0; Self::LENGTH];
+        let v = self.bit_offset
+            | ((self.bit_length - 1) << 16)
+            | (self.channel_type << 24)
+            | (self.channel_type_qualifiers.bits() << 28);
+        bytes[0..4].copy_from_slice(&v.to_le_bytes());
+
+        let [a, b, c, d] = self.sample_positions;
+        let v = a | (b << 8) | (c << 16) | (d << 24);
+        bytes[4..8].copy_from_slice(&v.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.lower.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.upper.to_le_bytes());
+        bytes
+    }
+
+    /// Write the serialized sample, as with [`as_bytes`](Self::as_bytes)
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.as_bytes())
+    }
+}
+
+/// Owned basic data format descriptor, for writing custom descriptors or modified copies of parsed ones
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BasicDataFormatDescriptorBuilder {
+    pub header: BasicDataFormatDescriptorHeader,
+    pub samples: std::vec::Vec<SampleInformation>,
+}
+
+#[cfg(feature = "std")]
+impl BasicDataFormatDescriptorBuilder {
+    pub fn new(header: BasicDataFormatDescriptorHeader) -> Self {
+        Self {
+            header,
+            samples: std::vec::Vec::new(),
+        }
+    }
+
+    /// Append a sample, in order of increasing bit offset
+    pub fn sample(mut self, sample: SampleInformation) -> Self {
+        self.samples.push(sample);
+        self
+    }
+
+    /// Serialize the descriptor block including its block header, for use in
+    /// [`Writer::data_format_descriptors`]
+    pub fn as_bytes(&self) -> std::vec::Vec<u8> {
+        basic_data_format_descriptor_block(&self.header, &self.samples)
+    }
+
+    /// Write the serialized descriptor block, as with [`as_bytes`](Self::as_bytes)
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.as_bytes())
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<&BasicDataFormatDescriptor<'_>> for BasicDataFormatDescriptorBuilder {
+    fn from(descriptor: &BasicDataFormatDescriptor<'_>) -> Self {
+        Self {
+            header: descriptor.header,
+            samples: descriptor.sample_information().collect(),
+        }
+    }
+}
+
+/// Serialize a basic data format descriptor block, including its block header
+#[cfg(feature = "std")]
+fn basic_data_format_descriptor_block(
+    header: &BasicDataFormatDescriptorHeader,
+    samples: &[SampleInformation],
+) -> std::vec::Vec<u8> {
+    let descriptor_block_size = DataFormatDescriptorHeader::LENGTH
+        + BasicDataFormatDescriptorHeader::LENGTH
+        + samples.len() * SampleInformation::LENGTH;
+
+    let mut output = std::vec::Vec::with_capacity(descriptor_block_size);
+    output.extend_from_slice(&DataFormatDescriptorHeader::BASIC.as_bytes(descriptor_block_size as u32));
+    output.extend_from_slice(&header.as_bytes());
+    for sample in samples {
+        output.extend_from_slice(&sample.as_bytes());
+    }
+    output
 }
 
 fn bytes_to_u32(bytes: &[u8], offset: &mut usize) -> Result<u32, ParseError> {
@@ -645,3 +901,51 @@ fn test_malformed_key_value_data_handling() {
     assert_eq!(iterator.next(), Some(("abc", &b"987"[..])));
     assert_eq!(iterator.next(), None);
 }
+
+#[test]
+fn test_data_format_descriptor_round_trip() {
+    let file = include_bytes!("../data/test_tex.ktx2");
+    let reader = Reader::new(&file[..]).unwrap();
+    let index = reader.header().index;
+    let original =
+        &reader.data()[index.dfd_byte_offset as usize + 4..(index.dfd_byte_offset + index.dfd_byte_length) as usize];
+
+    let descriptor = BasicDataFormatDescriptor::parse(reader.data_format_descriptors().next().unwrap().data).unwrap();
+    let builder = BasicDataFormatDescriptorBuilder::from(&descriptor);
+    assert_eq!(builder.as_bytes(), original);
+
+    let custom = BasicDataFormatDescriptorBuilder::new(builder.header).sample(SampleInformation {
+        bit_length: 32,
+        ..builder.samples[0]
+    });
+    let bytes = custom.as_bytes();
+    let parsed = BasicDataFormatDescriptor::parse(&bytes[DataFormatDescriptorHeader::LENGTH..]).unwrap();
+    assert_eq!(
+        parsed.sample_information().collect::<std::vec::Vec<_>>(),
+        custom.samples
+    );
+    assert_eq!(parsed.header, custom.header);
+
+    let mut written = std::vec::Vec::new();
+    custom.write_to(&mut written).unwrap();
+    assert_eq!(written, bytes);
+    let mut written = std::vec::Vec::new();
+    custom.samples[0].write_to(&mut written).unwrap();
+    assert_eq!(written, bytes[bytes.len() - SampleInformation::LENGTH..]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+#[should_panic(expected = "sample information field out of range")]
+fn test_sample_information_out_of_range() {
+    let sample = SampleInformation {
+        bit_offset: 0,
+        bit_length: 0,
+        channel_type: 0,
+        channel_type_qualifiers: ChannelTypeQualifiers::empty(),
+        sample_positions: [0; 4],
+        lower: 0,
+        upper: 0,
+    };
+    sample.as_bytes();
+}
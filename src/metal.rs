@@ -0,0 +1,149 @@
+//! Conversion between [`Format`] and `MTLPixelFormat` values, as used by Metal and the `KTXmetalPixelFormat` key.
+
+use crate::Format;
+use core::convert::TryInto;
+
+/// Key of the metadata entry containing the `MTLPixelFormat` of textures whose format is not a Vulkan format
+pub const KEY: &str = "KTXmetalPixelFormat";
+
+// BC1 formats without alpha come after their RGBA counterparts, so the reverse mapping prefers RGBA.
+const FORMATS: &[(Format, u32)] = &[
+    (Format::R8_UNORM, 10),
+    (Format::R8_SRGB, 11),
+    (Format::R8_SNORM, 12),
+    (Format::R8_UINT, 13),
+    (Format::R8_SINT, 14),
+    (Format::R16_UNORM, 20),
+    (Format::R16_SNORM, 22),
+    (Format::R16_UINT, 23),
+    (Format::R16_SINT, 24),
+    (Format::R16_SFLOAT, 25),
+    (Format::R8G8_UNORM, 30),
+    (Format::R8G8_SRGB, 31),
+    (Format::R8G8_SNORM, 32),
+    (Format::R8G8_UINT, 33),
+    (Format::R8G8_SINT, 34),
+    (Format::R5G6B5_UNORM_PACK16, 40),
+    (Format::R5G5B5A1_UNORM_PACK16, 41),
+    (Format::R4G4B4A4_UNORM_PACK16, 42),
+    (Format::A1R5G5B5_UNORM_PACK16, 43),
+    (Format::R32_UINT, 53),
+    (Format::R32_SINT, 54),
+    (Format::R32_SFLOAT, 55),
+    (Format::R16G16_UNORM, 60),
+    (Format::R16G16_SNORM, 62),
+    (Format::R16G16_UINT, 63),
+    (Format::R16G16_SINT, 64),
+    (Format::R16G16_SFLOAT, 65),
+    (Format::R8G8B8A8_UNORM, 70),
+    (Format::R8G8B8A8_SRGB, 71),
+    (Format::R8G8B8A8_SNORM, 72),
+    (Format::R8G8B8A8_UINT, 73),
+    (Format::R8G8B8A8_SINT, 74),
+    (Format::B8G8R8A8_UNORM, 80),
+    (Format::B8G8R8A8_SRGB, 81),
+    (Format::A2B10G10R10_UNORM_PACK32, 90),
+    (Format::A2B10G10R10_UINT_PACK32, 91),
+    (Format::B10G11R11_UFLOAT_PACK32, 92),
+    (Format::E5B9G9R9_UFLOAT_PACK32, 93),
+    (Format::A2R10G10B10_UNORM_PACK32, 94),
+    (Format::R32G32_UINT, 103),
+    (Format::R32G32_SINT, 104),
+    (Format::R32G32_SFLOAT, 105),
+    (Format::R16G16B16A16_UNORM, 110),
+    (Format::R16G16B16A16_SNORM, 112),
+    (Format::R16G16B16A16_UINT, 113),
+    (Format::R16G16B16A16_SINT, 114),
+    (Format::R16G16B16A16_SFLOAT, 115),
+    (Format::R32G32B32A32_UINT, 123),
+    (Format::R32G32B32A32_SINT, 124),
+    (Format::R32G32B32A32_SFLOAT, 125),
+    (Format::BC1_RGBA_UNORM_BLOCK, 130),
+    (Format::BC1_RGB_UNORM_BLOCK, 130),
+    (Format::BC1_RGBA_SRGB_BLOCK, 131),
+    (Format::BC1_RGB_SRGB_BLOCK, 131),
+    (Format::BC2_UNORM_BLOCK, 132),
+    (Format::BC2_SRGB_BLOCK, 133),
+    (Format::BC3_UNORM_BLOCK, 134),
+    (Format::BC3_SRGB_BLOCK, 135),
+    (Format::BC4_UNORM_BLOCK, 140),
+    (Format::BC4_SNORM_BLOCK, 141),
+    (Format::BC5_UNORM_BLOCK, 142),
+    (Format::BC5_SNORM_BLOCK, 143),
+    (Format::BC6H_SFLOAT_BLOCK, 150),
+    (Format::BC6H_UFLOAT_BLOCK, 151),
+    (Format::BC7_UNORM_BLOCK, 152),
+    (Format::BC7_SRGB_BLOCK, 153),
+    (Format::EAC_R11_UNORM_BLOCK, 170),
+    (Format::EAC_R11_SNORM_BLOCK, 172),
+    (Format::EAC_R11G11_UNORM_BLOCK, 174),
+    (Format::EAC_R11G11_SNORM_BLOCK, 176),
+    (Format::ETC2_R8G8B8A8_UNORM_BLOCK, 178),
+    (Format::ETC2_R8G8B8A8_SRGB_BLOCK, 179),
+    (Format::ETC2_R8G8B8_UNORM_BLOCK, 180),
+    (Format::ETC2_R8G8B8_SRGB_BLOCK, 181),
+    (Format::ETC2_R8G8B8A1_UNORM_BLOCK, 182),
+    (Format::ETC2_R8G8B8A1_SRGB_BLOCK, 183),
+    (Format::ASTC_4x4_SRGB_BLOCK, 186),
+    (Format::ASTC_5x4_SRGB_BLOCK, 187),
+    (Format::ASTC_5x5_SRGB_BLOCK, 188),
+    (Format::ASTC_6x5_SRGB_BLOCK, 189),
+    (Format::ASTC_6x6_SRGB_BLOCK, 190),
+    (Format::ASTC_8x5_SRGB_BLOCK, 192),
+    (Format::ASTC_8x6_SRGB_BLOCK, 193),
+    (Format::ASTC_8x8_SRGB_BLOCK, 194),
+    (Format::ASTC_10x5_SRGB_BLOCK, 195),
+    (Format::ASTC_10x6_SRGB_BLOCK, 196),
+    (Format::ASTC_10x8_SRGB_BLOCK, 197),
+    (Format::ASTC_10x10_SRGB_BLOCK, 198),
+    (Format::ASTC_12x10_SRGB_BLOCK, 199),
+    (Format::ASTC_12x12_SRGB_BLOCK, 200),
+    (Format::ASTC_4x4_UNORM_BLOCK, 204),
+    (Format::ASTC_5x4_UNORM_BLOCK, 205),
+    (Format::ASTC_5x5_UNORM_BLOCK, 206),
+    (Format::ASTC_6x5_UNORM_BLOCK, 207),
+    (Format::ASTC_6x6_UNORM_BLOCK, 208),
+    (Format::ASTC_8x5_UNORM_BLOCK, 210),
+    (Format::ASTC_8x6_UNORM_BLOCK, 211),
+    (Format::ASTC_8x8_UNORM_BLOCK, 212),
+    (Format::ASTC_10x5_UNORM_BLOCK, 213),
+    (Format::ASTC_10x6_UNORM_BLOCK, 214),
+    (Format::ASTC_10x8_UNORM_BLOCK, 215),
+    (Format::ASTC_10x10_UNORM_BLOCK, 216),
+    (Format::ASTC_12x10_UNORM_BLOCK, 217),
+    (Format::ASTC_12x12_UNORM_BLOCK, 218),
+    (Format::D16_UNORM, 250),
+    (Format::D32_SFLOAT, 252),
+    (Format::S8_UINT, 253),
+    (Format::D24_UNORM_S8_UINT, 255),
+    (Format::D32_SFLOAT_S8_UINT, 260),
+];
+
+/// The `MTLPixelFormat` equivalent to `format`, if there is one
+pub fn from_format(format: Format) -> Option<u32> {
+    FORMATS
+        .iter()
+        .find(|&&(candidate, _)| candidate == format)
+        .map(|&(_, pixel_format)| pixel_format)
+}
+
+/// The format equivalent to a `MTLPixelFormat`, if there is one
+pub fn to_format(pixel_format: u32) -> Option<Format> {
+    FORMATS
+        .iter()
+        .find(|&&(_, candidate)| candidate == pixel_format)
+        .map(|&(format, _)| format)
+}
+
+/// Parse the value of a [`KEY`] metadata entry
+pub fn parse_value(value: &[u8]) -> Option<u32> {
+    Some(u32::from_le_bytes(value.get(..4)?.try_into().unwrap()))
+}
+
+#[test]
+fn test_metal_round_trip() {
+    for &(format, pixel_format) in FORMATS {
+        assert_eq!(from_format(format), Some(pixel_format));
+        assert_eq!(from_format(to_format(pixel_format).unwrap()), Some(pixel_format));
+    }
+}
@@ -0,0 +1,383 @@
+use crate::{format::ALPHA, texel, DataFormatFlags, Format, Header, Index, TextureError, Writer, WriterLevel};
+use std::{collections::BTreeMap, vec::Vec};
+
+/// Filter used to downsample mip levels
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MipmapFilter {
+    Box,
+    Triangle,
+    /// Kaiser windowed sinc with a width of 3 and alpha of 4
+    Kaiser,
+    Lanczos3,
+}
+
+impl MipmapFilter {
+    fn support(self) -> f32 {
+        match self {
+            MipmapFilter::Box => 0.5,
+            MipmapFilter::Triangle => 1.0,
+            MipmapFilter::Kaiser | MipmapFilter::Lanczos3 => 3.0,
+        }
+    }
+
+    fn weight(self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            MipmapFilter::Box => (x <= 0.5) as u32 as f32,
+            MipmapFilter::Triangle => (1.0 - x).max(0.0),
+            MipmapFilter::Kaiser if x < 3.0 => {
+                const ALPHA: f32 = 4.0;
+                let t = x / 3.0;
+                sinc(x) * bessel_i0(ALPHA * (1.0 - t * t).sqrt()) / bessel_i0(ALPHA)
+            }
+            MipmapFilter::Lanczos3 if x < 3.0 => sinc(x) * sinc(x / 3.0),
+            _ => 0.0,
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let x = x * core::f32::consts::PI;
+        x.sin() / x
+    }
+}
+
+/// Zeroth order modified Bessel function of the first kind
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+    while term > sum * 1e-8 {
+        term *= (x / (2.0 * k)) * (x / (2.0 * k));
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+/// Options for mip chain generation
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MipmapOptions {
+    pub filter: MipmapFilter,
+    /// Number of levels including the base level, or `None` for the full mip chain. Counts beyond the full mip chain
+    /// are clamped to it.
+    pub level_count: Option<u32>,
+    /// Whether the color channels are premultiplied by alpha. Straight alpha is premultiplied while filtering, so
+    /// transparent texels don't bleed into their neighbours.
+    pub alpha_premultiplied: bool,
+    /// Renormalize RGB as tangent space normal vectors after filtering
+    pub normal_map: bool,
+}
+
+impl Default for MipmapOptions {
+    fn default() -> Self {
+        Self {
+            filter: MipmapFilter::Box,
+            level_count: None,
+            alpha_premultiplied: false,
+            normal_map: false,
+        }
+    }
+}
+
+/// Number of levels in the full mip chain of a texture of the given size
+pub fn full_mip_chain_length(pixel_width: u32, pixel_height: u32, pixel_depth: u32) -> u32 {
+    32 - pixel_width.max(pixel_height).max(pixel_depth).max(1).leading_zeros()
+}
+
+/// Generate the mip chain of an uncompressed texture from its base level
+///
+/// `extent` is the width, height and depth of the base level, with 0 for unused dimensions as in [`Header`]. Filtering
+/// is performed in linear space, the returned levels start with the unmodified base level.
+pub fn generate_mipmaps(
+    format: Format,
+    extent: [u32; 3],
+    base_level: &[u8],
+    options: &MipmapOptions,
+) -> Result<Vec<Vec<u8>>, TextureError> {
+    let components = format.components().unwrap_or(&[]);
+    let has_alpha = components.iter().any(|component| component.channel == ALPHA);
+    let premultiply = has_alpha && !options.alpha_premultiplied;
+    let full_mip_chain_length = full_mip_chain_length(extent[0], extent[1], extent[2]);
+    let level_count = options
+        .level_count
+        .map_or(full_mip_chain_length, |count| count.clamp(1, full_mip_chain_length));
+
+    let mut dimensions = extent.map(|dimension| dimension.max(1) as usize);
+    let texel_count = dimensions.iter().product::<usize>();
+    let mut texels = texel::decode(format, base_level).ok_or(TextureError::UnsupportedFormat)?;
+    if texels.len() != texel_count || base_level.len() != texel_count * format.block_size().unwrap() as usize {
+        return Err(TextureError::InvalidLength);
+    }
+
+    for texel in &mut texels {
+        if format.is_srgb() {
+            for channel in &mut texel[..3] {
+                *channel = srgb_to_linear(*channel);
+            }
+        }
+        if premultiply {
+            for channel in 0..3 {
+                texel[channel] *= texel[3];
+            }
+        }
+    }
+
+    let mut levels = std::vec![base_level.to_vec()];
+    for _ in 1..level_count {
+        for axis in 0..3 {
+            let length = (dimensions[axis] / 2).max(1);
+            texels = resample(&texels, dimensions, axis, length, options.filter);
+            dimensions[axis] = length;
+        }
+
+        let mut level = texels.clone();
+        for texel in &mut level {
+            if premultiply {
+                let alpha = texel[3];
+                for channel in &mut texel[..3] {
+                    *channel = if alpha > 0.0 { *channel / alpha } else { 0.0 };
+                }
+            }
+            if options.normal_map {
+                normalize(texel, format);
+            }
+            if format.is_srgb() {
+                for channel in &mut texel[..3] {
+                    *channel = linear_to_srgb(*channel);
+                }
+            }
+        }
+        levels.push(texel::encode(format, &level).unwrap());
+    }
+
+    Ok(levels)
+}
+
+fn resample(
+    texels: &[[f32; 4]],
+    dimensions: [usize; 3],
+    axis: usize,
+    length: usize,
+    filter: MipmapFilter,
+) -> Vec<[f32; 4]> {
+    let source_length = dimensions[axis];
+    if source_length == length {
+        return texels.to_vec();
+    }
+
+    // Weights of the source texels contributing to each destination texel
+    let scale = source_length as f32 / length as f32;
+    let radius = filter.support() * scale;
+    let contributions = (0..length)
+        .map(|index| {
+            let center = (index as f32 + 0.5) * scale;
+            let start = (center - radius).floor() as isize;
+            let end = (center + radius).ceil() as isize;
+            let mut weights = (start..=end)
+                .map(|source| {
+                    let weight = filter.weight((source as f32 + 0.5 - center) / scale);
+                    (source.max(0).min(source_length as isize - 1) as usize, weight)
+                })
+                .filter(|&(_, weight)| weight != 0.0)
+                .collect::<Vec<_>>();
+            let total = weights.iter().map(|&(_, weight)| weight).sum::<f32>();
+            for (_, weight) in &mut weights {
+                *weight /= total;
+            }
+            weights
+        })
+        .collect::<Vec<_>>();
+
+    let mut new_dimensions = dimensions;
+    new_dimensions[axis] = length;
+    let strides = [1, dimensions[0], dimensions[0] * dimensions[1]];
+    let mut output = std::vec![[0.0; 4]; new_dimensions.iter().product()];
+    let mut index = 0;
+    for z in 0..new_dimensions[2] {
+        for y in 0..new_dimensions[1] {
+            for x in 0..new_dimensions[0] {
+                let mut position = [x, y, z];
+                let output_texel = &mut output[index];
+                for &(source, weight) in &contributions[position[axis]] {
+                    position[axis] = source;
+                    let source_index = position[0] * strides[0] + position[1] * strides[1] + position[2] * strides[2];
+                    for (output, input) in output_texel.iter_mut().zip(&texels[source_index]) {
+                        *output += input * weight;
+                    }
+                }
+                index += 1;
+            }
+        }
+    }
+    output
+}
+
+fn normalize(texel: &mut [f32; 4], format: Format) {
+    let unsigned = format
+        .components()
+        .is_some_and(|components| components[0].numeric != crate::format::Numeric::SNorm);
+    let mut vector = [texel[0], texel[1], texel[2]];
+    if unsigned {
+        vector = vector.map(|value| value * 2.0 - 1.0);
+    }
+    let length = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if length > 0.0 {
+        vector = vector.map(|value| value / length);
+    }
+    if unsigned {
+        vector = vector.map(|value| value * 0.5 + 0.5);
+    }
+    texel[..3].copy_from_slice(&vector);
+}
+
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub(crate) fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+impl Writer {
+    /// Create an uncompressed texture from its base level, generating the remaining levels of its mip chain
+    ///
+    /// `extent` is the width, height and depth of the base level, with 0 for unused dimensions as in [`Header`].
+    pub fn with_mipmaps(
+        format: Format,
+        extent: [u32; 3],
+        base_level: &[u8],
+        options: &MipmapOptions,
+    ) -> Result<Self, TextureError> {
+        let levels = generate_mipmaps(format, extent, base_level, options)?;
+        let (mut dfd_header, samples) = format
+            .basic_data_format_descriptor()
+            .ok_or(TextureError::UnsupportedFormat)?;
+        if options.alpha_premultiplied {
+            dfd_header.flags |= DataFormatFlags::ALPHA_PREMULTIPLIED;
+        }
+
+        Ok(Self {
+            header: Header {
+                format: Some(format),
+                type_size: format.type_size(),
+                pixel_width: extent[0],
+                pixel_height: extent[1],
+                pixel_depth: extent[2],
+                layer_count: 0,
+                face_count: 1,
+                level_count: levels.len() as u32,
+                supercompression_scheme: None,
+                index: Index::default(),
+            },
+            data_format_descriptors: crate::basic_data_format_descriptor_block(&dfd_header, &samples),
+            key_value_data: BTreeMap::new(),
+            supercompression_global_data: Vec::new(),
+            levels: levels.into_iter().map(WriterLevel::new).collect(),
+        })
+    }
+}
+
+#[test]
+fn test_generate_mipmaps() {
+    let options = MipmapOptions::default();
+    assert_eq!(full_mip_chain_length(5, 3, 0), 3);
+
+    // Odd sizes round down
+    let base = std::vec![255; 5 * 3 * 4];
+    let levels = generate_mipmaps(Format::R8G8B8A8_SRGB, [5, 3, 0], &base, &options).unwrap();
+    assert_eq!(levels.iter().map(Vec::len).collect::<Vec<_>>(), [5 * 3 * 4, 2 * 4, 4]);
+    assert!(levels[2].iter().all(|&value| value == 255));
+
+    // Filtering happens in linear space
+    let base = [0, 0, 0, 255, 255, 255, 255, 255];
+    let levels = generate_mipmaps(Format::R8G8B8A8_SRGB, [2, 1, 0], &base, &options).unwrap();
+    assert_eq!(levels[1], [188, 188, 188, 255]);
+
+    // Transparent texels don't contribute color
+    let base = [255, 0, 0, 0, 0, 255, 0, 255];
+    let levels = generate_mipmaps(Format::R8G8B8A8_UNORM, [2, 1, 0], &base, &options).unwrap();
+    assert_eq!(levels[1], [0, 255, 0, 128]);
+
+    let writer = Writer::with_mipmaps(Format::R8G8B8A8_UNORM, [2, 1, 0], &base, &options).unwrap();
+    let file = writer.write();
+    let reader = crate::Reader::new(&file[..]).unwrap();
+    assert_eq!(reader.header().level_count, 2);
+    assert_eq!(reader.levels().nth(1).unwrap().data, [0, 255, 0, 128]);
+}
+
+#[test]
+fn test_mipmap_filters() {
+    let row = |filter, values: &[f32]| {
+        let base = texel::encode(
+            Format::R32_SFLOAT,
+            &values.iter().map(|&value| [value, 0.0, 0.0, 1.0]).collect::<Vec<_>>(),
+        )
+        .unwrap();
+        let options = MipmapOptions {
+            filter,
+            level_count: Some(2),
+            ..Default::default()
+        };
+        let levels = generate_mipmaps(Format::R32_SFLOAT, [values.len() as u32, 0, 0], &base, &options).unwrap();
+        texel::decode(Format::R32_SFLOAT, &levels[1])
+            .unwrap()
+            .iter()
+            .map(|texel| texel[0])
+            .collect::<Vec<_>>()
+    };
+    let step = [0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0];
+
+    assert_eq!(row(MipmapFilter::Box, &step), [0.0, 0.0, 1.0, 1.0]);
+    assert_eq!(row(MipmapFilter::Triangle, &[0.0, 0.0, 1.0, 1.0]), [0.125, 0.875]);
+    for filter in [MipmapFilter::Triangle, MipmapFilter::Kaiser, MipmapFilter::Lanczos3] {
+        // Weights are normalized and symmetric, and blur the edge across the middle texels
+        assert!(row(filter, &[0.25; 8]).iter().all(|&value| (value - 0.25).abs() < 1e-6));
+        let level = row(filter, &step);
+        assert!((level[1] + level[2] - 1.0).abs() < 1e-6 && (level[0] + level[3] - 1.0).abs() < 1e-6);
+        assert!(level[1] > 0.0 && level[1] < 0.5, "{:?} {:?}", filter, level);
+    }
+    // Windowed sinc filters have negative lobes, which sharpen the edge compared to the triangle filter
+    let triangle = row(MipmapFilter::Triangle, &step);
+    for filter in [MipmapFilter::Kaiser, MipmapFilter::Lanczos3] {
+        let level = row(filter, &step);
+        assert!(level[1] < triangle[1], "{:?} {:?}", filter, level);
+        assert!(level[0] < 0.0, "{:?} {:?}", filter, level);
+    }
+
+    // Counts beyond the full mip chain are clamped
+    let options = MipmapOptions {
+        level_count: Some(40),
+        ..Default::default()
+    };
+    let levels = generate_mipmaps(Format::R8_UNORM, [4, 4, 0], &[0; 16], &options).unwrap();
+    assert_eq!(levels.len(), 3);
+}
+
+#[test]
+fn test_normal_map_mipmaps() {
+    // +X and +Y averaged to the unit vector between them rather than a shorter one
+    let base = [255, 128, 128, 255, 128, 255, 128, 255];
+    let mut options = MipmapOptions::default();
+    let levels = generate_mipmaps(Format::R8G8B8A8_UNORM, [2, 1, 0], &base, &options).unwrap();
+    assert_eq!(levels[1], [191, 191, 128, 255]);
+    options.normal_map = true;
+    let levels = generate_mipmaps(Format::R8G8B8A8_UNORM, [2, 1, 0], &base, &options).unwrap();
+    assert_eq!(levels[1], [218, 218, 128, 255]);
+
+    // Signed normals don't need remapping
+    let base = [127, 0, 0, 127, 0, 127, 0, 127];
+    let levels = generate_mipmaps(Format::R8G8B8A8_SNORM, [2, 1, 0], &base, &options).unwrap();
+    assert_eq!(levels[1][..3], [90, 90, 0]);
+}
@@ -0,0 +1,140 @@
+use crate::{half::f16_to_f32, ChannelTypeQualifiers, SampleInformation};
+
+/// Lower and upper bounds of a sample, interpreted according to its qualifiers
+///
+/// Raw sample values at the bounds map to the minimum and maximum of the channel: 0.0 and 1.0 for unsigned samples,
+/// -1.0 and 1.0 for signed ones.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SampleBounds {
+    Float { lower: f32, upper: f32 },
+    Signed { lower: i32, upper: i32 },
+    Unsigned { lower: u32, upper: u32 },
+}
+
+impl SampleInformation {
+    /// Typed `lower` and `upper` bounds
+    ///
+    /// Float bounds are single precision regardless of the bit length of the sample. Signed bounds which only use
+    /// the sample's bits, such as `0x81` for -127 in an 8-bit sample, are sign-extended from the bit length.
+    pub fn bounds(&self) -> SampleBounds {
+        let qualifiers = self.channel_type_qualifiers;
+        if qualifiers.contains(ChannelTypeQualifiers::FLOAT) {
+            SampleBounds::Float {
+                lower: f32::from_bits(self.lower),
+                upper: f32::from_bits(self.upper),
+            }
+        } else if qualifiers.contains(ChannelTypeQualifiers::SIGNED) {
+            SampleBounds::Signed {
+                lower: self.sign_extend(u64::from(self.lower)) as i32,
+                upper: self.sign_extend(u64::from(self.upper)) as i32,
+            }
+        } else {
+            SampleBounds::Unsigned {
+                lower: self.lower,
+                upper: self.upper,
+            }
+        }
+    }
+
+    /// Convert a raw sample value, the `bit_length` bits at `bit_offset`, into the channel's range using the bounds
+    ///
+    /// Values outside of the bounds aren't clamped, so integer samples with bounds 0 to 1 keep their integer value.
+    /// Float samples of 16 bits are read as half floats and unsigned ones narrower than 16 bits, such as those of
+    /// `B10G11R11_UFLOAT_PACK32`, as half floats without the sign bit and the low mantissa bits. Samples with the
+    /// [`EXPONENT`](ChannelTypeQualifiers::EXPONENT) qualifier are returned unchanged.
+    pub fn normalize(&self, raw: u64) -> f32 {
+        let qualifiers = self.channel_type_qualifiers;
+        if qualifiers.contains(ChannelTypeQualifiers::EXPONENT) {
+            return raw as f32;
+        }
+
+        let signed = qualifiers.contains(ChannelTypeQualifiers::SIGNED);
+        let (value, lower, upper) = match self.bounds() {
+            SampleBounds::Float { lower, upper } => {
+                let value = match self.bit_length {
+                    64 => f64::from_bits(raw) as f32,
+                    32 => f32::from_bits(raw as u32),
+                    16 => f16_to_f32(raw as u16),
+                    bits if bits < 16 && !signed => f16_to_f32((raw << (15 - bits)) as u16),
+                    _ => return f32::NAN,
+                };
+                (f64::from(value), f64::from(lower), f64::from(upper))
+            }
+            SampleBounds::Signed { lower, upper } => (self.sign_extend(raw) as f64, f64::from(lower), f64::from(upper)),
+            SampleBounds::Unsigned { lower, upper } => (raw as f64, f64::from(lower), f64::from(upper)),
+        };
+
+        let unit = (value - lower) / (upper - lower);
+        if signed {
+            (unit * 2.0 - 1.0) as f32
+        } else {
+            unit as f32
+        }
+    }
+
+    /// Sign-extend a value of `bit_length` bits, leaving values with other high bits set as they are
+    fn sign_extend(&self, value: u64) -> i64 {
+        let bits = self.bit_length.min(64);
+        if bits == 64 || value >> bits != 0 {
+            // Already sign-extended to 32 bits
+            return if value >> 32 == 0 {
+                value as u32 as i32 as i64
+            } else {
+                value as i64
+            };
+        }
+        let shift = 64 - bits;
+        ((value << shift) as i64) >> shift
+    }
+}
+
+#[test]
+fn test_sample_bounds() {
+    let sample = |bit_length, channel_type_qualifiers, lower, upper| SampleInformation {
+        bit_offset: 0,
+        bit_length,
+        channel_type: 0,
+        channel_type_qualifiers,
+        sample_positions: [0; 4],
+        lower,
+        upper,
+    };
+
+    let unorm = sample(8, ChannelTypeQualifiers::empty(), 0, 255);
+    assert_eq!(unorm.bounds(), SampleBounds::Unsigned { lower: 0, upper: 255 });
+    assert_eq!(unorm.normalize(51), 0.2);
+
+    let snorm = sample(8, ChannelTypeQualifiers::SIGNED, -127i32 as u32, 127);
+    assert_eq!(
+        snorm.bounds(),
+        SampleBounds::Signed {
+            lower: -127,
+            upper: 127
+        }
+    );
+    assert_eq!(snorm.normalize(0x81), -1.0);
+    assert_eq!(snorm.normalize(127), 1.0);
+    let narrow_snorm = sample(8, ChannelTypeQualifiers::SIGNED, 0x81, 0x7f);
+    assert_eq!(narrow_snorm.bounds(), snorm.bounds());
+
+    let sint = sample(16, ChannelTypeQualifiers::SIGNED, -1i32 as u32, 1);
+    assert_eq!(sint.normalize(0xfffe), -2.0);
+
+    let half = sample(
+        16,
+        ChannelTypeQualifiers::FLOAT | ChannelTypeQualifiers::SIGNED,
+        (-1.0f32).to_bits(),
+        1.0f32.to_bits(),
+    );
+    assert_eq!(
+        half.bounds(),
+        SampleBounds::Float {
+            lower: -1.0,
+            upper: 1.0
+        }
+    );
+    assert_eq!(half.normalize(0xc400), -4.0);
+
+    let ufloat = sample(11, ChannelTypeQualifiers::FLOAT, 0, 1.0f32.to_bits());
+    assert_eq!(ufloat.normalize(0x3c0), 1.0);
+}
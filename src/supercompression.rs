@@ -0,0 +1,218 @@
+use crate::{
+    BasicDataFormatDescriptor, DataFormatDescriptorHeader, Format, Reader, SupercompressionScheme, TextureError,
+    Writer, WriterLevel,
+};
+use core::convert::TryFrom;
+use std::vec::Vec;
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// Re-encode the level data with another supercompression scheme, or none
+    ///
+    /// `level` is the compression level passed to the encoder: any level of `zstd::compression_level_range`, usually
+    /// 1 to 22, for Zstandard and 0 to 9 for ZLIB. It's ignored when removing supercompression, and other levels return
+    /// [`TextureError::InvalidCompressionLevel`]. Each scheme requires the feature of the same name, both for decoding
+    /// and encoding. BasisLZ isn't supported, as its level data can't be decoded without transcoding.
+    ///
+    /// `bytesPlane0` of the basic data format descriptor is set to 0 when supercompressing, as the specification
+    /// requires, and back to the texel block size when removing supercompression.
+    pub fn recompress(&self, scheme: Option<SupercompressionScheme>, level: i32) -> Result<Writer, TextureError> {
+        let mut writer = Writer::from_reader(self);
+        let current = writer.header.supercompression_scheme;
+        for level_data in &mut writer.levels {
+            let data = decompress(current, &level_data.data, level_data.uncompressed_byte_length)?;
+            *level_data = WriterLevel {
+                data: compress(scheme, &data, level)?,
+                uncompressed_byte_length: data.len() as u64,
+            };
+        }
+        writer.header.supercompression_scheme = scheme;
+        set_bytes_plane0(&mut writer);
+        Ok(writer)
+    }
+}
+
+fn set_bytes_plane0(writer: &mut Writer) {
+    let bytes_plane0 = match writer.header.supercompression_scheme {
+        Some(_) => 0,
+        None => match texel_block_size(writer) {
+            Some(size) => size,
+            None => return,
+        },
+    };
+    // bytesPlane0 follows the color model word and the texel block dimensions
+    if let Some(byte) = writer
+        .basic_data_format_descriptor_mut()
+        .and_then(|dfd| dfd.get_mut(DataFormatDescriptorHeader::LENGTH + 8))
+    {
+        *byte = bytes_plane0;
+    }
+}
+
+/// Size of a texel block, given by the format or, for formats without a `vkFormat`, by the extent of the samples
+fn texel_block_size(writer: &mut Writer) -> Option<u8> {
+    if let Some(size) = writer.header.format.and_then(Format::block_size) {
+        return u8::try_from(size).ok();
+    }
+    let dfd = writer.basic_data_format_descriptor_mut()?;
+    let dfd = BasicDataFormatDescriptor::parse(&dfd[DataFormatDescriptorHeader::LENGTH..]).ok()?;
+    let bits = dfd
+        .sample_information()
+        .map(|sample| sample.bit_offset + sample.bit_length)
+        .max()?;
+    u8::try_from(bits.div_ceil(8)).ok()
+}
+
+fn decompress(
+    scheme: Option<SupercompressionScheme>,
+    data: &[u8],
+    uncompressed_byte_length: u64,
+) -> Result<Vec<u8>, TextureError> {
+    let data = match scheme {
+        None => data.to_vec(),
+        #[cfg(feature = "zstd")]
+        Some(SupercompressionScheme::Zstandard) => read_limited(
+            zstd::stream::read::Decoder::new(data).map_err(|_| TextureError::DecompressionFailed)?,
+            uncompressed_byte_length,
+        )?,
+        #[cfg(feature = "zlib")]
+        Some(SupercompressionScheme::ZLIB) => {
+            read_limited(flate2::read::ZlibDecoder::new(data), uncompressed_byte_length)?
+        }
+        Some(_) => return Err(TextureError::UnsupportedSupercompression),
+    };
+    if data.len() as u64 != uncompressed_byte_length {
+        return Err(TextureError::InvalidLength);
+    }
+    Ok(data)
+}
+
+/// Decompress at most one byte more than expected, so that level data can't decompress without bound and the length
+/// from the level index isn't trusted for allocation
+fn read_limited(reader: impl std::io::Read, uncompressed_byte_length: u64) -> Result<Vec<u8>, TextureError> {
+    use std::io::Read;
+
+    let mut decompressed = Vec::new();
+    reader
+        .take(uncompressed_byte_length.saturating_add(1))
+        .read_to_end(&mut decompressed)
+        .map_err(|_| TextureError::DecompressionFailed)?;
+    Ok(decompressed)
+}
+
+pub(crate) fn compress(
+    scheme: Option<SupercompressionScheme>,
+    data: &[u8],
+    level: i32,
+) -> Result<Vec<u8>, TextureError> {
+    match scheme {
+        None => Ok(data.to_vec()),
+        #[cfg(feature = "zstd")]
+        Some(SupercompressionScheme::Zstandard) => {
+            if !zstd::compression_level_range().contains(&level) {
+                return Err(TextureError::InvalidCompressionLevel);
+            }
+            Ok(zstd::bulk::compress(data, level).expect("compressing to memory can't fail"))
+        }
+        #[cfg(feature = "zlib")]
+        Some(SupercompressionScheme::ZLIB) => {
+            use std::io::Write;
+
+            if !(0..=9).contains(&level) {
+                return Err(TextureError::InvalidCompressionLevel);
+            }
+            let compression = flate2::Compression::new(level as u32);
+            let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), compression);
+            encoder.write_all(data).expect("compressing to memory can't fail");
+            Ok(encoder.finish().expect("compressing to memory can't fail"))
+        }
+        Some(_) => Err(TextureError::UnsupportedSupercompression),
+    }
+}
+
+#[test]
+fn test_recompress() {
+    let file = include_bytes!("../data/test_tex.ktx2");
+    let reader = Reader::new(&file[..]).unwrap();
+    assert!(matches!(
+        reader.recompress(Some(SupercompressionScheme::BasisLZ), 0),
+        Err(TextureError::UnsupportedSupercompression)
+    ));
+
+    let schemes = [
+        #[cfg(feature = "zstd")]
+        SupercompressionScheme::Zstandard,
+        #[cfg(feature = "zlib")]
+        SupercompressionScheme::ZLIB,
+    ];
+    for scheme in schemes {
+        let compressed = reader.recompress(Some(scheme), 9).unwrap().write();
+        let compressed = Reader::new(&compressed[..]).unwrap();
+        assert_eq!(compressed.header().supercompression_scheme, Some(scheme));
+        assert!(compressed.levels().map(|level| level.data.len()).sum::<usize>() < file.len());
+
+        let decompressed = compressed.recompress(None, 0).unwrap().write();
+        assert_eq!(decompressed, &file[..]);
+
+        assert!(matches!(
+            reader.recompress(Some(scheme), 100),
+            Err(TextureError::InvalidCompressionLevel)
+        ));
+
+        // Level data which decompresses to more than the level index states
+        let mut writer = reader.recompress(Some(scheme), 9).unwrap();
+        writer.levels[0].uncompressed_byte_length -= 1;
+        let file = writer.write();
+        assert!(matches!(
+            Reader::new(&file[..]).unwrap().recompress(None, 0),
+            Err(TextureError::InvalidLength)
+        ));
+    }
+}
+
+#[cfg(any(feature = "zstd", feature = "zlib"))]
+#[test]
+fn test_recompress_bytes_planes() {
+    use crate::{Header, Index};
+
+    // 3 byte texel blocks, which align levels to 12 bytes rather than 4
+    let format = Format::R8G8B8_UNORM;
+    let writer = Writer {
+        header: Header {
+            format: Some(format),
+            type_size: 1,
+            pixel_width: 3,
+            pixel_height: 1,
+            pixel_depth: 0,
+            layer_count: 0,
+            face_count: 1,
+            level_count: 2,
+            supercompression_scheme: None,
+            index: Index::default(),
+        },
+        data_format_descriptors: format.data_format_descriptors().unwrap(),
+        key_value_data: Default::default(),
+        supercompression_global_data: Vec::new(),
+        levels: std::vec![
+            WriterLevel::new((0..9).collect()),
+            WriterLevel::new(std::vec![9, 10, 11])
+        ],
+    };
+    let file = writer.write();
+    let reader = Reader::new(&file[..]).unwrap();
+    let bytes_plane0 = |reader: &Reader<&[u8]>| {
+        let dfd = reader.data_format_descriptors().next().unwrap();
+        BasicDataFormatDescriptor::parse(dfd.data).unwrap().header.bytes_planes[0]
+    };
+    assert_eq!(bytes_plane0(&reader), 3);
+
+    #[cfg(feature = "zstd")]
+    let scheme = SupercompressionScheme::Zstandard;
+    #[cfg(not(feature = "zstd"))]
+    let scheme = SupercompressionScheme::ZLIB;
+    let compressed = reader.recompress(Some(scheme), 1).unwrap().write();
+    let compressed = Reader::new(&compressed[..]).unwrap();
+    assert_eq!(bytes_plane0(&compressed), 0);
+
+    let decompressed = compressed.recompress(None, 0).unwrap().write();
+    assert_eq!(decompressed, file);
+}
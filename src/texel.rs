@@ -0,0 +1,652 @@
+//! Conversion between uncompressed texel data and RGBA values.
+//!
+//! Values are kept in the numeric range of the format: normalized formats decode to `0.0..=1.0` or `-1.0..=1.0`,
+//! integer formats to their integer value, and sRGB formats are not linearized. Depth decodes to red and stencil to
+//! green. Missing color channels decode to 0 and missing alpha to 1. [`Reader::decode_level`] can also apply the
+//! texture's `KTXswizzle` and `KTXorientation` entries.
+
+use crate::{
+    format::{Component, Numeric, ALPHA, BLUE, DEPTH, GREEN, RED, STENCIL},
+    half::{f16_to_f32, f32_to_f16},
+    BasicDataFormatDescriptor, ChannelTypeQualifiers, ColorModel, Format, Reader, SampleBounds, SampleInformation,
+    TextureError,
+};
+use std::vec::Vec;
+
+/// Decode texel data of an uncompressed `format`
+///
+/// Returns `None` if the format is block-compressed or unknown. Trailing bytes that don't form a whole texel are
+/// ignored.
+pub fn decode(format: Format, data: &[u8]) -> Option<Vec<[f32; 4]>> {
+    let texel_size = format.block_size()? as usize;
+    if format == Format::E5B9G9R9_UFLOAT_PACK32 {
+        return Some(
+            data.chunks_exact(4)
+                .map(|texel| decode_e5b9g9r9(u32::from_le_bytes([texel[0], texel[1], texel[2], texel[3]])))
+                .collect(),
+        );
+    }
+
+    let components = format.components()?;
+    Some(
+        data.chunks_exact(texel_size)
+            .map(|texel| {
+                let mut rgba = [0.0, 0.0, 0.0, 1.0];
+                for component in components {
+                    let value = read_bits(texel, component.offset, component.bits);
+                    rgba[rgba_index(component.channel)] = decode_component(component, value);
+                }
+                rgba
+            })
+            .collect(),
+    )
+}
+
+/// Encode RGBA values into texel data of an uncompressed `format`
+///
+/// Values outside of the range of the format are clamped. Returns `None` if the format is block-compressed or
+/// unknown.
+pub fn encode(format: Format, texels: &[[f32; 4]]) -> Option<Vec<u8>> {
+    let texel_size = format.block_size()? as usize;
+    if format == Format::E5B9G9R9_UFLOAT_PACK32 {
+        return Some(
+            texels
+                .iter()
+                .flat_map(|&texel| encode_e5b9g9r9(texel).to_le_bytes())
+                .collect(),
+        );
+    }
+
+    let components = format.components()?;
+    let mut data = std::vec![0; texels.len() * texel_size];
+    for (texel, rgba) in data.chunks_exact_mut(texel_size).zip(texels) {
+        for component in components {
+            let value = encode_component(component, rgba[rgba_index(component.channel)]);
+            write_bits(texel, component, value);
+        }
+    }
+    Some(data)
+}
+
+/// Decode uncompressed texel data described only by a basic data format descriptor, without needing its `Format`
+///
+/// Samples are read according to their bit offsets, lengths, qualifiers and bounds as described by
+/// [`SampleInformation::normalize`], with the same channel mapping and value ranges as [`decode`]. Consecutive
+/// samples of the same channel hold successively more significant bits of a value. A sample with the
+/// [`EXPONENT`](ChannelTypeQualifiers::EXPONENT) qualifier scales the preceding mantissas of its channel, which are
+/// read as fractions of their bit length, by 2 to the power of the exponent minus its bias in `lower`, as in
+/// `E5B9G9R9_UFLOAT_PACK32`.
+///
+/// Multi-plane descriptors, such as those of planar Y′CbCr textures, aren't supported: each texel must take
+/// `bytes_planes[0]` bytes of a single plane. Returns [`TextureError::UnsupportedFormat`] for color models other than
+/// RGBSDA, texel blocks of more than one texel, descriptors with more than one plane or a zero `bytes_planes[0]`, as
+/// in supercompressed textures, samples of unknown channels, samples extending past the texel and channels whose
+/// samples add up to more than 64 bits, or 63 bits for mantissas with an exponent. Returns
+/// [`TextureError::InvalidLength`] if `data` isn't a whole number of texels.
+pub fn decode_with_descriptor(
+    descriptor: &BasicDataFormatDescriptor,
+    data: &[u8],
+) -> Result<Vec<[f32; 4]>, TextureError> {
+    let header = &descriptor.header;
+    if header.color_model != Some(ColorModel::RGBSDA)
+        || header.texel_block_dimensions != [1; 4]
+        || header.bytes_planes[1..].iter().any(|&bytes| bytes != 0)
+        || header.bytes_planes[0] == 0
+    {
+        return Err(TextureError::UnsupportedFormat);
+    }
+
+    // Group the samples of each channel value, with any exponent sample last
+    let mut values: Vec<(SampleInformation, Vec<SampleInformation>)> = Vec::new();
+    for sample in descriptor.sample_information() {
+        if !matches!(sample.channel_type, RED | GREEN | BLUE | STENCIL | DEPTH | ALPHA) {
+            return Err(TextureError::UnsupportedFormat);
+        }
+        match values.last_mut() {
+            Some((first, parts)) if first.channel_type == sample.channel_type => parts.push(sample),
+            _ => values.push((sample, std::vec![sample])),
+        }
+    }
+
+    let texel_bits = header.bytes_planes[0] * 8;
+    for (_, parts) in &values {
+        if parts
+            .iter()
+            .any(|part| part.bit_length == 0 || part.bit_offset + part.bit_length > texel_bits)
+        {
+            return Err(TextureError::UnsupportedFormat);
+        }
+        let (exponents, mantissas): (Vec<&SampleInformation>, Vec<_>) = parts
+            .iter()
+            .partition(|part| part.channel_type_qualifiers.contains(ChannelTypeQualifiers::EXPONENT));
+        let bits = mantissas.iter().map(|part| part.bit_length).sum::<u32>();
+        let valid = if exponents.is_empty() {
+            (1..=64).contains(&bits)
+        } else {
+            (1..64).contains(&bits) && exponents.iter().all(|exponent| exponent.bit_length < 64)
+        };
+        if !valid {
+            return Err(TextureError::UnsupportedFormat);
+        }
+    }
+
+    let texel_size = header.bytes_planes[0] as usize;
+    let texels = data.chunks_exact(texel_size);
+    if !texels.remainder().is_empty() {
+        return Err(TextureError::InvalidLength);
+    }
+    Ok(texels
+        .map(|texel| {
+            let mut rgba = [0.0, 0.0, 0.0, 1.0];
+            for (first, parts) in &values {
+                rgba[rgba_index(first.channel_type)] = decode_sample_value(texel, first, parts);
+            }
+            rgba
+        })
+        .collect())
+}
+
+fn decode_sample_value(texel: &[u8], first: &SampleInformation, parts: &[SampleInformation]) -> f32 {
+    let mut raw = 0;
+    let mut bits = 0;
+    let mut exponent = None;
+    for part in parts {
+        if part.channel_type_qualifiers.contains(ChannelTypeQualifiers::EXPONENT) {
+            exponent = Some((read_bits(texel, part.bit_offset, part.bit_length), part.lower));
+        } else {
+            raw |= read_bits(texel, part.bit_offset, part.bit_length) << bits;
+            bits += part.bit_length;
+        }
+    }
+
+    if let Some((exponent, bias)) = exponent {
+        let mantissa = raw as f64 / (1u64 << bits) as f64;
+        return (mantissa * 2.0f64.powi(exponent as i32 - bias as i32)) as f32;
+    }
+
+    let combined = SampleInformation {
+        bit_length: bits,
+        ..*first
+    };
+    let value = combined.normalize(raw);
+    match combined.bounds() {
+        // Signed normalized samples have symmetric bounds, unlike the -1 to 1 bounds of signed integers, and their
+        // most negative value lies below the lower bound
+        SampleBounds::Signed { lower, upper } if upper > 1 && lower == -upper => value.max(-1.0),
+        _ => value,
+    }
+}
+
+fn rgba_index(channel: u32) -> usize {
+    match channel {
+        RED | DEPTH => 0,
+        GREEN | STENCIL => 1,
+        BLUE => 2,
+        ALPHA => 3,
+        _ => unreachable!(),
+    }
+}
+
+fn read_bits(texel: &[u8], offset: u32, bits: u32) -> u64 {
+    let mut value = 0;
+    for bit in 0..bits {
+        let position = offset + bit;
+        let byte = texel[position as usize / 8];
+        value |= u64::from((byte >> (position % 8)) & 1) << bit;
+    }
+    value
+}
+
+fn write_bits(texel: &mut [u8], component: &Component, value: u64) {
+    for bit in 0..component.bits {
+        let position = component.offset + bit;
+        texel[position as usize / 8] |= (((value >> bit) & 1) as u8) << (position % 8);
+    }
+}
+
+fn sign_extend(value: u64, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value << shift) as i64) >> shift
+}
+
+fn decode_component(component: &Component, value: u64) -> f32 {
+    let bits = component.bits;
+    match component.numeric {
+        Numeric::UNorm | Numeric::SRgb => (value as f64 / max_unsigned(bits) as f64) as f32,
+        Numeric::SNorm => (sign_extend(value, bits) as f64 / max_signed(bits) as f64).max(-1.0) as f32,
+        Numeric::UInt => value as f32,
+        Numeric::SInt => sign_extend(value, bits) as f32,
+        Numeric::SFloat => match bits {
+            16 => f16_to_f32(value as u16),
+            32 => f32::from_bits(value as u32),
+            _ => f64::from_bits(value) as f32,
+        },
+        // Unsigned 11 and 10 bit floats share the exponent bias and layout of half floats
+        Numeric::UFloat => f16_to_f32((value << (15 - bits)) as u16),
+    }
+}
+
+fn encode_component(component: &Component, value: f32) -> u64 {
+    let bits = component.bits;
+    let mask = max_unsigned(bits);
+    match component.numeric {
+        Numeric::UNorm | Numeric::SRgb => (value.clamp(0.0, 1.0) as f64 * mask as f64).round() as u64,
+        Numeric::SNorm => ((value.clamp(-1.0, 1.0) as f64 * max_signed(bits) as f64).round() as i64) as u64 & mask,
+        Numeric::UInt => (value.round() as f64).max(0.0).min(mask as f64) as u64,
+        Numeric::SInt => {
+            let max = max_signed(bits) as f64;
+            ((value.round() as f64).max(-max - 1.0).min(max) as i64) as u64 & mask
+        }
+        Numeric::SFloat => match bits {
+            16 => u64::from(f32_to_f16(value)),
+            32 => u64::from(value.to_bits()),
+            _ => (value as f64).to_bits(),
+        },
+        Numeric::UFloat => {
+            let shift = 15 - bits;
+            // Round to nearest by adding half of the dropped mantissa bits
+            let half = u64::from(f32_to_f16(value.max(0.0)));
+            ((half + (1 << (shift - 1))) >> shift).min((31 << (bits - 5)) - 1)
+        }
+    }
+}
+
+fn max_unsigned(bits: u32) -> u64 {
+    if bits == 64 {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+fn max_signed(bits: u32) -> u64 {
+    (1 << (bits - 1)) - 1
+}
+
+const E5B9G9R9_MANTISSA_BITS: i32 = 9;
+const E5B9G9R9_EXPONENT_BIAS: i32 = 15;
+
+fn decode_e5b9g9r9(value: u32) -> [f32; 4] {
+    let exponent = (value >> 27) as i32;
+    let scale = 2.0f32.powi(exponent - E5B9G9R9_EXPONENT_BIAS - E5B9G9R9_MANTISSA_BITS);
+    [
+        (value & 0x1ff) as f32 * scale,
+        ((value >> 9) & 0x1ff) as f32 * scale,
+        ((value >> 18) & 0x1ff) as f32 * scale,
+        1.0,
+    ]
+}
+
+fn encode_e5b9g9r9(rgba: [f32; 4]) -> u32 {
+    const MAX: f32 = 65408.0;
+    let [r, g, b, _] = rgba.map(|value| if value > 0.0 { value.min(MAX) } else { 0.0 });
+    let max = r.max(g).max(b);
+
+    let mut exponent = (-E5B9G9R9_EXPONENT_BIAS - 1).max(max.log2().floor() as i32) + 1 + E5B9G9R9_EXPONENT_BIAS;
+    let scale = |exponent: i32| 2.0f32.powi(exponent - E5B9G9R9_EXPONENT_BIAS - E5B9G9R9_MANTISSA_BITS);
+    if (max / scale(exponent) + 0.5).floor() as u32 == 1 << E5B9G9R9_MANTISSA_BITS {
+        exponent += 1;
+    }
+
+    let quantize = |value: f32| ((value / scale(exponent) + 0.5).floor() as u32).min(0x1ff);
+    ((exponent as u32) << 27) | (quantize(b) << 18) | (quantize(g) << 9) | quantize(r)
+}
+
+/// Source of a channel in a `KTXswizzle` entry
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SwizzleComponent {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    Zero,
+    One,
+}
+
+/// Mapping of stored channels to RGBA, as given by the `KTXswizzle` metadata entry
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Swizzle(pub [SwizzleComponent; 4]);
+
+impl Swizzle {
+    pub const IDENTITY: Self = Self([
+        SwizzleComponent::Red,
+        SwizzleComponent::Green,
+        SwizzleComponent::Blue,
+        SwizzleComponent::Alpha,
+    ]);
+
+    /// Parse a value such as `rgb1`, with or without its terminating NUL
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        let value = value.strip_suffix(b"\0").unwrap_or(value);
+        if value.len() != 4 {
+            return None;
+        }
+        let mut components = [SwizzleComponent::Zero; 4];
+        for (component, &byte) in components.iter_mut().zip(value) {
+            *component = match byte {
+                b'r' => SwizzleComponent::Red,
+                b'g' => SwizzleComponent::Green,
+                b'b' => SwizzleComponent::Blue,
+                b'a' => SwizzleComponent::Alpha,
+                b'0' => SwizzleComponent::Zero,
+                b'1' => SwizzleComponent::One,
+                _ => return None,
+            };
+        }
+        Some(Self(components))
+    }
+
+    pub fn apply(&self, texels: &mut [[f32; 4]]) {
+        for texel in texels {
+            let source = *texel;
+            *texel = self.0.map(|component| match component {
+                SwizzleComponent::Red => source[0],
+                SwizzleComponent::Green => source[1],
+                SwizzleComponent::Blue => source[2],
+                SwizzleComponent::Alpha => source[3],
+                SwizzleComponent::Zero => 0.0,
+                SwizzleComponent::One => 1.0,
+            });
+        }
+    }
+}
+
+/// Direction of increasing texel coordinates, as given by the `KTXorientation` metadata entry
+///
+/// The canonical orientation, `rdi`, has the first texel at the left, top and front of the image.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Orientation {
+    /// X increases to the left
+    pub left: bool,
+    /// Y increases upwards
+    pub up: bool,
+    /// Z increases out of the screen
+    pub out: bool,
+}
+
+impl Orientation {
+    /// Parse a value such as `rd` or `ruo`, with or without its terminating NUL
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        let value = value.strip_suffix(b"\0").unwrap_or(value);
+        if value.is_empty() || value.len() > 3 {
+            return None;
+        }
+        let mut orientation = Self::default();
+        for (axis, &byte) in value.iter().enumerate() {
+            let flipped = match (axis, byte) {
+                (0, b'r') | (1, b'd') | (2, b'i') => false,
+                (0, b'l') | (1, b'u') | (2, b'o') => true,
+                _ => return None,
+            };
+            *[&mut orientation.left, &mut orientation.up, &mut orientation.out][axis] = flipped;
+        }
+        Some(orientation)
+    }
+
+    /// Flip consecutive images of `extent` texels into the canonical orientation
+    pub fn apply(&self, texels: &mut [[f32; 4]], extent: [u32; 3]) {
+        let [width, height, depth] = extent.map(|dimension| dimension.max(1) as usize);
+        for image in texels.chunks_exact_mut(width * height * depth) {
+            if self.out {
+                for z in 0..depth / 2 {
+                    let (front, back) = image.split_at_mut((depth - 1 - z) * width * height);
+                    front[z * width * height..(z + 1) * width * height].swap_with_slice(&mut back[..width * height]);
+                }
+            }
+            for slice in image.chunks_exact_mut(width * height) {
+                if self.up {
+                    for y in 0..height / 2 {
+                        let (top, bottom) = slice.split_at_mut((height - 1 - y) * width);
+                        top[y * width..(y + 1) * width].swap_with_slice(&mut bottom[..width]);
+                    }
+                }
+                if self.left {
+                    for row in slice.chunks_exact_mut(width) {
+                        row.reverse();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// What to apply to texels decoded by [`Reader::decode_level`]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct DecodeOptions {
+    /// Apply the `KTXswizzle` entry, so channels are in RGBA order
+    pub swizzle: bool,
+    /// Flip images according to the `KTXorientation` entry, so their first texel is at the left, top and front
+    pub orientation: bool,
+}
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// Swizzle given by the `KTXswizzle` entry, or `None` if it's missing or invalid
+    pub fn swizzle(&self) -> Option<Swizzle> {
+        let (_, value) = self.key_value_data().find(|&(key, _)| key == "KTXswizzle")?;
+        Swizzle::parse(value)
+    }
+
+    /// Orientation given by the `KTXorientation` entry, or `None` if it's missing or invalid
+    pub fn orientation(&self) -> Option<Orientation> {
+        let (_, value) = self.key_value_data().find(|&(key, _)| key == "KTXorientation")?;
+        Orientation::parse(value)
+    }
+
+    /// Decode all the images of an uncompressed mip level with [`decode`]
+    pub fn decode_level(&self, level: u32, options: DecodeOptions) -> Result<Vec<[f32; 4]>, TextureError> {
+        let header = self.header();
+        if header.supercompression_scheme.is_some() {
+            return Err(TextureError::UnsupportedSupercompression);
+        }
+        let format = header.format.ok_or(TextureError::UnsupportedFormat)?;
+        let data = self
+            .levels()
+            .nth(level as usize)
+            .ok_or(TextureError::InvalidLength)?
+            .data;
+        let mut texels = decode(format, data).ok_or(TextureError::UnsupportedFormat)?;
+
+        if options.swizzle {
+            if let Some(swizzle) = self.swizzle() {
+                swizzle.apply(&mut texels);
+            }
+        }
+        if options.orientation {
+            if let Some(orientation) = self.orientation() {
+                orientation.apply(&mut texels, header.level_extent(level));
+            }
+        }
+        Ok(texels)
+    }
+}
+
+#[test]
+fn test_texel_round_trip() {
+    let texels = [[0.0, 0.5, 1.0, 1.0], [0.25, 0.125, 0.75, 0.0]];
+    for &(format, channels) in &[
+        (Format::R8G8B8A8_UNORM, 4),
+        (Format::B8G8R8A8_SRGB, 4),
+        (Format::A2B10G10R10_UNORM_PACK32, 3),
+        (Format::R16G16B16A16_SFLOAT, 4),
+        (Format::R32G32B32A32_SFLOAT, 4),
+        (Format::B10G11R11_UFLOAT_PACK32, 3),
+        (Format::E5B9G9R9_UFLOAT_PACK32, 3),
+    ] {
+        let data = encode(format, &texels).unwrap();
+        assert_eq!(data.len(), 2 * format.block_size().unwrap() as usize);
+        for (decoded, texel) in decode(format, &data).unwrap().iter().zip(&texels) {
+            for channel in 0..channels {
+                assert!(
+                    (decoded[channel] - texel[channel]).abs() < 0.01,
+                    "{:?}: {:?} != {:?}",
+                    format,
+                    decoded,
+                    texel
+                );
+            }
+        }
+    }
+
+    assert_eq!(encode(Format::R8_SNORM, &[[-1.0, 0.0, 0.0, 0.0]]).unwrap(), [0x81]);
+    assert_eq!(decode(Format::R8_SNORM, &[0x80]).unwrap(), [[-1.0, 0.0, 0.0, 1.0]]);
+    assert_eq!(
+        decode(Format::R5G6B5_UNORM_PACK16, &[0x1f, 0x00]).unwrap(),
+        [[0.0, 0.0, 1.0, 1.0]]
+    );
+}
+
+#[test]
+fn test_decode_with_descriptor() {
+    let texels = [[0.0, 0.5, 1.0, 1.0], [0.25, -0.125, 0.75, 0.0], [3.0, 1.0, -1.0, 0.5]];
+    for format in (1..=184)
+        .filter_map(Format::new)
+        .filter(|format| !format.is_compressed())
+    {
+        let dfd = match format.data_format_descriptors() {
+            Some(dfd) => dfd,
+            None => continue,
+        };
+        let descriptor = BasicDataFormatDescriptor::parse(&dfd[8..]).unwrap();
+        let data = encode(format, &texels).unwrap();
+        assert_eq!(
+            decode_with_descriptor(&descriptor, &data).unwrap(),
+            decode(format, &data).unwrap(),
+            "{:?}",
+            format
+        );
+    }
+
+    // Malformed samples are rejected rather than read out of bounds
+    let header = crate::BasicDataFormatDescriptorHeader {
+        color_model: Some(ColorModel::RGBSDA),
+        color_primaries: None,
+        transfer_function: None,
+        flags: crate::DataFormatFlags::STRAIGHT_ALPHA,
+        texel_block_dimensions: [1; 4],
+        bytes_planes: [1, 0, 0, 0, 0, 0, 0, 0],
+    };
+    let sample = |bit_offset, bit_length, channel_type_qualifiers| SampleInformation {
+        bit_offset,
+        bit_length,
+        channel_type: RED,
+        channel_type_qualifiers,
+        sample_positions: [0; 4],
+        lower: 0,
+        upper: 255,
+    };
+    for samples in [
+        std::vec![sample(4, 8, ChannelTypeQualifiers::empty())],
+        std::vec![
+            sample(0, 4, ChannelTypeQualifiers::empty()),
+            sample(0, 64, ChannelTypeQualifiers::EXPONENT)
+        ],
+    ] {
+        let mut header = header;
+        header.bytes_planes[0] = if samples.len() == 1 { 1 } else { 16 };
+        let dfd = crate::BasicDataFormatDescriptorBuilder { header, samples }.as_bytes();
+        let descriptor = BasicDataFormatDescriptor::parse(&dfd[8..]).unwrap();
+        assert!(matches!(
+            decode_with_descriptor(&descriptor, &[0; 16]),
+            Err(TextureError::UnsupportedFormat)
+        ));
+    }
+
+    // Multi-plane descriptors and partial texels
+    let dfd = crate::BasicDataFormatDescriptorBuilder {
+        header: crate::BasicDataFormatDescriptorHeader {
+            bytes_planes: [1, 1, 0, 0, 0, 0, 0, 0],
+            ..header
+        },
+        samples: std::vec![sample(0, 8, ChannelTypeQualifiers::empty())],
+    }
+    .as_bytes();
+    let descriptor = BasicDataFormatDescriptor::parse(&dfd[8..]).unwrap();
+    assert!(matches!(
+        decode_with_descriptor(&descriptor, &[0; 2]),
+        Err(TextureError::UnsupportedFormat)
+    ));
+    let dfd = Format::R8G8_UNORM.data_format_descriptors().unwrap();
+    let descriptor = BasicDataFormatDescriptor::parse(&dfd[8..]).unwrap();
+    assert!(matches!(
+        decode_with_descriptor(&descriptor, &[0; 3]),
+        Err(TextureError::InvalidLength)
+    ));
+
+    // Only signed normalized samples are clamped to -1, whatever their bit length
+    let signed = |lower: i32, upper: i32| {
+        let dfd = crate::BasicDataFormatDescriptorBuilder {
+            header,
+            samples: std::vec![SampleInformation {
+                lower: lower as u32,
+                upper: upper as u32,
+                ..sample(0, 8, ChannelTypeQualifiers::SIGNED)
+            }],
+        }
+        .as_bytes();
+        let descriptor = BasicDataFormatDescriptor::parse(&dfd[8..]).unwrap();
+        decode_with_descriptor(&descriptor, &[0x80]).unwrap()[0][0]
+    };
+    assert_eq!(signed(-127, 127), -1.0);
+    assert_eq!(signed(-1, 1), -128.0);
+}
+
+#[test]
+fn test_swizzle_and_orientation() {
+    let mut writer = crate::Writer {
+        header: crate::Header {
+            format: Some(Format::R8G8_UNORM),
+            type_size: 1,
+            pixel_width: 2,
+            pixel_height: 2,
+            pixel_depth: 0,
+            layer_count: 0,
+            face_count: 1,
+            level_count: 1,
+            supercompression_scheme: None,
+            index: Default::default(),
+        },
+        data_format_descriptors: Format::R8G8_UNORM.data_format_descriptors().unwrap(),
+        key_value_data: Default::default(),
+        supercompression_global_data: Vec::new(),
+        levels: std::vec![crate::WriterLevel::new(std::vec![0, 255, 51, 255, 102, 255, 153, 0])],
+    };
+    writer.key_value_data.insert("KTXswizzle".into(), b"rrrg\0".to_vec());
+    writer.key_value_data.insert("KTXorientation".into(), b"lu\0".to_vec());
+    let file = writer.write();
+    let reader = Reader::new(&file).unwrap();
+    assert_eq!(
+        reader.orientation(),
+        Some(Orientation {
+            left: true,
+            up: true,
+            out: false
+        })
+    );
+
+    let raw = reader.decode_level(0, DecodeOptions::default()).unwrap();
+    assert_eq!(raw[0], [0.0, 1.0, 0.0, 1.0]);
+    let texels = reader
+        .decode_level(
+            0,
+            DecodeOptions {
+                swizzle: true,
+                orientation: true,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        texels,
+        [
+            [0.6, 0.6, 0.6, 0.0],
+            [0.4, 0.4, 0.4, 1.0],
+            [0.2, 0.2, 0.2, 1.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ]
+    );
+
+    let mut volume = [0.0, 1.0, 2.0].map(|z| [z, 0.0, 0.0, 1.0]);
+    Orientation::parse(b"rdo").unwrap().apply(&mut volume, [1, 1, 3]);
+    assert_eq!(volume.map(|texel| texel[0]), [2.0, 1.0, 0.0]);
+    assert_eq!(Swizzle::parse(b"rgb"), None);
+    assert_eq!(Orientation::parse(b"dr"), None);
+}
@@ -0,0 +1,117 @@
+use crate::{BasisLzGlobalData, Reader, SupercompressionScheme, TextureError, Writer};
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// Remove the `count` largest mip levels, making level `count` the new base level
+    ///
+    /// The dimensions and level index are updated accordingly. Level data is copied without decoding it, and the
+    /// image descriptors of the removed levels are dropped from BasisLZ global data.
+    pub fn drop_levels(&self, count: u32) -> Result<Writer, TextureError> {
+        let mut writer = Writer::from_reader(self);
+        let header = self.header();
+        if count as usize >= writer.levels.len() {
+            return Err(TextureError::InvalidLength);
+        }
+
+        if header.supercompression_scheme == Some(SupercompressionScheme::BasisLZ) {
+            let mut global_data = BasisLzGlobalData::parse(&writer.supercompression_global_data)
+                .map_err(|_| TextureError::InvalidLength)?;
+            // Image descriptors are ordered by level, then layer, face and z slice
+            let removed_images = (0..count)
+                .map(|level| {
+                    header.layer_count.max(1) as usize
+                        * header.face_count as usize
+                        * header.level_extent(level)[2] as usize
+                })
+                .sum::<usize>();
+            if removed_images > global_data.image_descs.len() {
+                return Err(TextureError::InvalidLength);
+            }
+            global_data.image_descs.drain(..removed_images);
+            writer.supercompression_global_data = global_data.as_bytes();
+        }
+
+        writer.levels.drain(..count as usize);
+        let header = &mut writer.header;
+        for dimension in [
+            &mut header.pixel_width,
+            &mut header.pixel_height,
+            &mut header.pixel_depth,
+        ] {
+            if *dimension != 0 {
+                *dimension = dimension.checked_shr(count).unwrap_or(0).max(1);
+            }
+        }
+        header.level_count = header.level_count.saturating_sub(count);
+        Ok(writer)
+    }
+}
+
+#[test]
+fn test_drop_levels() {
+    use crate::{Format, ImageDesc};
+
+    let writer = Writer {
+        header: crate::Header {
+            format: Some(Format::R8_UNORM),
+            type_size: 1,
+            pixel_width: 8,
+            pixel_height: 8,
+            pixel_depth: 0,
+            layer_count: 0,
+            face_count: 1,
+            level_count: 4,
+            supercompression_scheme: None,
+            index: Default::default(),
+        },
+        data_format_descriptors: Format::R8_UNORM.data_format_descriptors().unwrap(),
+        key_value_data: Default::default(),
+        supercompression_global_data: std::vec::Vec::new(),
+        levels: [64, 16, 4, 1]
+            .map(|length| crate::WriterLevel::new((0..length).collect()))
+            .to_vec(),
+    };
+    let file = writer.write();
+    let reader = Reader::new(&file[..]).unwrap();
+
+    let truncated = reader.drop_levels(2).unwrap();
+    assert_eq!(
+        (
+            truncated.header.pixel_width,
+            truncated.header.pixel_height,
+            truncated.header.pixel_depth
+        ),
+        (2, 2, 0)
+    );
+    assert_eq!(truncated.header.level_count, 2);
+    assert_eq!(truncated.levels[0], writer.levels[2]);
+    assert!(reader.drop_levels(4).is_err());
+
+    // More levels than a dimension has bits
+    let mut long = writer.clone();
+    long.header.level_count = 40;
+    long.levels.resize(40, crate::WriterLevel::new(std::vec![0]));
+    let file = long.write();
+    let truncated = Reader::new(&file[..]).unwrap().drop_levels(35).unwrap();
+    assert_eq!((truncated.header.pixel_width, truncated.header.pixel_height), (1, 1));
+
+    // Two layers of two levels lose the two image descriptors of the base level
+    let mut basis = writer;
+    basis.header.format = None;
+    basis.header.layer_count = 2;
+    basis.header.level_count = 2;
+    basis.levels.truncate(2);
+    basis.header.supercompression_scheme = Some(SupercompressionScheme::BasisLZ);
+    let image_desc = |rgb_slice_byte_length| ImageDesc {
+        rgb_slice_byte_length,
+        ..Default::default()
+    };
+    basis.supercompression_global_data = BasisLzGlobalData {
+        image_descs: std::vec![image_desc(1), image_desc(2), image_desc(3), image_desc(4)],
+        ..Default::default()
+    }
+    .as_bytes();
+    let file = basis.write();
+    let truncated = Reader::new(&file[..]).unwrap().drop_levels(1).unwrap();
+    let global_data = BasisLzGlobalData::parse(&truncated.supercompression_global_data).unwrap();
+    assert_eq!(global_data.image_descs, [image_desc(3), image_desc(4)]);
+}
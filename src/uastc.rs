@@ -0,0 +1,835 @@
+//! Encoder of [UASTC](https://github.com/BinomialLLC/basis_universal/wiki/UASTC-Texture-Specification) blocks.
+//!
+//! Uniform blocks use the solid color mode 8. Other blocks use whichever single subset mode has the lowest error:
+//! modes 0 and 18 for opaque blocks and modes 10 and 12 for blocks with alpha. The ETC1 hints of modes 0, 8 and 18
+//! are filled in so transcoders don't need to search for them, while the other hints are left at zero.
+
+use crate::{
+    basis::{Block, Source, SourceChannels},
+    etc1s::{expand5, modifiers},
+    uastc_data_format_descriptors, Reader, SupercompressionScheme, TextureError, UastcChannels, Writer, WriterLevel,
+};
+use std::vec::Vec;
+
+/// Options for [`Reader::encode_uastc`]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct UastcOptions {
+    /// Rate-distortion optimization, making blocks more similar to preceding blocks so supercompression works better
+    pub rdo: Option<UastcRdoOptions>,
+    /// Zstandard compression level to supercompress levels with, which requires the `zstd` feature
+    pub zstd_level: Option<i32>,
+}
+
+/// Options of rate-distortion optimization of UASTC blocks, with the defaults of `toktx`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UastcRdoOptions {
+    /// Weight of the estimated compressed size against the error, with higher values giving smaller files
+    pub lambda: f32,
+    /// Number of bytes of preceding blocks searched for matches
+    pub dictionary_size: usize,
+}
+
+impl Default for UastcRdoOptions {
+    fn default() -> Self {
+        Self {
+            lambda: 1.0,
+            dictionary_size: 4096,
+        }
+    }
+}
+
+/// Single subset mode of UASTC
+#[derive(Debug, Eq, PartialEq)]
+struct Mode {
+    /// Huffman code of the mode, read from the least significant bit
+    code: u32,
+    code_length: u32,
+    /// Number of bits of hints for transcoding, which follow the code
+    hint_bits: u32,
+    /// Whether endpoint values have a trit in addition to their bits
+    trits: bool,
+    endpoint_bits: u32,
+    weight_bits: u32,
+    alpha: bool,
+    /// Nearest endpoint value of each 8-bit value
+    quantization: [u8; 256],
+}
+
+impl Mode {
+    const fn new(code: u32, code_length: u32, trits: bool, endpoint_bits: u32, weight_bits: u32, alpha: bool) -> Self {
+        Self {
+            code,
+            code_length,
+            hint_bits: if alpha { 17 } else { 15 },
+            trits,
+            endpoint_bits,
+            weight_bits,
+            alpha,
+            quantization: quantization(trits, endpoint_bits),
+        }
+    }
+
+    fn channels(&self) -> usize {
+        if self.alpha {
+            4
+        } else {
+            3
+        }
+    }
+}
+
+/// Modes 0, 18, 10 and 12
+static MODES: [Mode; 4] = [
+    Mode::new(0x1, 4, true, 6, 4, false),
+    Mode::new(0x9, 4, false, 5, 5, false),
+    Mode::new(0x2, 3, true, 4, 4, true),
+    Mode::new(0x6, 3, true, 6, 3, true),
+];
+
+/// Code of the solid color mode 8
+const SOLID_CODE: u32 = 0x17;
+const SOLID_CODE_LENGTH: u32 = 5;
+
+/// 8-bit value of an endpoint, unquantized as in ASTC
+const fn unquantize_endpoint(trits: bool, bits: u32, value: u32) -> u32 {
+    if !trits {
+        return (value << (8 - bits)) | (value >> (2 * bits - 8));
+    }
+    let (low, trit) = (value & ((1 << bits) - 1), value >> bits);
+    let a = if low & 1 != 0 { 0x1FF } else { 0 };
+    let high = low >> 1;
+    let (b, c) = if bits == 4 {
+        ((high << 6) | high, 22)
+    } else {
+        ((high << 4) | (high >> 4), 5)
+    };
+    (a & 0x80) | (((trit * c + b) ^ a) >> 2)
+}
+
+const fn quantization(trits: bool, bits: u32) -> [u8; 256] {
+    let count = if trits { 3 << bits } else { 1 << bits };
+    let mut table = [0; 256];
+    let mut target = 0;
+    while target < 256 {
+        let (mut best, mut best_distance) = (0, u32::MAX);
+        let mut value = 0;
+        while value < count {
+            let distance = unquantize_endpoint(trits, bits, value).abs_diff(target);
+            if distance < best_distance {
+                best = value;
+                best_distance = distance;
+            }
+            value += 1;
+        }
+        table[target as usize] = best as u8;
+        target += 1;
+    }
+    table
+}
+
+/// 6-bit value of a weight, unquantized as in ASTC
+fn unquantize_weight(bits: u32, value: u32) -> u32 {
+    let weight = (value << (6 - bits)) | (value >> (2 * bits - 6));
+    if weight > 32 {
+        weight + 1
+    } else {
+        weight
+    }
+}
+
+fn interpolate(low: u32, high: u32, weight: u32) -> u8 {
+    let (low, high) = (low * 257, high * 257);
+    ((low * (64 - weight) + high * weight + 32) >> 14) as u8
+}
+
+/// Bits of a block, written from the least significant bit
+#[derive(Default)]
+struct Bits {
+    bits: u128,
+    position: u32,
+}
+
+impl Bits {
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= u128::from(value) << self.position;
+        self.position += count;
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.bits >> self.position) as u32 & ((1u64 << count) - 1) as u32;
+        self.position += count;
+        value
+    }
+}
+
+/// Endpoint values and weights of a block in one of [`MODES`]
+#[derive(Copy, Clone, Debug)]
+struct Encoding {
+    mode: &'static Mode,
+    /// Endpoint values of each channel
+    endpoints: [[u32; 2]; 4],
+    weights: [u32; 16],
+}
+
+impl Encoding {
+    fn unpack(block: &[u8; 16]) -> Option<Self> {
+        let mut bits = Bits {
+            bits: u128::from_le_bytes(*block),
+            position: 0,
+        };
+        let mode = MODES
+            .iter()
+            .find(|mode| bits.bits as u32 & ((1 << mode.code_length) - 1) == mode.code)?;
+        bits.position = mode.code_length + mode.hint_bits;
+
+        let mut values = [0; 8];
+        let count = mode.channels() * 2;
+        if mode.trits {
+            let mut trits = [0; 8];
+            for bundle in (0..count).step_by(5) {
+                let length = (count - bundle).min(5);
+                let mut packed = bits.read([0, 2, 4, 5, 7, 8][length]);
+                for trit in &mut trits[bundle..bundle + length] {
+                    *trit = packed % 3;
+                    packed /= 3;
+                }
+            }
+            for (value, trit) in values[..count].iter_mut().zip(trits) {
+                *value = (trit << mode.endpoint_bits) | bits.read(mode.endpoint_bits);
+            }
+        } else {
+            for value in &mut values[..count] {
+                *value = bits.read(mode.endpoint_bits);
+            }
+        }
+        let mut endpoints = [[0; 2]; 4];
+        for (channel, endpoints) in endpoints.iter_mut().take(mode.channels()).enumerate() {
+            *endpoints = [values[channel * 2], values[channel * 2 + 1]];
+        }
+
+        let mut weights = [0; 16];
+        for (index, weight) in weights.iter_mut().enumerate() {
+            *weight = bits.read(mode.weight_bits - u32::from(index == 0));
+        }
+        Some(Self {
+            mode,
+            endpoints,
+            weights,
+        })
+    }
+
+    /// Pack the block, first swapping the endpoints if needed so the first weight's top bit is zero
+    fn pack(mut self, hints: u32) -> [u8; 16] {
+        let mode = self.mode;
+        let max_weight = (1 << mode.weight_bits) - 1;
+        if self.weights[0] > max_weight / 2 {
+            for endpoints in &mut self.endpoints {
+                endpoints.swap(0, 1);
+            }
+            for weight in &mut self.weights {
+                *weight = max_weight - *weight;
+            }
+        }
+
+        let mut bits = Bits::default();
+        bits.write(mode.code, mode.code_length);
+        bits.write(hints, mode.hint_bits);
+        let values = self.endpoints[..mode.channels()].iter().flatten();
+        if mode.trits {
+            let trits: Vec<u32> = values.clone().map(|value| value >> mode.endpoint_bits).collect();
+            for bundle in trits.chunks(5) {
+                let packed = bundle.iter().rev().fold(0, |packed, trit| packed * 3 + trit);
+                bits.write(packed, [0, 2, 4, 5, 7, 8][bundle.len()]);
+            }
+        }
+        for value in values {
+            bits.write(value & ((1 << mode.endpoint_bits) - 1), mode.endpoint_bits);
+        }
+        for (index, &weight) in self.weights.iter().enumerate() {
+            bits.write(weight, mode.weight_bits - u32::from(index == 0));
+        }
+        debug_assert_eq!(bits.position, 128);
+        bits.bits.to_le_bytes()
+    }
+
+    fn endpoint_colors(&self) -> [[u32; 2]; 4] {
+        let mode = self.mode;
+        let mut colors = [[255; 2]; 4];
+        for (colors, endpoints) in colors.iter_mut().zip(&self.endpoints).take(mode.channels()) {
+            *colors = endpoints.map(|value| unquantize_endpoint(mode.trits, mode.endpoint_bits, value));
+        }
+        colors
+    }
+
+    fn decode(&self) -> Block {
+        let colors = self.endpoint_colors();
+        let mut texels = [[0; 4]; 16];
+        for (texel, &weight) in texels.iter_mut().zip(&self.weights) {
+            let weight = unquantize_weight(self.mode.weight_bits, weight);
+            for (value, [low, high]) in texel.iter_mut().zip(colors) {
+                *value = interpolate(low, high, weight);
+            }
+        }
+        texels
+    }
+
+    /// Pick the weight with the lowest error for each texel
+    fn select_weights(&mut self, texels: &Block) {
+        let colors = self.endpoint_colors();
+        let palette: Vec<[u8; 4]> = (0..1 << self.mode.weight_bits)
+            .map(|weight| {
+                let weight = unquantize_weight(self.mode.weight_bits, weight);
+                colors.map(|[low, high]| interpolate(low, high, weight))
+            })
+            .collect();
+        for (weight, texel) in self.weights.iter_mut().zip(texels) {
+            *weight = (0..palette.len())
+                .min_by_key(|&index| texel_error(&palette[index], texel))
+                .unwrap() as u32;
+        }
+    }
+
+    /// Set the endpoints to the least squares fit of the texels for the current weights
+    fn fit_endpoints(&mut self, texels: &Block) {
+        let mode = self.mode;
+        let weights = self
+            .weights
+            .map(|weight| unquantize_weight(mode.weight_bits, weight) as f32 / 64.0);
+        let (mut aa, mut ab, mut bb) = (0.0, 0.0, 0.0);
+        for &weight in &weights {
+            aa += (1.0 - weight) * (1.0 - weight);
+            ab += (1.0 - weight) * weight;
+            bb += weight * weight;
+        }
+        let determinant = aa * bb - ab * ab;
+        for channel in 0..mode.channels() {
+            let (mut a, mut b, mut mean) = (0.0, 0.0, 0.0);
+            for (&weight, texel) in weights.iter().zip(texels) {
+                let value = f32::from(texel[channel]);
+                a += (1.0 - weight) * value;
+                b += weight * value;
+                mean += value / 16.0;
+            }
+            let (low, high) = if determinant.abs() < 1e-6 {
+                (mean, mean)
+            } else {
+                ((a * bb - b * ab) / determinant, (b * aa - a * ab) / determinant)
+            };
+            self.endpoints[channel] = [low, high].map(|value| quantize(mode, value));
+        }
+    }
+}
+
+fn quantize(mode: &Mode, value: f32) -> u32 {
+    mode.quantization[value.round().clamp(0.0, 255.0) as usize].into()
+}
+
+fn texel_error(a: &[u8; 4], b: &[u8; 4]) -> u32 {
+    a.iter().zip(b).map(|(&a, &b)| u32::from(a.abs_diff(b)).pow(2)).sum()
+}
+
+fn block_error(a: &Block, b: &Block) -> u32 {
+    a.iter().zip(b).map(|(a, b)| texel_error(a, b)).sum()
+}
+
+/// Fit a mode to texels along their principal axis, refining the endpoints and weights twice
+fn fit(mode: &'static Mode, texels: &Block) -> Encoding {
+    let channels = mode.channels();
+    let mut mean = [0.0; 4];
+    for texel in texels {
+        for (mean, &value) in mean.iter_mut().zip(texel) {
+            *mean += f32::from(value) / 16.0;
+        }
+    }
+    let mut covariance = [[0.0; 4]; 4];
+    for texel in texels {
+        for i in 0..channels {
+            for j in 0..channels {
+                covariance[i][j] += (f32::from(texel[i]) - mean[i]) * (f32::from(texel[j]) - mean[j]);
+            }
+        }
+    }
+    // Power iteration starting from the channel of largest variance, which can't be orthogonal to the principal axis
+    let largest = (0..channels)
+        .max_by(|&a, &b| covariance[a][a].total_cmp(&covariance[b][b]))
+        .unwrap();
+    let mut axis = [0.0; 4];
+    axis[largest] = 1.0;
+    for _ in 0..8 {
+        let mut next = [0.0; 4];
+        for i in 0..channels {
+            next[i] = (0..channels).map(|j| covariance[i][j] * axis[j]).sum();
+        }
+        let length = next.iter().map(|value| value * value).sum::<f32>().sqrt();
+        if length < 1e-6 {
+            break;
+        }
+        axis = next.map(|value| value / length);
+    }
+    let projections = texels.iter().map(|texel| {
+        (0..channels)
+            .map(|channel| (f32::from(texel[channel]) - mean[channel]) * axis[channel])
+            .sum::<f32>()
+    });
+    let (min, max) = projections.fold((0.0f32, 0.0f32), |(min, max), value| (min.min(value), max.max(value)));
+
+    let mut endpoints = [[0; 2]; 4];
+    for channel in 0..channels {
+        endpoints[channel] = [min, max].map(|t| quantize(mode, mean[channel] + axis[channel] * t));
+    }
+    let mut encoding = Encoding {
+        mode,
+        endpoints,
+        weights: [0; 16],
+    };
+    encoding.select_weights(texels);
+    let mut best = (block_error(&encoding.decode(), texels), encoding);
+    for _ in 0..2 {
+        encoding.fit_endpoints(texels);
+        encoding.select_weights(texels);
+        let error = block_error(&encoding.decode(), texels);
+        if error < best.0 {
+            best = (error, encoding);
+        }
+    }
+    best.1
+}
+
+/// ETC1 hints of opaque modes: the flip and differential bits and the intensity table of each sub-block, after the
+/// two BC1 hints and with no selector bias
+fn etc1_hints(texels: &Block) -> u32 {
+    let mut best = (u32::MAX, 0);
+    for flip in [false, true] {
+        let sub_block = |index: usize| usize::from(if flip { index / 4 >= 2 } else { index % 4 >= 2 });
+        let mut sums = [[0; 3]; 2];
+        for (index, texel) in texels.iter().enumerate() {
+            for (sum, &value) in sums[sub_block(index)].iter_mut().zip(texel) {
+                *sum += u32::from(value);
+            }
+        }
+        let quantize = |sum: u32, max: u32| (sum * max + 8 * 255 / 2) / (8 * 255);
+        let colors = sums.map(|sums| sums.map(|sum| quantize(sum, 31)));
+        let differential =
+            (0..3).all(|channel| (-4..=3).contains(&(colors[1][channel] as i32 - colors[0][channel] as i32)));
+        let bases = if differential {
+            colors.map(|color| color.map(expand5))
+        } else {
+            sums.map(|sums| sums.map(|sum| quantize(sum, 15) as i32 * 17))
+        };
+
+        let mut error = 0;
+        let mut intensities = [0; 2];
+        for (sub_block_index, base) in bases.iter().enumerate() {
+            let (sub_block_error, intensity) = (0..8)
+                .map(|intensity| {
+                    let error = texels
+                        .iter()
+                        .enumerate()
+                        .filter(|&(index, _)| sub_block(index) == sub_block_index)
+                        .map(|(_, texel)| {
+                            modifiers(intensity)
+                                .iter()
+                                .map(|modifier| {
+                                    (0..3)
+                                        .map(|channel| {
+                                            let value = (base[channel] + modifier).clamp(0, 255);
+                                            (value - i32::from(texel[channel])).pow(2) as u32
+                                        })
+                                        .sum::<u32>()
+                                })
+                                .min()
+                                .unwrap()
+                        })
+                        .sum::<u32>();
+                    (error, intensity as u32)
+                })
+                .min()
+                .unwrap();
+            error += sub_block_error;
+            intensities[sub_block_index] = intensity;
+        }
+        if error < best.0 {
+            let hints =
+                u32::from(flip) | (u32::from(differential) << 1) | (intensities[0] << 2) | (intensities[1] << 5);
+            best = (error, hints);
+        }
+    }
+    best.1 << 2
+}
+
+/// Hints of a block in a mode
+fn hints(mode: &Mode, texels: &Block) -> u32 {
+    if mode.alpha {
+        0
+    } else {
+        etc1_hints(texels)
+    }
+}
+
+/// Encode a block with the solid color mode, with the ETC1 differential mode hints closest to the color
+fn encode_solid(color: [u8; 4]) -> [u8; 16] {
+    let mut best = (u32::MAX, 0, 0, [0; 3]);
+    for intensity in 0..8 {
+        for (selector, &modifier) in modifiers(intensity).iter().enumerate() {
+            let mut error = 0;
+            let mut base = [0; 3];
+            for (base, &value) in base.iter_mut().zip(&color) {
+                let (channel_error, channel_base) = (0..32)
+                    .map(|base| {
+                        let decoded = (expand5(base) + modifier).clamp(0, 255);
+                        ((decoded - i32::from(value)).pow(2) as u32, base)
+                    })
+                    .min()
+                    .unwrap();
+                error += channel_error;
+                *base = channel_base;
+            }
+            if error < best.0 {
+                best = (error, intensity as u32, selector as u32, base);
+            }
+        }
+    }
+
+    let mut bits = Bits::default();
+    bits.write(SOLID_CODE, SOLID_CODE_LENGTH);
+    for value in color {
+        bits.write(value.into(), 8);
+    }
+    let (_, intensity, selector, base) = best;
+    bits.write(1, 1);
+    bits.write(intensity, 3);
+    bits.write(selector, 2);
+    for value in base {
+        bits.write(value, 5);
+    }
+    bits.bits.to_le_bytes()
+}
+
+/// Encode a block with the mode giving the lowest error
+pub(crate) fn encode_block(texels: &Block) -> [u8; 16] {
+    if texels.iter().all(|texel| *texel == texels[0]) {
+        return encode_solid(texels[0]);
+    }
+    let mut mean = [0; 4];
+    for texel in texels {
+        for (mean, &value) in mean.iter_mut().zip(texel) {
+            *mean += u32::from(value);
+        }
+    }
+    let solid = encode_solid(mean.map(|sum| ((sum + 8) / 16) as u8));
+    let mut best = (block_error(&decode_block(&solid).unwrap(), texels), solid);
+
+    let opaque = texels.iter().all(|texel| texel[3] == 255);
+    for mode in MODES.iter().filter(|mode| mode.alpha != opaque) {
+        let encoding = fit(mode, texels);
+        let error = block_error(&encoding.decode(), texels);
+        if error < best.0 {
+            best = (error, encoding.pack(hints(mode, texels)));
+        }
+    }
+    best.1
+}
+
+/// Decode a block of a mode used by [`encode_block`] to RGBA texels, or `None` for other modes
+pub(crate) fn decode_block(block: &[u8; 16]) -> Option<Block> {
+    let bits = u128::from_le_bytes(*block);
+    if bits as u32 & ((1 << SOLID_CODE_LENGTH) - 1) == SOLID_CODE {
+        let mut bits = Bits {
+            bits,
+            position: SOLID_CODE_LENGTH,
+        };
+        let color = [(); 4].map(|()| bits.read(8) as u8);
+        return Some([color; 16]);
+    }
+    Encoding::unpack(block).map(|encoding| encoding.decode())
+}
+
+/// Estimated compressed size of a block in bits, counting runs of at least three bytes matching the same bytes of a
+/// preceding block as one match and other bytes as literals
+fn estimated_bits(block: &[u8; 16], previous: &[[u8; 16]]) -> u32 {
+    let mut bits = 0;
+    let mut position = 0;
+    while position < 16 {
+        let length = previous
+            .iter()
+            .map(|other| {
+                block[position..]
+                    .iter()
+                    .zip(&other[position..])
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0);
+        if length >= 3 {
+            bits += MATCH_BITS;
+            position += length;
+        } else {
+            bits += 8;
+            position += 1;
+        }
+    }
+    bits
+}
+
+/// Estimated size of a match of a Zstandard sequence in bits
+const MATCH_BITS: u32 = 24;
+
+/// Number of the most recent blocks whose weights are tried with refitted endpoints
+const WEIGHT_CANDIDATES: usize = 32;
+
+/// Replace blocks with copies of preceding blocks, or with blocks reusing their weights, when the added error is worth
+/// the estimated saving in compressed size
+fn optimize(blocks: &mut [[u8; 16]], sources: &[Block], options: &UastcRdoOptions) {
+    let window = (options.dictionary_size / 16).max(1);
+    let mut decoded: Vec<Block> = Vec::with_capacity(blocks.len());
+    for (index, texels) in sources.iter().enumerate() {
+        let (previous, rest) = blocks.split_at_mut(index);
+        let start = index.saturating_sub(window);
+        let previous = &previous[start..];
+        let cost = |error: u32, bits: u32| error as f32 / 64.0 + options.lambda * bits as f32 / 8.0;
+
+        let current = rest[0];
+        let current_error = block_error(&decode_block(&current).unwrap(), texels);
+        // Candidates more than 10 times the RMS error of the original are rejected
+        let max_error = current_error.max(16).saturating_mul(100);
+        let mut best = (cost(current_error, estimated_bits(&current, previous)), current);
+
+        for (offset, candidate) in previous.iter().enumerate().rev() {
+            let error = block_error(&decoded[start + offset], texels);
+            let candidate_cost = cost(error, MATCH_BITS);
+            if error <= max_error && candidate_cost < best.0 {
+                best = (candidate_cost, *candidate);
+            }
+        }
+
+        if let Some(encoding) = Encoding::unpack(&current) {
+            let hints = hints(encoding.mode, texels);
+            // Weights end the block, so only the bytes before the first whole byte of weights are literals
+            let weight_bits = encoding.mode.weight_bits * 16 - 1;
+            let literal_bits = (128 - weight_bits).div_ceil(8) * 8;
+            for candidate in previous.iter().rev().take(WEIGHT_CANDIDATES) {
+                let other = match Encoding::unpack(candidate) {
+                    Some(other) if other.mode == encoding.mode => other,
+                    _ => continue,
+                };
+                let mut spliced = Encoding {
+                    weights: other.weights,
+                    ..encoding
+                };
+                spliced.fit_endpoints(texels);
+                let error = block_error(&spliced.decode(), texels);
+                let candidate_cost = cost(error, literal_bits + MATCH_BITS);
+                if error <= max_error && candidate_cost < best.0 {
+                    best = (candidate_cost, spliced.pack(hints));
+                }
+            }
+        }
+
+        rest[0] = best.1;
+        decoded.push(decode_block(&best.1).unwrap());
+    }
+}
+
+/// Channels of the RGBA texels of UASTC blocks, with the first channel of one and two channel sources replicated to
+/// RGB and the second one in alpha
+fn uastc_texels(block: &Block, channels: SourceChannels) -> Block {
+    block.map(|[r, g, b, a]| match channels {
+        SourceChannels::R => [r, r, r, 255],
+        SourceChannels::Rg => [r, r, r, g],
+        SourceChannels::Rgb => [r, g, b, 255],
+        SourceChannels::Rgba => [r, g, b, a],
+    })
+}
+
+fn supercompress(data: Vec<u8>, zstd_level: Option<i32>) -> Result<WriterLevel, TextureError> {
+    match zstd_level {
+        None => Ok(WriterLevel::new(data)),
+        #[cfg(feature = "zstd")]
+        Some(level) => Ok(WriterLevel {
+            data: crate::supercompression::compress(Some(SupercompressionScheme::Zstandard), &data, level)?,
+            uncompressed_byte_length: data.len() as u64,
+        }),
+        #[cfg(not(feature = "zstd"))]
+        Some(_) => Err(TextureError::UnsupportedSupercompression),
+    }
+}
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// Encode a texture of an 8-bit normalized or sRGB format as UASTC, as `toktx --encode uastc` does
+    ///
+    /// The key/value data is kept, with `KTXswizzle` updated for one and two channel textures, which are stored as
+    /// [`UastcChannels::Rrr`] and [`UastcChannels::Rg`]. The source texture can't be supercompressed.
+    pub fn encode_uastc(&self, options: &UastcOptions) -> Result<Writer, TextureError> {
+        let source = Source::new(self)?;
+        let channels = match source.channels {
+            SourceChannels::R => UastcChannels::Rrr,
+            SourceChannels::Rg => UastcChannels::Rg,
+            SourceChannels::Rgb => UastcChannels::Rgb,
+            SourceChannels::Rgba => UastcChannels::Rgba,
+        };
+        let supercompression_scheme = options.zstd_level.map(|_| SupercompressionScheme::Zstandard);
+
+        let levels = source
+            .levels
+            .iter()
+            .map(|images| {
+                let texels: Vec<Block> = images
+                    .iter()
+                    .flatten()
+                    .map(|block| uastc_texels(block, source.channels))
+                    .collect();
+                let mut blocks: Vec<[u8; 16]> = texels.iter().map(encode_block).collect();
+                if let Some(rdo) = &options.rdo {
+                    optimize(&mut blocks, &texels, rdo);
+                }
+                supercompress(blocks.concat(), options.zstd_level)
+            })
+            .collect::<Result<_, _>>()?;
+
+        let data_format_descriptors = uastc_data_format_descriptors(channels, source.srgb, supercompression_scheme);
+        Ok(source.writer(
+            self,
+            data_format_descriptors,
+            supercompression_scheme,
+            Vec::new(),
+            levels,
+        ))
+    }
+}
+
+#[test]
+fn test_uastc_modes() {
+    // The mode codes form a complete prefix code together with the modes this encoder doesn't use
+    let solid = encode_solid([10, 200, 30, 255]);
+    assert_eq!(solid[0] & 0x1F, 0x17);
+    assert_eq!(decode_block(&solid), Some([[10, 200, 30, 255]; 16]));
+
+    for mode in &MODES {
+        let bits = mode.code_length
+            + mode.hint_bits
+            + if mode.trits {
+                [0, 2, 4, 5, 7, 8][5] + [0, 2, 4, 5, 7, 8][mode.channels() * 2 - 5]
+            } else {
+                0
+            }
+            + mode.endpoint_bits * mode.channels() as u32 * 2
+            + mode.weight_bits * 16
+            - 1;
+        assert_eq!(bits, 128);
+        for value in 0..=255 {
+            let quantized = mode.quantization[value as usize].into();
+            let unquantized = unquantize_endpoint(mode.trits, mode.endpoint_bits, quantized);
+            assert!(unquantized.abs_diff(value) <= 256 / (2 << mode.endpoint_bits) + 1);
+        }
+    }
+}
+
+#[test]
+fn test_uastc_quality() {
+    let psnr = |a: &[Block], b: &[Block]| {
+        let error: u32 = a.iter().zip(b).map(|(a, b)| block_error(a, b)).sum();
+        let mse = error as f64 / (a.len() * 64) as f64;
+        10.0 * (255.0 * 255.0 / mse).log10()
+    };
+    let gradient: Vec<Block> = (0..64)
+        .map(|block| {
+            let mut texels = [[0; 4]; 16];
+            for (index, texel) in texels.iter_mut().enumerate() {
+                // Colors of each block lie on a line, which single subset modes can represent
+                let t = (block % 8 * 4 + index % 4 + block / 8 * 4 + index / 4) as u32;
+                *texel = [(t * 4) as u8, (255 - t * 4) as u8, (t * 2 + 20) as u8, 255];
+            }
+            texels
+        })
+        .collect();
+    let translucent: Vec<Block> = gradient
+        .iter()
+        .map(|block| block.map(|[r, g, b, _]| [r, g, b, 255 - b]))
+        .collect();
+
+    for (sources, mode_alpha) in [(&gradient, false), (&translucent, true)] {
+        let blocks: Vec<[u8; 16]> = sources.iter().map(encode_block).collect();
+        for block in &blocks {
+            assert_eq!(Encoding::unpack(block).unwrap().mode.alpha, mode_alpha);
+        }
+        let decoded: Vec<Block> = blocks.iter().map(|block| decode_block(block).unwrap()).collect();
+        assert!(psnr(sources, &decoded) > 40.0);
+
+        let mut optimized = blocks.clone();
+        optimize(
+            &mut optimized,
+            sources,
+            &UastcRdoOptions {
+                lambda: 10.0,
+                ..Default::default()
+            },
+        );
+        let decoded: Vec<Block> = optimized.iter().map(|block| decode_block(block).unwrap()).collect();
+        assert!(psnr(sources, &decoded) > 30.0);
+        let estimate = |blocks: &[[u8; 16]]| -> u32 {
+            (0..blocks.len())
+                .map(|index| estimated_bits(&blocks[index], &blocks[index.saturating_sub(256)..index]))
+                .sum()
+        };
+        assert!(estimate(&optimized) < estimate(&blocks));
+    }
+}
+
+#[test]
+fn test_encode_uastc() {
+    use crate::{BasicDataFormatDescriptor, Format, MipmapOptions};
+    use core::convert::TryInto;
+
+    let base: Vec<u8> = (0..9 * 6).flat_map(|index| [(index * 4) as u8, 100]).collect();
+    let writer = Writer::with_mipmaps(Format::R8G8_UNORM, [9, 6, 0], &base, &MipmapOptions::default()).unwrap();
+    let file = writer.write();
+    let reader = Reader::new(&file[..]).unwrap();
+
+    let encoded = reader.encode_uastc(&UastcOptions::default()).unwrap().write();
+    let encoded = Reader::new(&encoded[..]).unwrap();
+    assert_eq!(encoded.header().format, None);
+    assert_eq!(encoded.header().level_count, 4);
+    assert_eq!(encoded.swizzle(), crate::texel::Swizzle::parse(b"ra01"));
+    let dfd = encoded.data_format_descriptors().next().unwrap();
+    let dfd = BasicDataFormatDescriptor::parse(dfd.data).unwrap();
+    assert_eq!(dfd.header.bytes_planes[0], 16);
+    let level = encoded.levels().next().unwrap().data;
+    assert_eq!(level.len(), 3 * 2 * 16);
+    let block = decode_block(level[..16].try_into().unwrap()).unwrap();
+    for (&value, expected) in block[5].iter().zip([40, 40, 40, 100]) {
+        assert!(value.abs_diff(expected) <= 4);
+    }
+
+    #[cfg(feature = "zstd")]
+    {
+        let options = UastcOptions {
+            rdo: Some(UastcRdoOptions::default()),
+            zstd_level: Some(3),
+        };
+        let supercompressed = reader.encode_uastc(&options).unwrap().write();
+        let supercompressed = Reader::new(&supercompressed[..]).unwrap();
+        assert_eq!(
+            supercompressed.header().supercompression_scheme,
+            Some(SupercompressionScheme::Zstandard)
+        );
+        let dfd = supercompressed.data_format_descriptors().next().unwrap();
+        assert_eq!(
+            BasicDataFormatDescriptor::parse(dfd.data).unwrap().header.bytes_planes[0],
+            0
+        );
+        let level = supercompressed.levels().next().unwrap();
+        assert_eq!(level.uncompressed_byte_length, 3 * 2 * 16);
+    }
+    #[cfg(not(feature = "zstd"))]
+    {
+        let options = UastcOptions {
+            zstd_level: Some(3),
+            ..Default::default()
+        };
+        assert!(matches!(
+            reader.encode_uastc(&options),
+            Err(TextureError::UnsupportedSupercompression)
+        ));
+    }
+}
@@ -0,0 +1,291 @@
+//! Integration with [wgpu](https://wgpu.rs), enabled by the `wgpu` feature.
+//!
+//! Formats which need optional device features, such as the BC, ETC2 and ASTC families, can be checked with
+//! [`required_features`].
+
+use crate::{Format, Header, ImageLayout, Reader, TextureError};
+use std::vec::Vec;
+use wgpu_types::{
+    AstcBlock, AstcChannel, Extent3d, Features, Origin3d, TexelCopyBufferLayout, TextureDescriptor, TextureDimension,
+    TextureFormat, TextureUsages,
+};
+
+/// Convert a KTX2 format into the equivalent wgpu texture format, if there is one
+///
+/// BC1 formats without alpha map to their RGBA counterpart. `X8_D24_UNORM_PACK32` has no equivalent as wgpu's
+/// `Depth24Plus` can't be written to.
+pub fn texture_format(format: Format) -> Option<TextureFormat> {
+    let astc = |block, srgb| TextureFormat::Astc {
+        block,
+        channel: if srgb {
+            AstcChannel::UnormSrgb
+        } else {
+            AstcChannel::Unorm
+        },
+    };
+
+    Some(match format {
+        Format::R8_UNORM => TextureFormat::R8Unorm,
+        Format::R8_SNORM => TextureFormat::R8Snorm,
+        Format::R8_UINT => TextureFormat::R8Uint,
+        Format::R8_SINT => TextureFormat::R8Sint,
+        Format::R8G8_UNORM => TextureFormat::Rg8Unorm,
+        Format::R8G8_SNORM => TextureFormat::Rg8Snorm,
+        Format::R8G8_UINT => TextureFormat::Rg8Uint,
+        Format::R8G8_SINT => TextureFormat::Rg8Sint,
+        Format::R8G8B8A8_UNORM => TextureFormat::Rgba8Unorm,
+        Format::R8G8B8A8_SNORM => TextureFormat::Rgba8Snorm,
+        Format::R8G8B8A8_UINT => TextureFormat::Rgba8Uint,
+        Format::R8G8B8A8_SINT => TextureFormat::Rgba8Sint,
+        Format::R8G8B8A8_SRGB => TextureFormat::Rgba8UnormSrgb,
+        Format::B8G8R8A8_UNORM => TextureFormat::Bgra8Unorm,
+        Format::B8G8R8A8_SRGB => TextureFormat::Bgra8UnormSrgb,
+        Format::A2B10G10R10_UNORM_PACK32 => TextureFormat::Rgb10a2Unorm,
+        Format::A2B10G10R10_UINT_PACK32 => TextureFormat::Rgb10a2Uint,
+        Format::R16_UNORM => TextureFormat::R16Unorm,
+        Format::R16_SNORM => TextureFormat::R16Snorm,
+        Format::R16_UINT => TextureFormat::R16Uint,
+        Format::R16_SINT => TextureFormat::R16Sint,
+        Format::R16_SFLOAT => TextureFormat::R16Float,
+        Format::R16G16_UNORM => TextureFormat::Rg16Unorm,
+        Format::R16G16_SNORM => TextureFormat::Rg16Snorm,
+        Format::R16G16_UINT => TextureFormat::Rg16Uint,
+        Format::R16G16_SINT => TextureFormat::Rg16Sint,
+        Format::R16G16_SFLOAT => TextureFormat::Rg16Float,
+        Format::R16G16B16A16_UNORM => TextureFormat::Rgba16Unorm,
+        Format::R16G16B16A16_SNORM => TextureFormat::Rgba16Snorm,
+        Format::R16G16B16A16_UINT => TextureFormat::Rgba16Uint,
+        Format::R16G16B16A16_SINT => TextureFormat::Rgba16Sint,
+        Format::R16G16B16A16_SFLOAT => TextureFormat::Rgba16Float,
+        Format::R32_UINT => TextureFormat::R32Uint,
+        Format::R32_SINT => TextureFormat::R32Sint,
+        Format::R32_SFLOAT => TextureFormat::R32Float,
+        Format::R32G32_UINT => TextureFormat::Rg32Uint,
+        Format::R32G32_SINT => TextureFormat::Rg32Sint,
+        Format::R32G32_SFLOAT => TextureFormat::Rg32Float,
+        Format::R32G32B32A32_UINT => TextureFormat::Rgba32Uint,
+        Format::R32G32B32A32_SINT => TextureFormat::Rgba32Sint,
+        Format::R32G32B32A32_SFLOAT => TextureFormat::Rgba32Float,
+        Format::R64_UINT => TextureFormat::R64Uint,
+        Format::B10G11R11_UFLOAT_PACK32 => TextureFormat::Rg11b10Ufloat,
+        Format::E5B9G9R9_UFLOAT_PACK32 => TextureFormat::Rgb9e5Ufloat,
+        Format::D16_UNORM => TextureFormat::Depth16Unorm,
+        Format::D32_SFLOAT => TextureFormat::Depth32Float,
+        Format::S8_UINT => TextureFormat::Stencil8,
+        Format::D24_UNORM_S8_UINT => TextureFormat::Depth24PlusStencil8,
+        Format::D32_SFLOAT_S8_UINT => TextureFormat::Depth32FloatStencil8,
+        Format::BC1_RGB_UNORM_BLOCK | Format::BC1_RGBA_UNORM_BLOCK => TextureFormat::Bc1RgbaUnorm,
+        Format::BC1_RGB_SRGB_BLOCK | Format::BC1_RGBA_SRGB_BLOCK => TextureFormat::Bc1RgbaUnormSrgb,
+        Format::BC2_UNORM_BLOCK => TextureFormat::Bc2RgbaUnorm,
+        Format::BC2_SRGB_BLOCK => TextureFormat::Bc2RgbaUnormSrgb,
+        Format::BC3_UNORM_BLOCK => TextureFormat::Bc3RgbaUnorm,
+        Format::BC3_SRGB_BLOCK => TextureFormat::Bc3RgbaUnormSrgb,
+        Format::BC4_UNORM_BLOCK => TextureFormat::Bc4RUnorm,
+        Format::BC4_SNORM_BLOCK => TextureFormat::Bc4RSnorm,
+        Format::BC5_UNORM_BLOCK => TextureFormat::Bc5RgUnorm,
+        Format::BC5_SNORM_BLOCK => TextureFormat::Bc5RgSnorm,
+        Format::BC6H_UFLOAT_BLOCK => TextureFormat::Bc6hRgbUfloat,
+        Format::BC6H_SFLOAT_BLOCK => TextureFormat::Bc6hRgbFloat,
+        Format::BC7_UNORM_BLOCK => TextureFormat::Bc7RgbaUnorm,
+        Format::BC7_SRGB_BLOCK => TextureFormat::Bc7RgbaUnormSrgb,
+        Format::ETC2_R8G8B8_UNORM_BLOCK => TextureFormat::Etc2Rgb8Unorm,
+        Format::ETC2_R8G8B8_SRGB_BLOCK => TextureFormat::Etc2Rgb8UnormSrgb,
+        Format::ETC2_R8G8B8A1_UNORM_BLOCK => TextureFormat::Etc2Rgb8A1Unorm,
+        Format::ETC2_R8G8B8A1_SRGB_BLOCK => TextureFormat::Etc2Rgb8A1UnormSrgb,
+        Format::ETC2_R8G8B8A8_UNORM_BLOCK => TextureFormat::Etc2Rgba8Unorm,
+        Format::ETC2_R8G8B8A8_SRGB_BLOCK => TextureFormat::Etc2Rgba8UnormSrgb,
+        Format::EAC_R11_UNORM_BLOCK => TextureFormat::EacR11Unorm,
+        Format::EAC_R11_SNORM_BLOCK => TextureFormat::EacR11Snorm,
+        Format::EAC_R11G11_UNORM_BLOCK => TextureFormat::EacRg11Unorm,
+        Format::EAC_R11G11_SNORM_BLOCK => TextureFormat::EacRg11Snorm,
+        Format::ASTC_4x4_UNORM_BLOCK => astc(AstcBlock::B4x4, false),
+        Format::ASTC_4x4_SRGB_BLOCK => astc(AstcBlock::B4x4, true),
+        Format::ASTC_5x4_UNORM_BLOCK => astc(AstcBlock::B5x4, false),
+        Format::ASTC_5x4_SRGB_BLOCK => astc(AstcBlock::B5x4, true),
+        Format::ASTC_5x5_UNORM_BLOCK => astc(AstcBlock::B5x5, false),
+        Format::ASTC_5x5_SRGB_BLOCK => astc(AstcBlock::B5x5, true),
+        Format::ASTC_6x5_UNORM_BLOCK => astc(AstcBlock::B6x5, false),
+        Format::ASTC_6x5_SRGB_BLOCK => astc(AstcBlock::B6x5, true),
+        Format::ASTC_6x6_UNORM_BLOCK => astc(AstcBlock::B6x6, false),
+        Format::ASTC_6x6_SRGB_BLOCK => astc(AstcBlock::B6x6, true),
+        Format::ASTC_8x5_UNORM_BLOCK => astc(AstcBlock::B8x5, false),
+        Format::ASTC_8x5_SRGB_BLOCK => astc(AstcBlock::B8x5, true),
+        Format::ASTC_8x6_UNORM_BLOCK => astc(AstcBlock::B8x6, false),
+        Format::ASTC_8x6_SRGB_BLOCK => astc(AstcBlock::B8x6, true),
+        Format::ASTC_8x8_UNORM_BLOCK => astc(AstcBlock::B8x8, false),
+        Format::ASTC_8x8_SRGB_BLOCK => astc(AstcBlock::B8x8, true),
+        Format::ASTC_10x5_UNORM_BLOCK => astc(AstcBlock::B10x5, false),
+        Format::ASTC_10x5_SRGB_BLOCK => astc(AstcBlock::B10x5, true),
+        Format::ASTC_10x6_UNORM_BLOCK => astc(AstcBlock::B10x6, false),
+        Format::ASTC_10x6_SRGB_BLOCK => astc(AstcBlock::B10x6, true),
+        Format::ASTC_10x8_UNORM_BLOCK => astc(AstcBlock::B10x8, false),
+        Format::ASTC_10x8_SRGB_BLOCK => astc(AstcBlock::B10x8, true),
+        Format::ASTC_10x10_UNORM_BLOCK => astc(AstcBlock::B10x10, false),
+        Format::ASTC_10x10_SRGB_BLOCK => astc(AstcBlock::B10x10, true),
+        Format::ASTC_12x10_UNORM_BLOCK => astc(AstcBlock::B12x10, false),
+        Format::ASTC_12x10_SRGB_BLOCK => astc(AstcBlock::B12x10, true),
+        Format::ASTC_12x12_UNORM_BLOCK => astc(AstcBlock::B12x12, false),
+        Format::ASTC_12x12_SRGB_BLOCK => astc(AstcBlock::B12x12, true),
+        _ => return None,
+    })
+}
+
+/// Device features needed to use `format`, or `None` if there's no equivalent wgpu format
+pub fn required_features(format: Format) -> Option<Features> {
+    texture_format(format).map(|format| format.required_features())
+}
+
+/// Describe a texture matching `header`
+///
+/// Cube maps are described as 2D textures with six array layers per cube, ready to be viewed as a cube or cube array.
+/// 1D arrays and mipmapped 1D textures are described as 2D textures with a height of 1, as wgpu's 1D textures have a
+/// single layer and level.
+pub fn texture_descriptor(
+    header: &Header,
+    usage: TextureUsages,
+) -> Result<TextureDescriptor<Option<&'static str>, &'static [TextureFormat]>, TextureError> {
+    let format = header
+        .format
+        .and_then(texture_format)
+        .ok_or(TextureError::UnsupportedFormat)?;
+    let [width, height, depth] = header.level_extent(0);
+    let (dimension, depth_or_array_layers) = if header.pixel_depth > 0 {
+        (TextureDimension::D3, depth)
+    } else {
+        let layers = header
+            .layer_count
+            .max(1)
+            .checked_mul(header.face_count)
+            .ok_or(TextureError::InvalidLength)?;
+        let dimension = if header.pixel_height > 0 || layers > 1 || header.level_count > 1 {
+            TextureDimension::D2
+        } else {
+            TextureDimension::D1
+        };
+        (dimension, layers)
+    };
+
+    Ok(TextureDescriptor {
+        label: None,
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers,
+        },
+        mip_level_count: header.level_count.max(1),
+        sample_count: 1,
+        dimension,
+        format,
+        usage,
+        view_formats: &[],
+    })
+}
+
+/// Data of a single array layer or cube face of a mip level, laid out for `Queue::write_texture`
+#[derive(Copy, Clone, Debug)]
+pub struct ImageUpload<'data> {
+    pub mip_level: u32,
+    pub origin: Origin3d,
+    pub data: &'data [u8],
+    pub layout: TexelCopyBufferLayout,
+    /// Size of the copy, rounded up to whole texel blocks
+    pub size: Extent3d,
+}
+
+/// Split the levels of `reader` into images to upload to a texture created with [`texture_descriptor`]
+///
+/// Combined depth/stencil formats aren't supported, as wgpu copies each aspect separately, and supercompressed textures
+/// have to be decompressed first.
+pub fn image_uploads<Data: AsRef<[u8]>>(reader: &Reader<Data>) -> Result<Vec<ImageUpload<'_>>, TextureError> {
+    let header = reader.header();
+    if header.supercompression_scheme.is_some() {
+        return Err(TextureError::UnsupportedSupercompression);
+    }
+    let format = match header.format {
+        Some(Format::D16_UNORM_S8_UINT | Format::D24_UNORM_S8_UINT | Format::D32_SFLOAT_S8_UINT) | None => {
+            return Err(TextureError::UnsupportedFormat)
+        }
+        Some(format) => format,
+    };
+    let image_count = header
+        .layer_count
+        .max(1)
+        .checked_mul(header.face_count)
+        .ok_or(TextureError::InvalidLength)?;
+
+    let mut uploads = Vec::new();
+    for (mip_level, level) in reader.levels().enumerate() {
+        let mip_level = mip_level as u32;
+        let layout = ImageLayout::new(format, header.level_extent(mip_level))?;
+        if Some(level.data.len()) != layout.byte_length.checked_mul(image_count as usize) {
+            return Err(TextureError::InvalidLength);
+        }
+
+        let [width, height, depth] = layout.physical_extent;
+        for (image, data) in level.data.chunks_exact(layout.byte_length).enumerate() {
+            uploads.push(ImageUpload {
+                mip_level,
+                origin: Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: image as u32,
+                },
+                data,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(layout.bytes_per_row),
+                    rows_per_image: Some(layout.rows_per_image),
+                },
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: depth,
+                },
+            });
+        }
+    }
+    Ok(uploads)
+}
+
+#[test]
+fn test_image_uploads() {
+    let reader = Reader::new(&include_bytes!("../data/test_tex.ktx2")[..]).unwrap();
+    let descriptor = texture_descriptor(&reader.header(), TextureUsages::TEXTURE_BINDING).unwrap();
+    assert_eq!(descriptor.format, TextureFormat::Rgba8Uint);
+    assert_eq!(descriptor.dimension, TextureDimension::D2);
+    assert_eq!(descriptor.mip_level_count, 11);
+
+    let uploads = image_uploads(&reader).unwrap();
+    assert_eq!(uploads.len(), 11);
+    assert_eq!(uploads[1].layout.bytes_per_row, Some(512 * 4));
+    assert_eq!(uploads[1].layout.rows_per_image, Some(256));
+    assert_eq!(uploads[10].size.width, 1);
+
+    let mut header = reader.header();
+    header.pixel_height = 0;
+    header.level_count = 1;
+    let descriptor = texture_descriptor(&header, TextureUsages::TEXTURE_BINDING).unwrap();
+    assert_eq!(descriptor.dimension, TextureDimension::D1);
+    header.layer_count = 4;
+    let descriptor = texture_descriptor(&header, TextureUsages::TEXTURE_BINDING).unwrap();
+    assert_eq!(descriptor.dimension, TextureDimension::D2);
+    assert_eq!(descriptor.size.height, 1);
+    assert_eq!(descriptor.size.depth_or_array_layers, 4);
+
+    assert_eq!(texture_format(Format::X8_D24_UNORM_PACK32), None);
+    assert_eq!(required_features(Format::R8_UNORM), Some(Features::empty()));
+    assert_eq!(
+        required_features(Format::BC7_UNORM_BLOCK),
+        Some(Features::TEXTURE_COMPRESSION_BC)
+    );
+    assert_eq!(required_features(Format::R8G8B8_UNORM), None);
+
+    let mut writer = crate::Writer::from_reader(&reader);
+    writer.header.format = Some(Format::D24_UNORM_S8_UINT);
+    let depth_stencil = writer.write();
+    let depth_stencil = Reader::new(&depth_stencil[..]).unwrap();
+    assert!(matches!(
+        image_uploads(&depth_stencil),
+        Err(TextureError::UnsupportedFormat)
+    ));
+}
@@ -0,0 +1,258 @@
+use crate::{BasicDataFormatDescriptor, DataFormatDescriptorHeader, Header, Index, LevelIndex, Reader};
+use std::{collections::BTreeMap, string::String, vec::Vec};
+
+/// Encodes KTX2 texture data
+///
+/// The [`Index`] of [`Writer::header`] is ignored: the byte offsets and lengths of all sections are computed when
+/// writing.
+#[derive(Clone, Debug)]
+pub struct Writer {
+    /// Container-level metadata
+    pub header: Header,
+    /// Data format descriptor blocks, excluding the leading total size field
+    pub data_format_descriptors: Vec<u8>,
+    /// Key-value pairs, written in key order as required by the specification. Keys must be non-empty and free of NUL.
+    pub key_value_data: BTreeMap<String, Vec<u8>>,
+    pub supercompression_global_data: Vec<u8>,
+    /// Mip levels, starting with the base level
+    pub levels: Vec<WriterLevel>,
+}
+
+/// Data of a single mip level to be written
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct WriterLevel {
+    pub data: Vec<u8>,
+    pub uncompressed_byte_length: u64,
+}
+
+impl WriterLevel {
+    /// Level which is not supercompressed
+    pub fn new(data: Vec<u8>) -> Self {
+        let uncompressed_byte_length = data.len() as u64;
+        Self {
+            data,
+            uncompressed_byte_length,
+        }
+    }
+}
+
+impl Writer {
+    /// Copy of all the sections of a texture, which writes an identical file if the original was laid out as
+    /// [`Writer::write`] does
+    pub fn from_reader<Data: AsRef<[u8]>>(reader: &Reader<Data>) -> Self {
+        let index = reader.header().index;
+        Self {
+            header: reader.header(),
+            data_format_descriptors: reader.data()
+                [index.dfd_byte_offset as usize + 4..(index.dfd_byte_offset + index.dfd_byte_length) as usize]
+                .to_vec(),
+            key_value_data: reader
+                .key_value_data()
+                .map(|(key, value)| (key.into(), value.to_vec()))
+                .collect(),
+            supercompression_global_data: reader.supercompression_global_data().to_vec(),
+            levels: reader
+                .levels()
+                .map(|level| WriterLevel {
+                    data: level.data.to_vec(),
+                    uncompressed_byte_length: level.uncompressed_byte_length,
+                })
+                .collect(),
+        }
+    }
+
+    /// Remove the `KTXwriter` and `KTXwriterScSettings` entries, which describe the tool that wrote a file rather
+    /// than its content
+    pub fn remove_writer_metadata(&mut self) {
+        self.key_value_data
+            .retain(|key, _| key != "KTXwriter" && key != "KTXwriterScSettings");
+    }
+
+    /// Serialize the texture into a KTX2 file
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of levels doesn't match `header.level_count`, treating a level count of 0 as 1, or if a
+    /// key-value data key is empty or contains a NUL, which [`Reader`] couldn't parse.
+    pub fn write(&self) -> Vec<u8> {
+        assert_eq!(
+            self.levels.len(),
+            self.header.level_count.max(1) as usize,
+            "level count doesn't match the header"
+        );
+        assert!(
+            self.key_value_data
+                .keys()
+                .all(|key| !key.is_empty() && !key.contains('\0')),
+            "key-value data keys must be non-empty and can't contain NUL"
+        );
+
+        let mut header = self.header;
+        let level_index_start = Header::LENGTH;
+        let mut output = std::vec![0; level_index_start + self.levels.len() * LevelIndex::LENGTH];
+
+        let dfd_byte_offset = output.len();
+        let dfd_byte_length = 4 + self.data_format_descriptors.len();
+        output.extend_from_slice(&(dfd_byte_length as u32).to_le_bytes());
+        output.extend_from_slice(&self.data_format_descriptors);
+
+        pad_to(&mut output, 4);
+        let kvd_byte_offset = output.len();
+        for (key, value) in &self.key_value_data {
+            let key_and_value_byte_length = key.len() + 1 + value.len();
+            output.extend_from_slice(&(key_and_value_byte_length as u32).to_le_bytes());
+            output.extend_from_slice(key.as_bytes());
+            output.push(0);
+            output.extend_from_slice(value);
+            pad_to(&mut output, 4);
+        }
+        let kvd_byte_length = output.len() - kvd_byte_offset;
+
+        let mut sgd_byte_offset = 0;
+        if !self.supercompression_global_data.is_empty() {
+            pad_to(&mut output, 8);
+            sgd_byte_offset = output.len();
+            output.extend_from_slice(&self.supercompression_global_data);
+        }
+
+        header.index = Index {
+            dfd_byte_offset: dfd_byte_offset as u32,
+            dfd_byte_length: dfd_byte_length as u32,
+            kvd_byte_offset: if kvd_byte_length == 0 {
+                0
+            } else {
+                kvd_byte_offset as u32
+            },
+            kvd_byte_length: kvd_byte_length as u32,
+            sgd_byte_offset: sgd_byte_offset as u64,
+            sgd_byte_length: self.supercompression_global_data.len() as u64,
+        };
+        output[..Header::LENGTH].copy_from_slice(&header.as_bytes());
+
+        // Levels are stored starting with the smallest one
+        let alignment = self.level_alignment();
+        for (level_index, level) in self.levels.iter().enumerate().rev() {
+            pad_to(&mut output, alignment);
+            let index = LevelIndex {
+                byte_offset: output.len() as u64,
+                byte_length: level.data.len() as u64,
+                uncompressed_byte_length: level.uncompressed_byte_length,
+            };
+            output.extend_from_slice(&level.data);
+
+            let start = level_index_start + level_index * LevelIndex::LENGTH;
+            output[start..start + LevelIndex::LENGTH].copy_from_slice(&index.as_bytes());
+        }
+
+        output
+    }
+
+    /// Bytes of the basic data format descriptor block, starting with its block header, wherever it is among the
+    /// descriptor blocks
+    pub(crate) fn basic_data_format_descriptor_mut(&mut self) -> Option<&mut [u8]> {
+        let mut offset = 0;
+        while let Some(bytes) = self
+            .data_format_descriptors
+            .get(offset..offset + DataFormatDescriptorHeader::LENGTH)
+        {
+            let (header, descriptor_block_size) = DataFormatDescriptorHeader::parse(bytes).ok()?;
+            if descriptor_block_size == 0 {
+                return None;
+            }
+            if header == DataFormatDescriptorHeader::BASIC {
+                return self
+                    .data_format_descriptors
+                    .get_mut(offset..offset + descriptor_block_size);
+            }
+            offset += descriptor_block_size;
+        }
+        None
+    }
+
+    /// Alignment of level data: 1 if supercompressed, otherwise the least common multiple of the texel block size
+    /// and 4.
+    fn level_alignment(&self) -> usize {
+        if self.header.supercompression_scheme.is_some() {
+            return 1;
+        }
+
+        let texel_block_size = crate::DataFormatDescriptorIterator {
+            data: &self.data_format_descriptors,
+        }
+        .find(|dfd| dfd.header == DataFormatDescriptorHeader::BASIC)
+        .and_then(|dfd| BasicDataFormatDescriptor::parse(dfd.data).ok())
+        .map_or(0, |dfd| dfd.header.bytes_planes[0] as usize);
+
+        match texel_block_size {
+            0 => 4,
+            size => size * 4 / gcd(size, 4),
+        }
+    }
+}
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// Rewrite the file with the layout of [`Writer::write`], copying level data as is
+    ///
+    /// Sections are packed in order with only the padding required by the specification, and levels are aligned to
+    /// their texel block size. Use [`Writer::from_reader`] to replace the data format descriptor or key-value data
+    /// before writing.
+    pub fn repack(&self) -> Vec<u8> {
+        Writer::from_reader(self).write()
+    }
+}
+
+fn pad_to(output: &mut Vec<u8>, alignment: usize) {
+    let padding = (alignment - output.len() % alignment) % alignment;
+    output.resize(output.len() + padding, 0);
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        let t = b;
+        b = a % b;
+        a = t;
+    }
+    a
+}
+
+#[test]
+fn test_write_round_trip() {
+    let file = include_bytes!("../data/test_tex.ktx2");
+    let reader = Reader::new(&file[..]).unwrap();
+
+    let writer = Writer::from_reader(&reader);
+
+    assert_eq!(writer.write(), &file[..]);
+}
+
+#[test]
+fn test_repack() {
+    let file = include_bytes!("../data/test_tex.ktx2");
+
+    // Bytes outside of the sections are dropped
+    let mut padded = file.to_vec();
+    padded.extend_from_slice(&[0; 13]);
+    assert_eq!(Reader::new(&padded[..]).unwrap().repack(), &file[..]);
+
+    let mut writer = Writer::from_reader(&Reader::new(&file[..]).unwrap());
+    writer.remove_writer_metadata();
+    let stripped = writer.write();
+    let stripped = Reader::new(&stripped[..]).unwrap();
+    assert_eq!(
+        stripped.key_value_data().map(|(key, _)| key).collect::<Vec<_>>(),
+        ["KTXorientation"]
+    );
+    let original = Reader::new(&file[..]).unwrap();
+    assert!(stripped.levels().zip(original.levels()).all(|(a, b)| a.data == b.data));
+}
+
+#[test]
+#[should_panic(expected = "key-value data keys")]
+fn test_write_invalid_key() {
+    let file = include_bytes!("../data/test_tex.ktx2");
+    let mut writer = Writer::from_reader(&Reader::new(&file[..]).unwrap());
+    writer
+        .key_value_data
+        .insert(String::from("KTX\0writer"), b"value\0".to_vec());
+    writer.write();
+}
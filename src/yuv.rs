@@ -0,0 +1,495 @@
+//! Planar Y′CbCr textures with 4:4:4, 4:2:2 and 4:2:0 chroma subsampling, described by their data format descriptor.
+//!
+//! These textures have no Vulkan format. The level data holds the full-resolution luma plane followed by either one
+//! plane of interleaved Cb and Cr samples or separate Cb and Cr planes, each with rows of `ceil(width / x)` samples,
+//! where `x` and `y` are the subsampling factors. The texel block of the descriptor is `x` by `y` texels, with the
+//! luma of each block row described as its own plane, so that the planes of a block cover the same bytes as the
+//! level data.
+//!
+//! Sample positions are in 1/256ths of the texel block. Samples of more than 8 bits are stored in the high bits of
+//! 16-bit values.
+
+use crate::{
+    BasicDataFormatDescriptor, BasicDataFormatDescriptorBuilder, BasicDataFormatDescriptorHeader,
+    ChannelTypeQualifiers, ColorModel, ColorPrimaries, DataFormatDescriptorHeader, DataFormatFlags, Header, Index,
+    Reader, SampleInformation, TextureError, TransferFunction, Writer, WriterLevel,
+};
+use std::{collections::BTreeMap, vec::Vec};
+
+/// Horizontal and vertical resolution of the chroma planes relative to the luma plane
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ChromaSubsampling {
+    /// Full-resolution chroma
+    Yuv444,
+    /// Chroma at half the horizontal resolution
+    Yuv422,
+    /// Chroma at half the horizontal and vertical resolution
+    Yuv420,
+}
+
+impl ChromaSubsampling {
+    /// Number of luma samples per chroma sample horizontally and vertically, which is the texel block size
+    pub fn factors(self) -> [u32; 2] {
+        match self {
+            Self::Yuv444 => [1, 1],
+            Self::Yuv422 => [2, 1],
+            Self::Yuv420 => [2, 2],
+        }
+    }
+}
+
+/// Layout of the chroma samples
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum YuvPlanes {
+    /// Interleaved Cb and Cr in a single plane, like NV12 and P010
+    Two,
+    /// Separate Cb and Cr planes, like I420
+    Three,
+}
+
+/// Position of chroma samples relative to the luma samples they cover, along one axis
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ChromaSiting {
+    /// Chroma samples are at the position of the first luma sample
+    Cosited,
+    /// Chroma samples are halfway between the luma samples
+    Midpoint,
+}
+
+/// Range of luma and chroma values
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum YuvRange {
+    /// Values use the full range of the samples
+    Full,
+    /// Luma uses 16 to 235 and chroma 16 to 240, scaled to the bit depth
+    Narrow,
+}
+
+/// Description of a planar Y′CbCr texture
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct YuvFormat {
+    pub subsampling: ChromaSubsampling,
+    pub planes: YuvPlanes,
+    /// Bits of each sample, from 1 to 16
+    pub bit_depth: u32,
+    pub range: YuvRange,
+    /// Horizontal and vertical chroma siting, which only matters for subsampled axes
+    pub chroma_siting: [ChromaSiting; 2],
+    /// Primaries of the RGB values, which also select the Y′CbCr conversion matrix
+    pub color_primaries: ColorPrimaries,
+    pub transfer_function: TransferFunction,
+}
+
+impl YuvFormat {
+    /// Recognize a descriptor written by [`YuvFormat::data_format_descriptor`] or following the same layout
+    ///
+    /// Unspecified primaries and transfer functions are treated as BT.709.
+    pub fn from_descriptor(descriptor: &BasicDataFormatDescriptor) -> Option<Self> {
+        let header = &descriptor.header;
+        if header.color_model != Some(ColorModel::YUVSDA) {
+            return None;
+        }
+        let subsampling = match header.texel_block_dimensions {
+            [1, 1, 1, 1] => ChromaSubsampling::Yuv444,
+            [2, 1, 1, 1] => ChromaSubsampling::Yuv422,
+            [2, 2, 1, 1] => ChromaSubsampling::Yuv420,
+            _ => return None,
+        };
+        let [x, y] = subsampling.factors();
+
+        let samples = descriptor.sample_information().collect::<Vec<_>>();
+        let luma = samples.iter().filter(|sample| sample.channel_type == 0).count();
+        let chroma = |channel_type| {
+            let mut matching = samples.iter().filter(|sample| sample.channel_type == channel_type);
+            matching.next().filter(|_| matching.next().is_none())
+        };
+        let (cb, _) = (chroma(1)?, chroma(2)?);
+        if luma != (x * y) as usize || samples.len() != luma + 2 {
+            return None;
+        }
+
+        let sample_bytes = header.bytes_planes[0] / x;
+        let bit_depth = samples[0].bit_length;
+        if !matches!(sample_bytes, 1 | 2) || bit_depth == 0 || bit_depth > sample_bytes * 8 {
+            return None;
+        }
+        let planes = match header.bytes_planes[y as usize..]
+            .iter()
+            .filter(|&&bytes| bytes != 0)
+            .count()
+        {
+            1 => YuvPlanes::Two,
+            2 => YuvPlanes::Three,
+            _ => return None,
+        };
+        let siting = |position| match position {
+            0 => ChromaSiting::Cosited,
+            _ => ChromaSiting::Midpoint,
+        };
+
+        Some(Self {
+            subsampling,
+            planes,
+            bit_depth,
+            range: if samples[0].lower == 0 {
+                YuvRange::Full
+            } else {
+                YuvRange::Narrow
+            },
+            chroma_siting: [siting(cb.sample_positions[0]), siting(cb.sample_positions[1])],
+            color_primaries: header.color_primaries.unwrap_or(ColorPrimaries::BT709),
+            transfer_function: header.transfer_function.unwrap_or(TransferFunction::ITU),
+        })
+    }
+
+    /// Data format descriptor blocks, excluding the leading total size field
+    ///
+    /// Returns `None` if the bit depth isn't in `1..=16`.
+    pub fn data_format_descriptor(&self) -> Option<Vec<u8>> {
+        if !(1..=16).contains(&self.bit_depth) {
+            return None;
+        }
+        let [x, y] = self.subsampling.factors();
+        let sample_bytes = self.sample_bytes();
+        let mut bytes_planes = [0; 8];
+        for plane in &mut bytes_planes[..y as usize] {
+            *plane = x * sample_bytes;
+        }
+        match self.planes {
+            YuvPlanes::Two => bytes_planes[y as usize] = 2 * sample_bytes,
+            YuvPlanes::Three => {
+                bytes_planes[y as usize] = sample_bytes;
+                bytes_planes[y as usize + 1] = sample_bytes;
+            }
+        }
+
+        let mut builder = BasicDataFormatDescriptorBuilder::new(BasicDataFormatDescriptorHeader {
+            color_model: Some(ColorModel::YUVSDA),
+            color_primaries: Some(self.color_primaries),
+            transfer_function: Some(self.transfer_function),
+            flags: DataFormatFlags::STRAIGHT_ALPHA,
+            texel_block_dimensions: [x, y, 1, 1],
+            bytes_planes,
+        });
+        let padding = sample_bytes * 8 - self.bit_depth;
+        let sample = |index: u32, channel_type, position: [u32; 2], (lower, upper)| SampleInformation {
+            bit_offset: index * sample_bytes * 8 + padding,
+            bit_length: self.bit_depth,
+            channel_type,
+            channel_type_qualifiers: ChannelTypeQualifiers::empty(),
+            sample_positions: [position[0], position[1], 0, 0],
+            lower,
+            upper,
+        };
+        for row in 0..y {
+            for column in 0..x {
+                let position = [column * 256 / x, row * 256 / y];
+                builder = builder.sample(sample(row * x + column, 0, position, self.bounds(false)));
+            }
+        }
+        let siting = |axis: usize, factor: u32| match self.chroma_siting[axis] {
+            ChromaSiting::Cosited => 0,
+            ChromaSiting::Midpoint => (factor - 1) * 128 / factor,
+        };
+        let position = [siting(0, x), siting(1, y)];
+        builder = builder
+            .sample(sample(x * y, 1, position, self.bounds(true)))
+            .sample(sample(x * y + 1, 2, position, self.bounds(true)));
+        Some(builder.as_bytes())
+    }
+
+    /// Size of the level data of an image, or `None` if it overflows
+    pub fn byte_length(&self, width: u32, height: u32) -> Option<usize> {
+        let [chroma_width, chroma_height] = self.chroma_extent(width, height);
+        let luma = (width as usize).checked_mul(height as usize)?;
+        let chroma = (chroma_width as usize)
+            .checked_mul(chroma_height as usize)?
+            .checked_mul(2)?;
+        luma.checked_add(chroma)?.checked_mul(self.sample_bytes() as usize)
+    }
+
+    fn sample_bytes(&self) -> u32 {
+        self.bit_depth.div_ceil(8)
+    }
+
+    fn chroma_extent(&self, width: u32, height: u32) -> [u32; 2] {
+        let [x, y] = self.subsampling.factors();
+        [width.div_ceil(x), height.div_ceil(y)]
+    }
+
+    /// Raw values of the lower and upper bounds of luma or chroma samples
+    fn bounds(&self, chroma: bool) -> (u32, u32) {
+        match self.range {
+            YuvRange::Full => (0, (1 << self.bit_depth) - 1),
+            YuvRange::Narrow => {
+                let scale = |value: u32| (value << self.bit_depth) >> 8;
+                (scale(16), scale(if chroma { 240 } else { 235 }))
+            }
+        }
+    }
+
+    /// Luma and blue and red difference weights of the Y′CbCr matrix
+    fn coefficients(&self) -> (f32, f32) {
+        match self.color_primaries {
+            ColorPrimaries::BT2020 => (0.2627, 0.0593),
+            ColorPrimaries::BT601EBU
+            | ColorPrimaries::BT601SMPTE
+            | ColorPrimaries::PAL525
+            | ColorPrimaries::NTSC1953 => (0.299, 0.114),
+            _ => (0.2126, 0.0722),
+        }
+    }
+
+    /// Byte offsets of the first Cb and Cr samples and the distance between consecutive chroma samples
+    ///
+    /// The dimensions must have a valid [`YuvFormat::byte_length`], so that the offsets can't overflow.
+    fn chroma_layout(&self, width: u32, height: u32) -> (usize, usize, usize) {
+        let sample_bytes = self.sample_bytes() as usize;
+        let luma = width as usize * height as usize * sample_bytes;
+        match self.planes {
+            YuvPlanes::Two => (luma, luma + sample_bytes, 2 * sample_bytes),
+            YuvPlanes::Three => {
+                let [chroma_width, chroma_height] = self.chroma_extent(width, height);
+                let plane = chroma_width as usize * chroma_height as usize * sample_bytes;
+                (luma, luma + plane, sample_bytes)
+            }
+        }
+    }
+}
+
+/// Convert Y′CbCr level data into RGBA texels, with alpha set to 1
+///
+/// RGB values use the transfer function of the format. Chroma is interpolated bilinearly according to its siting.
+/// Returns `None` if the bit depth isn't in `1..=16` or the length of `data` doesn't match the dimensions.
+pub fn decode(format: &YuvFormat, width: u32, height: u32, data: &[u8]) -> Option<Vec<[f32; 4]>> {
+    if !(1..=16).contains(&format.bit_depth) || Some(data.len()) != format.byte_length(width, height) {
+        return None;
+    }
+    let sample_bytes = format.sample_bytes() as usize;
+    let shift = format.sample_bytes() * 8 - format.bit_depth;
+    let read = |offset: usize| {
+        let raw = match sample_bytes {
+            1 => u32::from(data[offset]),
+            _ => u32::from(u16::from_le_bytes([data[offset], data[offset + 1]])),
+        };
+        raw >> shift
+    };
+
+    let (luma_lower, luma_upper) = format.bounds(false);
+    let (chroma_lower, chroma_upper) = format.bounds(true);
+    let chroma_center = (chroma_lower + chroma_upper).div_ceil(2);
+    let (cb_start, cr_start, stride) = format.chroma_layout(width, height);
+    let [chroma_width, chroma_height] = format.chroma_extent(width, height);
+    let factors = format.subsampling.factors();
+
+    // Position of a texel in the chroma plane along one axis, as the two samples to interpolate and the weight of
+    // the second one
+    let chroma_coordinate = |axis: usize, texel: u32, extent: u32| {
+        let factor = factors[axis] as f32;
+        let offset = match format.chroma_siting[axis] {
+            ChromaSiting::Cosited => 0.0,
+            ChromaSiting::Midpoint => (factor - 1.0) / 2.0,
+        };
+        let position = ((texel as f32 - offset) / factor).clamp(0.0, (extent - 1) as f32);
+        let first = position.floor() as u32;
+        (first, (first + 1).min(extent - 1), position - first as f32)
+    };
+
+    let (kr, kb) = format.coefficients();
+    let mut texels = Vec::with_capacity(width as usize * height as usize);
+    for y in 0..height {
+        let (top, bottom, vertical) = chroma_coordinate(1, y, chroma_height);
+        for x in 0..width {
+            let (left, right, horizontal) = chroma_coordinate(0, x, chroma_width);
+            let chroma = |start: usize| {
+                let sample = |column: u32, row: u32| {
+                    let index = row as usize * chroma_width as usize + column as usize;
+                    let raw = read(start + index * stride) as f32;
+                    (raw - chroma_center as f32) / (chroma_upper - chroma_lower) as f32
+                };
+                let top = sample(left, top) * (1.0 - horizontal) + sample(right, top) * horizontal;
+                let bottom = sample(left, bottom) * (1.0 - horizontal) + sample(right, bottom) * horizontal;
+                top * (1.0 - vertical) + bottom * vertical
+            };
+
+            let luma = read((y as usize * width as usize + x as usize) * sample_bytes) as f32;
+            let luma = (luma - luma_lower as f32) / (luma_upper - luma_lower) as f32;
+            let (cb, cr) = (chroma(cb_start), chroma(cr_start));
+            let red = luma + 2.0 * (1.0 - kr) * cr;
+            let blue = luma + 2.0 * (1.0 - kb) * cb;
+            let green = (luma - kr * red - kb * blue) / (1.0 - kr - kb);
+            texels.push([red, green, blue, 1.0]);
+        }
+    }
+    Some(texels)
+}
+
+/// Convert RGBA texels into Y′CbCr level data, ignoring alpha
+///
+/// RGB values use the transfer function of the format. Chroma is averaged over the texels of each texel block
+/// regardless of the siting. Returns `None` if the bit depth isn't in `1..=16` or the number of texels doesn't match
+/// the dimensions.
+pub fn encode(format: &YuvFormat, width: u32, height: u32, texels: &[[f32; 4]]) -> Option<Vec<u8>> {
+    if !(1..=16).contains(&format.bit_depth) || Some(texels.len()) != (width as usize).checked_mul(height as usize) {
+        return None;
+    }
+    let sample_bytes = format.sample_bytes() as usize;
+    let shift = format.sample_bytes() * 8 - format.bit_depth;
+    let max = (1u32 << format.bit_depth) - 1;
+    let mut data = std::vec![0; format.byte_length(width, height)?];
+    let mut write = |offset: usize, value: f32| {
+        let raw = (value.round().clamp(0.0, max as f32) as u32) << shift;
+        data[offset..offset + sample_bytes].copy_from_slice(&raw.to_le_bytes()[..sample_bytes]);
+    };
+
+    let (kr, kb) = format.coefficients();
+    let luma = |[red, green, blue, _]: [f32; 4]| kr * red + (1.0 - kr - kb) * green + kb * blue;
+    let (luma_lower, luma_upper) = format.bounds(false);
+    for (index, &texel) in texels.iter().enumerate() {
+        let value = luma_lower as f32 + luma(texel) * (luma_upper - luma_lower) as f32;
+        write(index * sample_bytes, value);
+    }
+
+    let (chroma_lower, chroma_upper) = format.bounds(true);
+    let chroma_center = (chroma_lower + chroma_upper).div_ceil(2) as f32;
+    let chroma_scale = (chroma_upper - chroma_lower) as f32;
+    let (cb_start, cr_start, stride) = format.chroma_layout(width, height);
+    let [chroma_width, chroma_height] = format.chroma_extent(width, height);
+    let [x_factor, y_factor] = format.subsampling.factors();
+    for row in 0..chroma_height {
+        for column in 0..chroma_width {
+            let (mut cb, mut cr, mut count) = (0.0, 0.0, 0.0);
+            for y in row * y_factor..(row + 1).saturating_mul(y_factor).min(height) {
+                for x in column * x_factor..(column + 1).saturating_mul(x_factor).min(width) {
+                    let texel = texels[y as usize * width as usize + x as usize];
+                    let luma = luma(texel);
+                    cb += (texel[2] - luma) / (2.0 * (1.0 - kb));
+                    cr += (texel[0] - luma) / (2.0 * (1.0 - kr));
+                    count += 1.0;
+                }
+            }
+            let offset = (row as usize * chroma_width as usize + column as usize) * stride;
+            write(cb_start + offset, chroma_center + cb / count * chroma_scale);
+            write(cr_start + offset, chroma_center + cr / count * chroma_scale);
+        }
+    }
+    Some(data)
+}
+
+impl Writer {
+    /// Create a single-level Y′CbCr texture from level data laid out as described in the [module](crate::yuv)
+    /// documentation
+    pub fn yuv(format: &YuvFormat, width: u32, height: u32, data: &[u8]) -> Result<Self, TextureError> {
+        let data_format_descriptors = format.data_format_descriptor().ok_or(TextureError::UnsupportedFormat)?;
+        if Some(data.len()) != format.byte_length(width, height) {
+            return Err(TextureError::InvalidLength);
+        }
+        Ok(Self {
+            header: Header {
+                format: None,
+                // The type size of formats without a vkFormat is always 1
+                type_size: 1,
+                pixel_width: width,
+                pixel_height: height,
+                pixel_depth: 0,
+                layer_count: 0,
+                face_count: 1,
+                level_count: 1,
+                supercompression_scheme: None,
+                index: Index::default(),
+            },
+            data_format_descriptors,
+            key_value_data: BTreeMap::new(),
+            supercompression_global_data: Vec::new(),
+            levels: std::vec![WriterLevel::new(data.to_vec())],
+        })
+    }
+}
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// Y′CbCr layout of the texture, if its basic data format descriptor describes one
+    pub fn yuv_format(&self) -> Option<YuvFormat> {
+        let dfd = self
+            .data_format_descriptors()
+            .find(|dfd| dfd.header == DataFormatDescriptorHeader::BASIC)?;
+        YuvFormat::from_descriptor(&BasicDataFormatDescriptor::parse(dfd.data).ok()?)
+    }
+}
+
+#[test]
+fn test_yuv_round_trip() {
+    let formats = [
+        YuvFormat {
+            subsampling: ChromaSubsampling::Yuv420,
+            planes: YuvPlanes::Three,
+            bit_depth: 8,
+            range: YuvRange::Narrow,
+            chroma_siting: [ChromaSiting::Cosited, ChromaSiting::Midpoint],
+            color_primaries: ColorPrimaries::BT709,
+            transfer_function: TransferFunction::ITU,
+        },
+        YuvFormat {
+            subsampling: ChromaSubsampling::Yuv422,
+            planes: YuvPlanes::Two,
+            bit_depth: 10,
+            range: YuvRange::Full,
+            chroma_siting: [ChromaSiting::Midpoint, ChromaSiting::Cosited],
+            color_primaries: ColorPrimaries::BT2020,
+            transfer_function: TransferFunction::PQEOTF,
+        },
+    ];
+
+    // Gray texels have no chroma, and a single color is unaffected by subsampling
+    let (width, height) = (5, 3);
+    let gray = (0..width * height)
+        .map(|index| {
+            let value = index as f32 / (width * height) as f32;
+            [value, value, value, 1.0]
+        })
+        .collect::<Vec<_>>();
+    let color = std::vec![[0.8, 0.2, 0.1, 1.0]; (width * height) as usize];
+
+    for format in formats {
+        for texels in [&gray, &color] {
+            let data = encode(&format, width, height, texels).unwrap();
+            let file = Writer::yuv(&format, width, height, &data).unwrap().write();
+            let reader = Reader::new(&file).unwrap();
+            assert_eq!(reader.yuv_format(), Some(format));
+
+            let decoded = decode(&format, width, height, reader.levels().next().unwrap().data).unwrap();
+            for (decoded, expected) in decoded.iter().zip(texels) {
+                assert!(
+                    decoded.iter().zip(expected).all(|(a, b)| (a - b).abs() < 0.01),
+                    "{:?} {:?}",
+                    decoded,
+                    expected
+                );
+            }
+        }
+    }
+
+    for bit_depth in [0, 17, 32] {
+        let format = YuvFormat {
+            bit_depth,
+            ..formats[0]
+        };
+        assert_eq!(encode(&format, width, height, &gray), None);
+        assert_eq!(decode(&format, width, height, &[0; 64]), None);
+        assert_eq!(format.data_format_descriptor(), None);
+        assert!(matches!(
+            Writer::yuv(&format, width, height, &[0; 64]),
+            Err(TextureError::UnsupportedFormat)
+        ));
+    }
+
+    // 16-bit samples are still written with a type size of 1
+    let data = encode(&formats[1], width, height, &gray).unwrap();
+    assert_eq!(
+        Writer::yuv(&formats[1], width, height, &data).unwrap().header.type_size,
+        1
+    );
+
+    assert_eq!(formats[0].byte_length(u32::MAX, u32::MAX), None);
+    assert_eq!(encode(&formats[0], u32::MAX, u32::MAX, &gray), None);
+    assert_eq!(decode(&formats[0], u32::MAX, u32::MAX, &[0; 64]), None);
+}